@@ -0,0 +1,373 @@
+use crate::output::{self, OutputFormat};
+use crate::rpc::RpcClient;
+use serde::Serialize;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+use solana_sdk::{system_instruction, system_program, sysvar};
+use std::fs::File;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use vesting_schedule::utils::utils::EventStatus;
+use vesting_schedule::{VestingData, VestingScheduleBuf, VestingUser};
+use vesting_schedule_client::import::{self, GrantSpec, ReconciliationReport};
+use vesting_schedule_client::projection;
+use vesting_schedule_client::{accounts, instructions};
+
+// Grants added per transaction, keeps add-users well below the packet size
+const ADD_USERS_PER_TRANSACTION: usize = 5;
+const ASSOCIATED_TOKEN_PROGRAM_ID: &str = "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL";
+
+pub struct Config {
+    pub rpc: RpcClient,
+    pub payer: Keypair,
+    pub output: OutputFormat,
+}
+
+#[derive(Serialize)]
+struct Signatures {
+    signatures: Vec<String>,
+}
+
+fn send(config: &Config, instructions: &[Instruction], extra_signers: &[&Keypair]) -> Result<String, String> {
+    let mut signers: Vec<&dyn Signer> = vec![&config.payer];
+    signers.extend(extra_signers.iter().map(|signer| *signer as &dyn Signer));
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&config.payer.pubkey()),
+        &signers,
+        config.rpc.get_recent_blockhash()?,
+    );
+    config
+        .rpc
+        .send_and_confirm_transaction(&transaction)
+        .map(|signature| signature.to_string())
+}
+
+fn print_signatures(config: &Config, signatures: Vec<String>) {
+    output::print(config.output, &Signatures { signatures }, |value| {
+        value.signatures.iter().map(|signature| format!("Signature: {}\n", signature)).collect()
+    });
+}
+
+pub fn fetch_vesting_data(rpc: &RpcClient, vesting_data: &Pubkey) -> Result<VestingData, String> {
+    let data = rpc
+        .get_account_data(vesting_data)?
+        .ok_or_else(|| format!("vesting data {} does not exist", vesting_data))?;
+    accounts::decode_vesting_data(&data).map_err(|err| format!("vesting data {}: {}", vesting_data, err))
+}
+
+pub fn fetch_vesting_schedule(rpc: &RpcClient, vesting_schedule: &Pubkey) -> Result<VestingScheduleBuf, String> {
+    let data = rpc
+        .get_account_data(vesting_schedule)?
+        .ok_or_else(|| format!("vesting schedule {} does not exist", vesting_schedule))?;
+    accounts::decode_vesting_schedule(&data).map_err(|err| format!("vesting schedule {}: {}", vesting_schedule, err))
+}
+
+pub fn now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64
+}
+
+#[derive(Serialize)]
+struct Initialized {
+    vesting_data: String,
+    vesting_schedule: String,
+    signature: String,
+}
+
+pub fn initialize(config: &Config, vesting_vault: &Pubkey, tge_timestamp: u64) -> Result<(), String> {
+    let vesting_data = Keypair::new();
+    let vesting_schedule = Keypair::new();
+    let space = instructions::vesting_schedule_space();
+    let lamports = config.rpc.get_minimum_balance_for_rent_exemption(space)?;
+
+    let signature = send(
+        config,
+        &[
+            system_instruction::create_account(
+                &config.payer.pubkey(),
+                &vesting_schedule.pubkey(),
+                lamports,
+                space as u64,
+                &vesting_schedule::ID,
+            ),
+            instructions::initialize(
+                &config.payer.pubkey(),
+                &vesting_data.pubkey(),
+                &vesting_schedule.pubkey(),
+                vesting_vault,
+                tge_timestamp,
+            ),
+        ],
+        &[&vesting_data, &vesting_schedule],
+    )?;
+
+    let initialized = Initialized {
+        vesting_data: vesting_data.pubkey().to_string(),
+        vesting_schedule: vesting_schedule.pubkey().to_string(),
+        signature,
+    };
+    output::print(config.output, &initialized, |value| {
+        format!(
+            "Vesting data:     {}\nVesting schedule: {}\nSignature:        {}\n",
+            value.vesting_data, value.vesting_schedule, value.signature
+        )
+    });
+    Ok(())
+}
+
+pub enum Sheet<'a> {
+    Csv(&'a str),
+    Json(&'a str),
+}
+
+fn read_sheet(sheet: &Sheet) -> Result<(Vec<GrantSpec>, Vec<String>), String> {
+    let (path, parse): (_, fn(File) -> _) = match sheet {
+        Sheet::Csv(path) => (path, |file| import::parse_csv(file)),
+        Sheet::Json(path) => (path, |file| import::parse_json(file)),
+    };
+    let file = File::open(path).map_err(|err| format!("{}: {}", path, err))?;
+    let (specs, issues) = parse(file);
+    Ok((specs, issues.iter().map(|issue| issue.to_string()).collect()))
+}
+
+#[derive(Serialize)]
+struct AddUsersReport {
+    dry_run: bool,
+    grants: usize,
+    errors: Vec<String>,
+    signatures: Vec<String>,
+    reconciliation: Option<ReconciliationReport>,
+}
+
+// The cli keypair signs the admin instructions, as it did initialize
+fn check_admin(config: &Config, vesting_data: &VestingData) -> Result<(), String> {
+    if vesting_data.admin != config.payer.pubkey() {
+        return Err(format!("the admin of the schedule is {}, not the cli keypair", vesting_data.admin));
+    }
+    Ok(())
+}
+
+pub fn add_users(config: &Config, vesting_data: &Pubkey, sheet: &Sheet, dry_run: bool) -> Result<(), String> {
+    let vesting_data_account = fetch_vesting_data(&config.rpc, vesting_data)?;
+    let vesting_schedule = vesting_data_account.vesting_schedule;
+    let schedule = fetch_vesting_schedule(&config.rpc, &vesting_schedule)?;
+    let (specs, mut errors) = read_sheet(sheet)?;
+    errors.extend(import::validate(&specs, &schedule.borrow()).iter().map(|issue| issue.to_string()));
+
+    let mut report = AddUsersReport {
+        dry_run,
+        grants: specs.len(),
+        errors,
+        signatures: Vec::new(),
+        reconciliation: None,
+    };
+    if report.errors.is_empty() && !dry_run {
+        check_admin(config, &vesting_data_account)?;
+        for chunk in specs.chunks(ADD_USERS_PER_TRANSACTION) {
+            let add_user_instructions: Vec<Instruction> = chunk
+                .iter()
+                .map(|spec| {
+                    instructions::add_user(
+                        &config.payer.pubkey(),
+                        vesting_data,
+                        &vesting_schedule,
+                        &spec.beneficiary,
+                        spec.unlocked_at_tge,
+                        spec.unlocking_period,
+                        spec.planned_tokens,
+                    )
+                })
+                .collect();
+            report.signatures.push(send(config, &add_user_instructions, &[])?);
+        }
+        // the sheet only covers the new grants, slots that were active before are left out
+        let mut schedule_after = fetch_vesting_schedule(&config.rpc, &vesting_schedule)?;
+        for (before, after) in schedule.borrow().data.iter().zip(schedule_after.borrow_mut().data.iter_mut()) {
+            if before.status == EventStatus::PendingToken as u8 {
+                after.status = EventStatus::Inactive as u8;
+            }
+        }
+        report.reconciliation = Some(import::reconcile(&specs, &schedule_after.borrow()));
+    }
+
+    output::print(config.output, &report, |report| {
+        let mut out: String = report.errors.iter().map(|error| format!("error: {}\n", error)).collect();
+        out += &match (report.errors.is_empty(), report.dry_run) {
+            (false, _) => format!("{} errors, nothing was sent\n", report.errors.len()),
+            (true, true) => format!("{} grants are valid, dry run so nothing was sent\n", report.grants),
+            (true, false) => format!("Added {} grants\n", report.grants),
+        };
+        out += &report.signatures.iter().map(|signature| format!("Signature: {}\n", signature)).collect::<String>();
+        if let Some(reconciliation) = &report.reconciliation {
+            out += "\n";
+            out += &output::display_reconciliation(reconciliation);
+        }
+        out
+    });
+    if !report.errors.is_empty() {
+        Err("grant sheet is invalid".to_string())
+    } else if report.reconciliation.is_some_and(|reconciliation| !reconciliation.is_clean()) {
+        Err("schedule does not match the grant sheet".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+// Compares a sheet with every active grant of the schedule, exits with an error on any difference
+pub fn reconcile(config: &Config, vesting_data: &Pubkey, sheet: &Sheet) -> Result<(), String> {
+    let vesting_schedule = fetch_vesting_data(&config.rpc, vesting_data)?.vesting_schedule;
+    let schedule = fetch_vesting_schedule(&config.rpc, &vesting_schedule)?;
+    let (specs, errors) = read_sheet(sheet)?;
+    if !errors.is_empty() {
+        errors.iter().for_each(|error| eprintln!("error: {}", error));
+        return Err("grant sheet is invalid".to_string());
+    }
+
+    let report = import::reconcile(&specs, &schedule.borrow());
+    output::print(config.output, &report, output::display_reconciliation);
+    if report.is_clean() {
+        Ok(())
+    } else {
+        Err("schedule does not match the grant sheet".to_string())
+    }
+}
+
+pub enum GrantSource<'a> {
+    OnChain(Pubkey),
+    Sheet(Sheet<'a>, u64),
+}
+
+// Unlock calendar of a schedule or of a sheet that is not on-chain yet, needs no keypair.
+// Display output is CSV.
+pub fn project(
+    rpc: &RpcClient,
+    format: OutputFormat,
+    source: &GrantSource,
+    from: i64,
+    to: i64,
+    step: i64,
+    circulating_supply: u64,
+) -> Result<(), String> {
+    let (vesting_data, grants): (VestingData, Vec<(String, VestingUser)>) = match source {
+        GrantSource::OnChain(vesting_data_key) => {
+            let vesting_data = fetch_vesting_data(rpc, vesting_data_key)?;
+            let schedule = fetch_vesting_schedule(rpc, &vesting_data.vesting_schedule)?;
+            let grants = schedule
+                .borrow()
+                .data
+                .iter()
+                .enumerate()
+                .filter(|(_, grant)| grant.status == EventStatus::PendingToken as u8)
+                .map(|(slot, grant)| (format!("slot {} {}", slot, grant.user), *grant))
+                .collect();
+            (vesting_data, grants)
+        }
+        GrantSource::Sheet(sheet, tge_timestamp) => {
+            let (specs, errors) = read_sheet(sheet)?;
+            if !errors.is_empty() {
+                errors.iter().for_each(|error| eprintln!("error: {}", error));
+                return Err("grant sheet is invalid".to_string());
+            }
+            let vesting_data = VestingData {
+                tge_timestamp: *tge_timestamp,
+                ..Default::default()
+            };
+            let grants = specs
+                .iter()
+                .map(|spec| (format!("row {} {}", spec.row, spec.beneficiary), spec.to_vesting_user()))
+                .collect();
+            (vesting_data, grants)
+        }
+    };
+
+    let projection = projection::project(&vesting_data, &grants, from, to, step, circulating_supply)
+        .map_err(|err| format!("cannot project the schedule: {:?}", err))?;
+    output::print(format, &projection, projection::to_csv);
+    Ok(())
+}
+
+pub fn remove_user(config: &Config, vesting_data: &Pubkey, beneficiary: &Pubkey, index: Option<u64>) -> Result<(), String> {
+    let vesting_data_account = fetch_vesting_data(&config.rpc, vesting_data)?;
+    check_admin(config, &vesting_data_account)?;
+    let vesting_schedule = vesting_data_account.vesting_schedule;
+    let remove_user = instructions::remove_user(&config.payer.pubkey(), vesting_data, &vesting_schedule, beneficiary, index);
+    let signature = send(config, &[remove_user], &[])?;
+    print_signatures(config, vec![signature]);
+    Ok(())
+}
+
+pub fn show(config: &Config, vesting_data_key: &Pubkey, timestamp: Option<i64>) -> Result<(), String> {
+    let vesting_data = fetch_vesting_data(&config.rpc, vesting_data_key)?;
+    let schedule = fetch_vesting_schedule(&config.rpc, &vesting_data.vesting_schedule)?;
+    let view = output::schedule_view(vesting_data_key, &vesting_data, &schedule.borrow(), timestamp.unwrap_or_else(now));
+    output::print(config.output, &view, output::display_schedule);
+    Ok(())
+}
+
+fn associated_token_address(wallet: &Pubkey, mint: &Pubkey) -> Pubkey {
+    let program_id = Pubkey::from_str(ASSOCIATED_TOKEN_PROGRAM_ID).unwrap();
+    Pubkey::find_program_address(&[wallet.as_ref(), anchor_spl::token::ID.as_ref(), mint.as_ref()], &program_id).0
+}
+
+fn create_associated_token_account(payer: &Pubkey, wallet: &Pubkey, mint: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: Pubkey::from_str(ASSOCIATED_TOKEN_PROGRAM_ID).unwrap(),
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(associated_token_address(wallet, mint), false),
+            AccountMeta::new_readonly(*wallet, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(anchor_spl::token::ID, false),
+            AccountMeta::new_readonly(sysvar::rent::ID, false),
+        ],
+        data: vec![],
+    }
+}
+
+// Claims for the cli keypair, creating its token account for the vested mint if needed.
+// The program finds the grant, `index` picks one when the keypair holds several.
+pub fn claim(config: &Config, vesting_data_key: &Pubkey, index: Option<u64>) -> Result<(), String> {
+    let vesting_data = fetch_vesting_data(&config.rpc, vesting_data_key)?;
+    let beneficiary = config.payer.pubkey();
+
+    let vault = config
+        .rpc
+        .get_account_data(&vesting_data.vesting_vault)?
+        .ok_or_else(|| format!("vesting vault {} does not exist", vesting_data.vesting_vault))?;
+    let mint = Pubkey::new(&vault[..32]);
+    let beneficiary_ata = associated_token_address(&beneficiary, &mint);
+
+    let mut claim_instructions = Vec::new();
+    if config.rpc.get_account_data(&beneficiary_ata)?.is_none() {
+        claim_instructions.push(create_associated_token_account(&beneficiary, &beneficiary, &mint));
+    }
+    // a grant with a receipt is claimed with the keypair's receipt account
+    let schedule = fetch_vesting_schedule(&config.rpc, &vesting_data.vesting_schedule)?;
+    let receipt_mint = match index {
+        Some(index) => schedule.borrow().data.get(index as usize).map(|grant| grant.receipt_mint),
+        None => schedule
+            .borrow()
+            .data
+            .iter()
+            .find(|grant| grant.status == EventStatus::PendingToken as u8 && grant.user == beneficiary)
+            .map(|grant| grant.receipt_mint),
+    }
+    .filter(|receipt_mint| *receipt_mint != Pubkey::default());
+    let receipt_account = receipt_mint.map(|receipt_mint| associated_token_address(&beneficiary, &receipt_mint));
+    claim_instructions.push(instructions::claim(
+        &vesting_data.admin,
+        vesting_data_key,
+        &vesting_data.vesting_schedule,
+        &vesting_data.vesting_vault,
+        &beneficiary,
+        &beneficiary_ata,
+        index,
+        receipt_account.as_ref(),
+    ));
+    let signature = send(config, &claim_instructions, &[])?;
+    print_signatures(config, vec![signature]);
+    Ok(())
+}
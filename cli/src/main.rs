@@ -0,0 +1,297 @@
+mod commands;
+mod output;
+mod rpc;
+
+use clap::{crate_description, crate_version, App, AppSettings, Arg, ArgGroup, ArgMatches, SubCommand};
+use commands::{Config, GrantSource, Sheet};
+use output::OutputFormat;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::read_keypair_file;
+use std::process::exit;
+use std::str::FromStr;
+use vesting_schedule_client::projection;
+
+fn vesting_data_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("vesting_data")
+        .long("vesting-data")
+        .value_name("PUBKEY")
+        .takes_value(true)
+        .required(true)
+        .help("VestingData account of the schedule")
+}
+
+fn index_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("index")
+        .long("index")
+        .takes_value(true)
+        .help("Slot of the grant, only needed when the beneficiary holds several")
+}
+
+fn sheet_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.arg(
+        Arg::with_name("csv")
+            .long("csv")
+            .value_name("FILE")
+            .takes_value(true)
+            .help("Sheet with the header beneficiary,unlocked_at_tge,unlocking_period,planned_tokens"),
+    )
+    .arg(
+        Arg::with_name("json")
+            .long("json")
+            .value_name("FILE")
+            .takes_value(true)
+            .help("Array of objects with the same keys as the CSV header"),
+    )
+    .group(ArgGroup::with_name("sheet").args(&["csv", "json"]).required(true))
+}
+
+fn sheet_of<'a>(matches: &'a ArgMatches) -> Sheet<'a> {
+    match matches.value_of("csv") {
+        Some(path) => Sheet::Csv(path),
+        None => Sheet::Json(matches.value_of("json").unwrap()),
+    }
+}
+
+fn pubkey_of(matches: &ArgMatches, name: &str) -> Result<Pubkey, String> {
+    let value = matches.value_of(name).unwrap();
+    Pubkey::from_str(value).map_err(|_| format!("invalid pubkey for --{}: {}", name.replace('_', "-"), value))
+}
+
+fn parse_of<T: FromStr>(matches: &ArgMatches, name: &str) -> Result<T, String> {
+    let value = matches.value_of(name).unwrap();
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for {}: {}", name, value))
+}
+
+fn step_of(matches: &ArgMatches) -> Result<i64, String> {
+    match matches.value_of("step").unwrap() {
+        "day" => Ok(projection::DAY),
+        "week" => Ok(projection::WEEK),
+        "month" => Ok(projection::MONTH),
+        _ => parse_of(matches, "step"),
+    }
+}
+
+fn run(matches: &ArgMatches) -> Result<(), String> {
+    let rpc = rpc::RpcClient::new(matches.value_of("url").unwrap());
+    let output = match matches.value_of("output") {
+        Some("json") => OutputFormat::Json,
+        _ => OutputFormat::Display,
+    };
+    if let ("project", Some(m)) = matches.subcommand() {
+        let source = match m.value_of("vesting_data") {
+            Some(_) => GrantSource::OnChain(pubkey_of(m, "vesting_data")?),
+            None => GrantSource::Sheet(sheet_of(m), parse_of(m, "tge_timestamp")?),
+        };
+        return commands::project(
+            &rpc,
+            output,
+            &source,
+            m.value_of("from").map(|_| parse_of(m, "from")).transpose()?.unwrap_or_else(commands::now),
+            parse_of(m, "to")?,
+            step_of(m)?,
+            parse_of(m, "circulating_supply")?,
+        );
+    }
+
+    let keypair_path = matches.value_of("keypair").unwrap();
+    let config = Config {
+        rpc,
+        payer: read_keypair_file(keypair_path).map_err(|err| format!("{}: {}", keypair_path, err))?,
+        output,
+    };
+
+    match matches.subcommand() {
+        ("init", Some(m)) => commands::initialize(&config, &pubkey_of(m, "vesting_vault")?, parse_of(m, "tge_timestamp")?),
+        ("add-users", Some(m)) => commands::add_users(
+            &config,
+            &pubkey_of(m, "vesting_data")?,
+            &sheet_of(m),
+            m.is_present("dry_run"),
+        ),
+        ("reconcile", Some(m)) => commands::reconcile(&config, &pubkey_of(m, "vesting_data")?, &sheet_of(m)),
+        ("remove-user", Some(m)) => commands::remove_user(
+            &config,
+            &pubkey_of(m, "vesting_data")?,
+            &pubkey_of(m, "beneficiary")?,
+            m.value_of("index").map(|_| parse_of(m, "index")).transpose()?,
+        ),
+        ("show", Some(m)) => commands::show(
+            &config,
+            &pubkey_of(m, "vesting_data")?,
+            m.value_of("at").map(|_| parse_of(m, "at")).transpose()?,
+        ),
+        ("claim", Some(m)) => commands::claim(
+            &config,
+            &pubkey_of(m, "vesting_data")?,
+            m.value_of("index").map(|_| parse_of(m, "index")).transpose()?,
+        ),
+        _ => unreachable!(),
+    }
+}
+
+fn main() {
+    let default_keypair = std::env::var("HOME")
+        .map(|home| format!("{}/.config/solana/id.json", home))
+        .unwrap_or_default();
+    let matches = App::new("vesting-cli")
+        .about(crate_description!())
+        .version(crate_version!())
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(
+            Arg::with_name("url")
+                .long("url")
+                .short("u")
+                .global(true)
+                .takes_value(true)
+                .default_value("http://localhost:8899")
+                .help("JSON RPC URL of the cluster"),
+        )
+        .arg(
+            Arg::with_name("keypair")
+                .long("keypair")
+                .short("k")
+                .global(true)
+                .takes_value(true)
+                .default_value(&default_keypair)
+                .help("Fee payer, admin for init/add-users/remove-user and beneficiary for claim"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .global(true)
+                .takes_value(true)
+                .possible_values(&["display", "json"])
+                .default_value("display"),
+        )
+        .subcommand(
+            SubCommand::with_name("init")
+                .about("Create and initialize a new schedule")
+                .arg(
+                    Arg::with_name("vesting_vault")
+                        .long("vesting-vault")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Token account holding the vested tokens, owned by the keypair"),
+                )
+                .arg(
+                    Arg::with_name("tge_timestamp")
+                        .long("tge-timestamp")
+                        .value_name("UNIX_TIMESTAMP")
+                        .takes_value(true)
+                        .required(true),
+                ),
+        )
+        .subcommand(sheet_args(
+            SubCommand::with_name("add-users")
+                .about("Add the grants of a CSV or JSON sheet and reconcile the schedule against it")
+                .arg(vesting_data_arg())
+                .arg(
+                    Arg::with_name("dry_run")
+                        .long("dry-run")
+                        .help("Only validate the sheet against the schedule"),
+                ),
+        ))
+        .subcommand(sheet_args(
+            SubCommand::with_name("reconcile")
+                .about("Compare a CSV or JSON sheet with the active grants, slot by slot")
+                .arg(vesting_data_arg()),
+        ))
+        .subcommand(
+            SubCommand::with_name("remove-user")
+                .about("Deactivate a grant of a beneficiary")
+                .arg(vesting_data_arg())
+                .arg(
+                    Arg::with_name("beneficiary")
+                        .long("beneficiary")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(index_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("show")
+                .about("Show the schedule with vested, claimed and claimable amounts")
+                .arg(vesting_data_arg())
+                .arg(
+                    Arg::with_name("at")
+                        .long("at")
+                        .value_name("UNIX_TIMESTAMP")
+                        .takes_value(true)
+                        .help("Compute the amounts at this time instead of now"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("claim")
+                .about("Claim everything claimable for the keypair")
+                .arg(vesting_data_arg())
+                .arg(index_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("project")
+                .about("Unlock calendar per grant, per cohort and in total, as CSV or with --output json")
+                .arg(vesting_data_arg().required(false))
+                .arg(
+                    Arg::with_name("csv")
+                        .long("csv")
+                        .value_name("FILE")
+                        .takes_value(true)
+                        .requires("tge_timestamp")
+                        .help("Project a grant sheet instead of an on-chain schedule"),
+                )
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .value_name("FILE")
+                        .takes_value(true)
+                        .requires("tge_timestamp")
+                        .help("Project a JSON grant sheet instead of an on-chain schedule"),
+                )
+                .group(ArgGroup::with_name("source").args(&["vesting_data", "csv", "json"]).required(true))
+                .arg(
+                    Arg::with_name("tge_timestamp")
+                        .long("tge-timestamp")
+                        .value_name("UNIX_TIMESTAMP")
+                        .takes_value(true)
+                        .help("TGE of the sheet"),
+                )
+                .arg(
+                    Arg::with_name("from")
+                        .long("from")
+                        .value_name("UNIX_TIMESTAMP")
+                        .takes_value(true)
+                        .help("Start of the calendar, defaults to now"),
+                )
+                .arg(
+                    Arg::with_name("to")
+                        .long("to")
+                        .value_name("UNIX_TIMESTAMP")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("step")
+                        .long("step")
+                        .value_name("day|week|month|SECONDS")
+                        .takes_value(true)
+                        .default_value("week"),
+                )
+                .arg(
+                    Arg::with_name("circulating_supply")
+                        .long("circulating-supply")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .default_value("0")
+                        .help("Circulating supply at --from that does not come from the schedule"),
+                ),
+        )
+        .get_matches();
+
+    if let Err(err) = run(&matches) {
+        eprintln!("error: {}", err);
+        exit(1);
+    }
+}
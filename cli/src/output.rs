@@ -0,0 +1,209 @@
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+use vesting_schedule::utils::utils::EventStatus;
+use vesting_schedule::{VestingData, VestingScheduleRef};
+use vesting_schedule_client::import::{Reconciliation, ReconciliationReport};
+use vesting_schedule_client::vested_amount_at;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Display,
+    Json,
+}
+
+#[derive(Serialize)]
+pub struct GrantView {
+    pub index: usize,
+    pub beneficiary: String,
+    pub status: &'static str,
+    pub unlocked_at_tge: u8,
+    pub unlocking_period: u8,
+    pub planned_tokens: u64,
+    pub vested_tokens: u64,
+    pub claimed_tokens: u64,
+    pub claimable_tokens: u64,
+}
+
+#[derive(Serialize)]
+pub struct ScheduleView {
+    pub vesting_data: String,
+    pub vesting_schedule: String,
+    pub admin: String,
+    pub tge_timestamp: u64,
+    pub timestamp: i64,
+    pub paused: bool,
+    pub total_issued_so_far: u64,
+    pub total_reclaimed: u64,
+    pub len: u64,
+    pub capacity: u64,
+    pub grants: Vec<GrantView>,
+}
+
+pub fn schedule_view(
+    vesting_data_key: &Pubkey,
+    vesting_data: &VestingData,
+    schedule: &VestingScheduleRef,
+    timestamp: i64,
+) -> ScheduleView {
+    let grants = schedule
+        .data
+        .iter()
+        .enumerate()
+        .filter(|(_, grant)| grant.status != EventStatus::Inactive as u8)
+        .map(|(index, grant)| {
+            let vested_tokens = vested_amount_at(vesting_data, grant, timestamp).unwrap_or(0);
+            let status = if grant.status == EventStatus::Expired as u8 {
+                "expired"
+            } else if grant.frozen != 0 {
+                "frozen"
+            } else if grant.suspended != 0 {
+                "suspended"
+            } else {
+                "active"
+            };
+            GrantView {
+                index,
+                beneficiary: grant.user.to_string(),
+                status,
+                unlocked_at_tge: grant.unlocked_at_tge,
+                unlocking_period: grant.unlocking_period,
+                planned_tokens: grant.planned_tokens,
+                vested_tokens,
+                claimed_tokens: grant.claimed_tokens,
+                claimable_tokens: if status == "expired" {
+                    0
+                } else {
+                    vested_tokens.saturating_sub(grant.claimed_tokens)
+                },
+            }
+        })
+        .collect();
+
+    ScheduleView {
+        vesting_data: vesting_data_key.to_string(),
+        vesting_schedule: vesting_data.vesting_schedule.to_string(),
+        admin: vesting_data.admin.to_string(),
+        tge_timestamp: vesting_data.tge_timestamp,
+        timestamp,
+        paused: vesting_data.paused,
+        total_issued_so_far: vesting_data.total_issued_so_far,
+        total_reclaimed: vesting_data.total_reclaimed,
+        len: schedule.len,
+        capacity: schedule.capacity,
+        grants,
+    }
+}
+
+pub fn print<T: Serialize>(format: OutputFormat, value: &T, display: impl FnOnce(&T) -> String) {
+    match format {
+        OutputFormat::Display => print!("{}", display(value)),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(value).unwrap()),
+    }
+}
+
+pub fn display_schedule(view: &ScheduleView) -> String {
+    let mut out = String::new();
+    out += &format!("Vesting data:        {}\n", view.vesting_data);
+    out += &format!("Vesting schedule:    {}\n", view.vesting_schedule);
+    out += &format!("Admin:               {}\n", view.admin);
+    out += &format!("TGE timestamp:       {}\n", view.tge_timestamp);
+    out += &format!("Paused:              {}\n", view.paused);
+    out += &format!("Total issued so far: {}\n", view.total_issued_so_far);
+    out += &format!("Total reclaimed:     {}\n", view.total_reclaimed);
+    out += &format!("Active grants:       {} of {}\n\n", view.len, view.capacity);
+    out += &format!(
+        "{:>5}  {:<44}  {:<9}  {:>4}  {:>6}  {:>20}  {:>20}  {:>20}  {:>20}\n",
+        "Index", "Beneficiary", "Status", "TGE%", "Months", "Planned", "Vested", "Claimed", "Claimable"
+    );
+    for grant in &view.grants {
+        out += &format!(
+            "{:>5}  {:<44}  {:<9}  {:>4}  {:>6}  {:>20}  {:>20}  {:>20}  {:>20}\n",
+            grant.index,
+            grant.beneficiary,
+            grant.status,
+            grant.unlocked_at_tge,
+            grant.unlocking_period,
+            grant.planned_tokens,
+            grant.vested_tokens,
+            grant.claimed_tokens,
+            grant.claimable_tokens
+        );
+    }
+    out
+}
+
+pub fn display_reconciliation(report: &ReconciliationReport) -> String {
+    let mut out = String::new();
+    for entry in &report.entries {
+        out += &match entry {
+            Reconciliation::Matched { slot, row, beneficiary } => {
+                format!("slot {:>3}  row {:>4}  {:<44}  ok\n", slot, row, beneficiary)
+            }
+            Reconciliation::Mismatch { slot, row, beneficiary, fields } => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|field| format!("{} sheet {} on-chain {}", field.field, field.sheet, field.on_chain))
+                    .collect();
+                format!("slot {:>3}  row {:>4}  {:<44}  MISMATCH {}\n", slot, row, beneficiary, fields.join(", "))
+            }
+            Reconciliation::NotInSheet { slot, beneficiary } => {
+                format!("slot {:>3}  row    -  {:<44}  NOT IN SHEET\n", slot, beneficiary)
+            }
+            Reconciliation::MissingOnChain { row, beneficiary } => {
+                format!("slot   -  row {:>4}  {:<44}  MISSING ON-CHAIN\n", row, beneficiary)
+            }
+        };
+    }
+    out + &format!(
+        "{} matched, {} mismatched, {} not in sheet, {} missing on-chain\n",
+        report.matched, report.mismatched, report.not_in_sheet, report.missing_on_chain
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vesting_schedule::{VestingScheduleBuf, VestingUser};
+    use vesting_schedule_client::import::{reconcile, GrantSpec};
+
+    #[test]
+    fn test_schedule_view() {
+        let vesting_data = VestingData { tge_timestamp: 1_000, ..Default::default() };
+        let mut buf = VestingScheduleBuf::new(300);
+        let mut schedule = buf.borrow_mut();
+        let mut grant = VestingUser::new(20, Pubkey::new_unique(), 12, 1_000_000);
+        grant.claimed_tokens = 250_000;
+        schedule.data[3] = grant;
+        schedule.len = 1;
+
+        let view = schedule_view(&Pubkey::new_unique(), &vesting_data, &buf.borrow(), 1_000 + 4 * 732 * 60 * 60);
+        assert_eq!(1, view.grants.len());
+        assert_eq!(3, view.grants[0].index);
+        assert_eq!(466_666, view.grants[0].vested_tokens);
+        assert_eq!(216_666, view.grants[0].claimable_tokens);
+
+        let table = display_schedule(&view);
+        assert!(table.contains(&grant.user.to_string()));
+        assert!(table.contains("Active grants:       1 of 300"));
+        let json: serde_json::Value = serde_json::to_value(&view).unwrap();
+        assert_eq!(216_666, json["grants"][0]["claimable_tokens"]);
+    }
+
+    #[test]
+    fn test_display_reconciliation() {
+        let mut buf = VestingScheduleBuf::new(300);
+        let schedule = buf.borrow_mut();
+        schedule.data[2] = VestingUser::new(20, Pubkey::new_unique(), 12, 1_000);
+        let spec = GrantSpec {
+            row: 2,
+            beneficiary: schedule.data[2].user,
+            unlocked_at_tge: 20,
+            unlocking_period: 6,
+            planned_tokens: 1_000,
+        };
+
+        let out = display_reconciliation(&reconcile(&[spec], &buf.borrow()));
+        assert!(out.contains("MISMATCH unlocking_period sheet 6 on-chain 12"));
+        assert!(out.ends_with("0 matched, 1 mismatched, 0 not in sheet, 0 missing on-chain\n"));
+    }
+}
@@ -0,0 +1,91 @@
+use serde_json::{json, Value};
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::Transaction;
+use std::str::FromStr;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(60);
+
+// Just the JSON-RPC calls the cli needs, so it does not pull in the whole solana-client
+pub struct RpcClient {
+    url: String,
+}
+
+impl RpcClient {
+    pub fn new(url: &str) -> Self {
+        RpcClient { url: url.to_string() }
+    }
+
+    fn call(&self, method: &str, params: Value) -> Result<Value, String> {
+        let request = json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params});
+        let response: Value = ureq::post(&self.url)
+            .send_json(request)
+            .map_err(|err| format!("{} failed: {}", method, err))?
+            .into_json()
+            .map_err(|err| format!("{} returned invalid json: {}", method, err))?;
+        if let Some(error) = response.get("error") {
+            return Err(format!("{} failed: {}", method, error));
+        }
+        Ok(response["result"].clone())
+    }
+
+    pub fn get_account_data(&self, pubkey: &Pubkey) -> Result<Option<Vec<u8>>, String> {
+        let result = self.call(
+            "getAccountInfo",
+            json!([pubkey.to_string(), {"encoding": "base64", "commitment": "confirmed"}]),
+        )?;
+        if result["value"].is_null() {
+            return Ok(None);
+        }
+        let encoded = result["value"]["data"][0]
+            .as_str()
+            .ok_or_else(|| format!("unexpected account data for {}", pubkey))?;
+        base64::decode(encoded)
+            .map(Some)
+            .map_err(|err| format!("invalid account data for {}: {}", pubkey, err))
+    }
+
+    pub fn get_minimum_balance_for_rent_exemption(&self, space: usize) -> Result<u64, String> {
+        self.call("getMinimumBalanceForRentExemption", json!([space]))?
+            .as_u64()
+            .ok_or_else(|| "unexpected rent exemption response".to_string())
+    }
+
+    pub fn get_recent_blockhash(&self) -> Result<Hash, String> {
+        let result = self.call("getRecentBlockhash", json!([{"commitment": "confirmed"}]))?;
+        let blockhash = result["value"]["blockhash"]
+            .as_str()
+            .ok_or_else(|| "unexpected blockhash response".to_string())?;
+        Hash::from_str(blockhash).map_err(|err| err.to_string())
+    }
+
+    pub fn send_and_confirm_transaction(&self, transaction: &Transaction) -> Result<Signature, String> {
+        let serialized = bincode::serialize(transaction).map_err(|err| err.to_string())?;
+        let signature = self.call(
+            "sendTransaction",
+            json!([base64::encode(serialized), {"encoding": "base64", "preflightCommitment": "confirmed"}]),
+        )?;
+        let signature = Signature::from_str(signature.as_str().unwrap_or_default())
+            .map_err(|err| err.to_string())?;
+
+        let started = Instant::now();
+        while started.elapsed() < CONFIRM_TIMEOUT {
+            let statuses = self.call("getSignatureStatuses", json!([[signature.to_string()]]))?;
+            let status = &statuses["value"][0];
+            if !status.is_null() {
+                if !status["err"].is_null() {
+                    return Err(format!("transaction {} failed: {}", signature, status["err"]));
+                }
+                let confirmation = status["confirmationStatus"].as_str().unwrap_or_default();
+                if confirmation == "confirmed" || confirmation == "finalized" {
+                    return Ok(signature);
+                }
+            }
+            sleep(Duration::from_millis(500));
+        }
+        Err(format!("transaction {} was not confirmed in time", signature))
+    }
+}
@@ -0,0 +1,97 @@
+use anchor_lang::prelude::*;
+use anchor_lang::AccountDeserialize;
+use vesting_schedule::utils::layout;
+use vesting_schedule::utils::layout::AccountLayout;
+use vesting_schedule::{ClaimableView, MerkleClaimStatus, VestingData, VestingScheduleBuf};
+
+// The decoders also read accounts that were not migrated yet, with the version they are at
+pub fn decode_vesting_data(data: &[u8]) -> std::result::Result<VestingData, ProgramError> {
+    let data = layout::upgrade(data, AccountLayout::VestingData).ok_or(ProgramError::InvalidAccountData)?;
+    VestingData::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_merkle_claim_status(data: &[u8]) -> std::result::Result<MerkleClaimStatus, ProgramError> {
+    let data = layout::upgrade(data, AccountLayout::MerkleClaimStatus).ok_or(ProgramError::InvalidAccountData)?;
+    MerkleClaimStatus::try_deserialize(&mut &data[..])
+}
+
+// VestingSchedule is a zero copy account sized by its capacity, so this keeps a copy of the whole account
+pub fn decode_vesting_schedule(data: &[u8]) -> std::result::Result<VestingScheduleBuf, ProgramError> {
+    let data = layout::upgrade(data, AccountLayout::VestingSchedule).ok_or(ProgramError::InvalidAccountData)?;
+    VestingScheduleBuf::from_account_data(&data)
+}
+
+// Return data of get_claimable, as found in the simulation result after base64 decoding
+pub fn decode_claimable_view(data: &[u8]) -> std::result::Result<ClaimableView, ProgramError> {
+    ClaimableView::try_from_slice(data).map_err(|_| ProgramError::InvalidAccountData)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::AccountSerialize;
+    use vesting_schedule::VestingUser;
+
+    #[test]
+    fn test_decode_vesting_data() {
+        let vesting_data = VestingData {
+            admin: Pubkey::new_unique(),
+            tge_timestamp: 1_629_158_400,
+            total_issued_so_far: 42,
+            ..Default::default()
+        };
+        let mut data = Vec::new();
+        vesting_data.try_serialize(&mut data).unwrap();
+
+        let decoded = decode_vesting_data(&data).unwrap();
+        assert_eq!(vesting_data.admin, decoded.admin);
+        assert_eq!(1_629_158_400, decoded.tge_timestamp);
+        assert_eq!(42, decoded.total_issued_so_far);
+        assert!(decode_merkle_claim_status(&data).is_err());
+
+        // not migrated yet, as the first deployed program wrote it: no admin recorded
+        let v1 = include_bytes!("../../programs/vesting-schedule/tests/fixtures/vesting_data_v1.bin");
+        let decoded = decode_vesting_data(v1).unwrap();
+        assert_eq!((1, Pubkey::default(), 450_000), (decoded.version, decoded.admin, decoded.total_issued_so_far));
+        assert_eq!(Pubkey::new(&[2; 32]), decoded.vesting_vault);
+
+        let v1 = include_bytes!("../../programs/vesting-schedule/tests/fixtures/vesting_schedule_v1.bin");
+        let decoded = decode_vesting_schedule(v1).unwrap();
+        let decoded = decoded.borrow();
+        assert_eq!((1, 3, 300), (decoded.version, { decoded.len }, { decoded.capacity }));
+        assert_eq!(Pubkey::new(&[12; 32]), decoded.data[2].user);
+    }
+
+    #[test]
+    fn test_decode_vesting_schedule() {
+        let mut schedule = VestingScheduleBuf::new(70);
+        let user = Pubkey::new_unique();
+        schedule.borrow_mut().len = 1;
+        schedule.borrow_mut().data[67] = VestingUser::new(20, user, 12, 1_000_000);
+        let mut data = schedule.account_data().to_vec();
+
+        let decoded = decode_vesting_schedule(&data).unwrap();
+        let decoded = decoded.borrow();
+        assert_eq!((1, 70), ({ decoded.len }, { decoded.capacity }));
+        assert_eq!(user, decoded.data[67].user);
+        assert_eq!(1_000_000, { decoded.data[67].planned_tokens });
+        assert!(decode_vesting_schedule(&data[..data.len() - 1]).is_err());
+
+        data[0] ^= 1;
+        assert_eq!(Err(ProgramError::InvalidAccountData), decode_vesting_schedule(&data).map(|_| ()));
+        assert!(decode_vesting_schedule(&data[..100]).is_err());
+
+    }
+
+    #[test]
+    fn test_decode_claimable_view() {
+        let view = ClaimableView {
+            vested: 300,
+            claimed: 100,
+            claimable: 200,
+            next_unlock_timestamp: Some(1_629_158_460),
+        };
+        assert_eq!(view, decode_claimable_view(&view.try_to_vec().unwrap()).unwrap());
+        assert!(decode_claimable_view(&[1, 2, 3]).is_err());
+    }
+}
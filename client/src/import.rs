@@ -0,0 +1,391 @@
+// Grant sheets (CSV or JSON) to VestingUser specs, plus reconciliation of a sheet
+// against the grants that ended up in the on-chain VestingSchedule.
+//
+// CSV has the header beneficiary,unlocked_at_tge,unlocking_period,planned_tokens.
+// JSON is an array of objects with the same keys, amounts may be numbers or strings.
+use anchor_lang::prelude::Pubkey;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fmt;
+use std::io::Read;
+use std::str::FromStr;
+use vesting_schedule::utils::utils::EventStatus;
+use vesting_schedule::{VestingScheduleRef, VestingUser};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GrantSpec {
+    // line of the CSV or position in the JSON array, starting at 1
+    pub row: u64,
+    pub beneficiary: Pubkey,
+    pub unlocked_at_tge: u8,
+    pub unlocking_period: u8,
+    pub planned_tokens: u64,
+}
+
+impl GrantSpec {
+    pub fn to_vesting_user(&self) -> VestingUser {
+        VestingUser::new(self.unlocked_at_tge, self.beneficiary, self.unlocking_period, self.planned_tokens)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImportIssue {
+    // 0 when the issue is about the sheet as a whole
+    pub row: u64,
+    pub message: String,
+}
+
+impl fmt::Display for ImportIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.row == 0 {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "row {}: {}", self.row, self.message)
+        }
+    }
+}
+
+fn issue(row: u64, message: String) -> ImportIssue {
+    ImportIssue { row, message }
+}
+
+#[derive(Deserialize)]
+struct CsvRow {
+    beneficiary: String,
+    unlocked_at_tge: String,
+    unlocking_period: String,
+    planned_tokens: String,
+}
+
+fn parse_field<T: FromStr>(row: u64, name: &str, value: &str, issues: &mut Vec<ImportIssue>) -> Option<T> {
+    let parsed = value.parse().ok();
+    if parsed.is_none() {
+        issues.push(issue(row, format!("invalid {} {:?}", name, value)));
+    }
+    parsed
+}
+
+fn parse_spec(
+    row: u64,
+    beneficiary: &str,
+    unlocked_at_tge: &str,
+    unlocking_period: &str,
+    planned_tokens: &str,
+    issues: &mut Vec<ImportIssue>,
+) -> Option<GrantSpec> {
+    let beneficiary = parse_field::<Pubkey>(row, "beneficiary", beneficiary, issues);
+    let unlocked_at_tge = parse_field(row, "unlocked_at_tge", unlocked_at_tge, issues);
+    let unlocking_period = parse_field(row, "unlocking_period", unlocking_period, issues);
+    let planned_tokens = parse_field(row, "planned_tokens", planned_tokens, issues);
+    Some(GrantSpec {
+        row,
+        beneficiary: beneficiary?,
+        unlocked_at_tge: unlocked_at_tge?,
+        unlocking_period: unlocking_period?,
+        planned_tokens: planned_tokens?,
+    })
+}
+
+// Parses every row, collecting one issue per problem instead of stopping at the first
+pub fn parse_csv(reader: impl Read) -> (Vec<GrantSpec>, Vec<ImportIssue>) {
+    let mut specs = Vec::new();
+    let mut issues = Vec::new();
+    let mut csv_reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(reader);
+    for (i, record) in csv_reader.deserialize::<CsvRow>().enumerate() {
+        // header is line 1
+        let row = i as u64 + 2;
+        match record {
+            Ok(raw) => specs.extend(parse_spec(
+                row,
+                &raw.beneficiary,
+                &raw.unlocked_at_tge,
+                &raw.unlocking_period,
+                &raw.planned_tokens,
+                &mut issues,
+            )),
+            Err(err) => issues.push(issue(row, err.to_string())),
+        }
+    }
+    (specs, issues)
+}
+
+pub fn parse_json(reader: impl Read) -> (Vec<GrantSpec>, Vec<ImportIssue>) {
+    let mut specs = Vec::new();
+    let mut issues = Vec::new();
+    let entries = match serde_json::from_reader::<_, Value>(reader) {
+        Ok(Value::Array(entries)) => entries,
+        Ok(_) => return (specs, vec![issue(0, "expected an array of grants".to_string())]),
+        Err(err) => return (specs, vec![issue(0, err.to_string())]),
+    };
+    for (i, entry) in entries.iter().enumerate() {
+        let row = i as u64 + 1;
+        let field = |name: &str| match &entry[name] {
+            Value::String(value) => value.trim().to_string(),
+            Value::Number(value) => value.to_string(),
+            Value::Null => String::new(),
+            other => other.to_string(),
+        };
+        specs.extend(parse_spec(
+            row,
+            &field("beneficiary"),
+            &field("unlocked_at_tge"),
+            &field("unlocking_period"),
+            &field("planned_tokens"),
+            &mut issues,
+        ));
+    }
+    (specs, issues)
+}
+
+// Checks the specs against each other and against the grants already in the schedule
+pub fn validate(specs: &[GrantSpec], schedule: &VestingScheduleRef) -> Vec<ImportIssue> {
+    let mut issues = Vec::new();
+    let mut on_chain = HashSet::new();
+    let mut free_slots = 0;
+    for grant in schedule.data.iter() {
+        if grant.status == EventStatus::Inactive as u8 {
+            free_slots += 1;
+        } else if grant.status == EventStatus::PendingToken as u8 {
+            on_chain.insert(grant.user);
+        }
+    }
+
+    let mut seen = HashSet::new();
+    for spec in specs {
+        if spec.unlocked_at_tge > 100 {
+            issues.push(issue(spec.row, format!("unlocked_at_tge {} is over 100 percent", spec.unlocked_at_tge)));
+        }
+        if spec.unlocking_period == 0 && spec.unlocked_at_tge < 100 {
+            issues.push(issue(spec.row, "unlocking_period 0 needs unlocked_at_tge 100".to_string()));
+        }
+        if spec.planned_tokens == 0 {
+            issues.push(issue(spec.row, "planned_tokens is 0".to_string()));
+        }
+        if !seen.insert(spec.beneficiary) {
+            issues.push(issue(spec.row, format!("{} appears more than once", spec.beneficiary)));
+        }
+        if on_chain.contains(&spec.beneficiary) {
+            issues.push(issue(spec.row, format!("{} already has a grant in the schedule", spec.beneficiary)));
+        }
+    }
+    if specs.len() > free_slots {
+        issues.push(issue(0, format!("{} grants do not fit into {} free slots", specs.len(), free_slots)));
+    }
+    issues
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldMismatch {
+    pub field: &'static str,
+    pub sheet: u64,
+    pub on_chain: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Reconciliation {
+    Matched { slot: usize, row: u64, beneficiary: String },
+    Mismatch { slot: usize, row: u64, beneficiary: String, fields: Vec<FieldMismatch> },
+    // active on-chain grant nobody put in the sheet
+    NotInSheet { slot: usize, beneficiary: String },
+    MissingOnChain { row: u64, beneficiary: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReconciliationReport {
+    pub matched: usize,
+    pub mismatched: usize,
+    pub not_in_sheet: usize,
+    pub missing_on_chain: usize,
+    pub entries: Vec<Reconciliation>,
+}
+
+impl ReconciliationReport {
+    pub fn is_clean(&self) -> bool {
+        self.mismatched == 0 && self.not_in_sheet == 0 && self.missing_on_chain == 0
+    }
+}
+
+// Walks the active slots in order and pairs each with the sheet row of the same beneficiary,
+// sheet rows left over at the end never made it on-chain
+pub fn reconcile(specs: &[GrantSpec], schedule: &VestingScheduleRef) -> ReconciliationReport {
+    let mut used = vec![false; specs.len()];
+    let mut entries = Vec::new();
+
+    for (slot, grant) in schedule.data.iter().enumerate() {
+        if grant.status != EventStatus::PendingToken as u8 {
+            continue;
+        }
+        let beneficiary = grant.user.to_string();
+        let position = specs
+            .iter()
+            .enumerate()
+            .position(|(i, spec)| !used[i] && spec.beneficiary == grant.user);
+        let spec = match position {
+            Some(i) => {
+                used[i] = true;
+                &specs[i]
+            }
+            None => {
+                entries.push(Reconciliation::NotInSheet { slot, beneficiary });
+                continue;
+            }
+        };
+        let fields: Vec<FieldMismatch> = [
+            ("unlocked_at_tge", spec.unlocked_at_tge as u64, grant.unlocked_at_tge as u64),
+            ("unlocking_period", spec.unlocking_period as u64, grant.unlocking_period as u64),
+            ("planned_tokens", spec.planned_tokens, grant.planned_tokens),
+        ]
+        .iter()
+        .filter(|(_, sheet, on_chain)| sheet != on_chain)
+        .map(|&(field, sheet, on_chain)| FieldMismatch { field, sheet, on_chain })
+        .collect();
+        entries.push(if fields.is_empty() {
+            Reconciliation::Matched { slot, row: spec.row, beneficiary }
+        } else {
+            Reconciliation::Mismatch { slot, row: spec.row, beneficiary, fields }
+        });
+    }
+    for (spec, _) in specs.iter().zip(used).filter(|(_, used)| !used) {
+        entries.push(Reconciliation::MissingOnChain {
+            row: spec.row,
+            beneficiary: spec.beneficiary.to_string(),
+        });
+    }
+
+    let count = |kind: fn(&Reconciliation) -> bool| entries.iter().filter(|entry| kind(entry)).count();
+    ReconciliationReport {
+        matched: count(|entry| matches!(entry, Reconciliation::Matched { .. })),
+        mismatched: count(|entry| matches!(entry, Reconciliation::Mismatch { .. })),
+        not_in_sheet: count(|entry| matches!(entry, Reconciliation::NotInSheet { .. })),
+        missing_on_chain: count(|entry| matches!(entry, Reconciliation::MissingOnChain { .. })),
+        entries,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vesting_schedule::VestingScheduleBuf;
+
+    const BENEFICIARY: &str = "29GPMU5gtBDbd368EwquqTmo33tKgvneAK9REmmxkqm8";
+    const OTHER: &str = "8v1DhJaewvhbhDmptNrkYig7YFcExsRKteR3cYjLw2iy";
+
+    fn spec(row: u64, beneficiary: &str, unlocked_at_tge: u8, unlocking_period: u8, planned_tokens: u64) -> GrantSpec {
+        GrantSpec {
+            row,
+            beneficiary: Pubkey::from_str(beneficiary).unwrap(),
+            unlocked_at_tge,
+            unlocking_period,
+            planned_tokens,
+        }
+    }
+
+    fn messages(issues: Vec<ImportIssue>) -> Vec<String> {
+        issues.iter().map(|issue| issue.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let sheet = format!(
+            "beneficiary,unlocked_at_tge,unlocking_period,planned_tokens\n\
+             {}, 15, 12, 1000000000\n\
+             not-a-key,15,12,1000\n\
+             {},300,12,-5\n",
+            BENEFICIARY, OTHER
+        );
+        let (specs, issues) = parse_csv(sheet.as_bytes());
+        assert_eq!(vec![spec(2, BENEFICIARY, 15, 12, 1_000_000_000)], specs);
+        assert_eq!(
+            vec![
+                "row 3: invalid beneficiary \"not-a-key\"",
+                "row 4: invalid unlocked_at_tge \"300\"",
+                "row 4: invalid planned_tokens \"-5\"",
+            ],
+            messages(issues)
+        );
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let sheet = format!(
+            r#"[
+                {{"beneficiary": "{}", "unlocked_at_tge": 15, "unlocking_period": 12, "planned_tokens": "18000000000000000000"}},
+                {{"beneficiary": "{}", "unlocked_at_tge": 20, "unlocking_period": 8}}
+            ]"#,
+            BENEFICIARY, OTHER
+        );
+        let (specs, issues) = parse_json(sheet.as_bytes());
+        assert_eq!(vec![spec(1, BENEFICIARY, 15, 12, 18_000_000_000_000_000_000)], specs);
+        assert_eq!(vec!["row 2: invalid planned_tokens \"\""], messages(issues));
+
+        let (_, issues) = parse_json(&b"{}"[..]);
+        assert_eq!(vec!["expected an array of grants"], messages(issues));
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut buf = VestingScheduleBuf::new(300);
+        let schedule = buf.borrow_mut();
+        schedule.data[0] = VestingUser::new(20, Pubkey::from_str(OTHER).unwrap(), 12, 1);
+        let schedule = buf.borrow();
+        let specs = vec![
+            spec(2, BENEFICIARY, 20, 0, 1_000),
+            spec(3, BENEFICIARY, 100, 0, 0),
+            spec(4, OTHER, 20, 12, 1_000),
+        ];
+        assert_eq!(
+            vec![
+                "row 2: unlocking_period 0 needs unlocked_at_tge 100".to_string(),
+                "row 3: planned_tokens is 0".to_string(),
+                format!("row 3: {} appears more than once", BENEFICIARY),
+                format!("row 4: {} already has a grant in the schedule", OTHER),
+            ],
+            messages(validate(&specs, &schedule))
+        );
+        assert_eq!(1, validate(&specs[..1], &schedule).len());
+    }
+
+    #[test]
+    fn test_reconcile() {
+        let third = Pubkey::new_unique();
+        let mut buf = VestingScheduleBuf::new(300);
+        let schedule = buf.borrow_mut();
+        schedule.data[1] = VestingUser::new(20, Pubkey::from_str(OTHER).unwrap(), 12, 1_000);
+        schedule.data[4] = VestingUser::new(15, Pubkey::from_str(BENEFICIARY).unwrap(), 18, 2_000);
+        schedule.data[6] = VestingUser::new(15, third, 18, 2_000);
+        let mut removed = VestingUser::new(15, Pubkey::new_unique(), 18, 2_000);
+        removed.status = EventStatus::Inactive as u8;
+        schedule.data[7] = removed;
+        let schedule = buf.borrow();
+
+        let missing = Pubkey::new_unique().to_string();
+        let specs = vec![
+            spec(2, BENEFICIARY, 15, 12, 2_500),
+            spec(3, OTHER, 20, 12, 1_000),
+            spec(4, &missing, 20, 12, 1_000),
+        ];
+        let report = reconcile(&specs, &schedule);
+        assert!(!report.is_clean());
+        assert_eq!((1, 1, 1, 1), (report.matched, report.mismatched, report.not_in_sheet, report.missing_on_chain));
+        assert_eq!(
+            vec![
+                Reconciliation::Matched { slot: 1, row: 3, beneficiary: OTHER.to_string() },
+                Reconciliation::Mismatch {
+                    slot: 4,
+                    row: 2,
+                    beneficiary: BENEFICIARY.to_string(),
+                    fields: vec![
+                        FieldMismatch { field: "unlocking_period", sheet: 12, on_chain: 18 },
+                        FieldMismatch { field: "planned_tokens", sheet: 2_500, on_chain: 2_000 },
+                    ],
+                },
+                Reconciliation::NotInSheet { slot: 6, beneficiary: third.to_string() },
+                Reconciliation::MissingOnChain { row: 4, beneficiary: missing },
+            ],
+            report.entries
+        );
+        assert!(reconcile(&specs[1..2], &schedule).entries.len() == 3);
+    }
+}
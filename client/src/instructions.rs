@@ -0,0 +1,671 @@
+use crate::pda;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::{system_instruction, system_program};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use vesting_schedule::utils::layout;
+use vesting_schedule::utils::utils::INITIAL_CAPACITY;
+use vesting_schedule::{accounts, instruction};
+
+// Bytes the vesting schedule account has to be created with before initialize
+pub fn vesting_schedule_space() -> usize {
+    layout::schedule_size(INITIAL_CAPACITY)
+}
+
+fn program_instruction(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: vesting_schedule::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+pub fn initialize(
+    admin: &Pubkey,
+    vesting_data: &Pubkey,
+    vesting_schedule: &Pubkey,
+    vesting_vault: &Pubkey,
+    tge_timestamp: u64,
+) -> Instruction {
+    program_instruction(
+        accounts::Initialize {
+            admin: *admin,
+            vesting_data: *vesting_data,
+            vesting_schedule: *vesting_schedule,
+            vesting_vault: *vesting_vault,
+            token_program: anchor_spl::token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::Initialize { tge_timestamp },
+    )
+}
+
+// Signed by the admin, as is remove_user
+#[allow(clippy::too_many_arguments)]
+pub fn add_user(
+    admin: &Pubkey,
+    vesting_data: &Pubkey,
+    vesting_schedule: &Pubkey,
+    user_pubkey: &Pubkey,
+    unlocked_at_tge: u8,
+    unlocking_period: u8,
+    planned_tokens: u64,
+) -> Instruction {
+    program_instruction(
+        accounts::AddUser {
+            admin: *admin,
+            vesting_data: *vesting_data,
+            vesting_schedule: *vesting_schedule,
+            system_program: system_program::ID,
+        },
+        instruction::AddUser {
+            unlocked_at_tge,
+            user_pubkey: *user_pubkey,
+            unlocking_period,
+            planned_tokens,
+        },
+    )
+}
+
+// `index` only has to be given when the beneficiary holds several grants
+pub fn remove_user(
+    admin: &Pubkey,
+    vesting_data: &Pubkey,
+    vesting_schedule: &Pubkey,
+    beneficiary: &Pubkey,
+    index: Option<u64>,
+) -> Instruction {
+    program_instruction(
+        accounts::RemoveUser {
+            admin: *admin,
+            vesting_data: *vesting_data,
+            vesting_schedule: *vesting_schedule,
+            system_program: system_program::ID,
+        },
+        instruction::RemoveUser {
+            beneficiary: *beneficiary,
+            index,
+        },
+    )
+}
+
+// `receipt_account` is the claim user's token account holding the grant's receipt, grants without
+// a receipt take None. The same goes for the other beneficiary instructions below.
+#[allow(clippy::too_many_arguments)]
+pub fn claim(
+    admin: &Pubkey,
+    vesting_data: &Pubkey,
+    vesting_schedule: &Pubkey,
+    vesting_vault: &Pubkey,
+    claim_user: &Pubkey,
+    claim_user_ata: &Pubkey,
+    index: Option<u64>,
+    receipt_account: Option<&Pubkey>,
+) -> Instruction {
+    program_instruction(
+        accounts::Claim {
+            owner: *admin,
+            vesting_schedule: *vesting_schedule,
+            vesting_data: *vesting_data,
+            claim_user: *claim_user,
+            claim_user_ata: *claim_user_ata,
+            vesting_vault: *vesting_vault,
+            vesting_vault_authority: pda::vesting_vault_authority(admin).key,
+            token_program: anchor_spl::token::ID,
+            system_program: system_program::ID,
+            receipt_account: *receipt_account.unwrap_or(claim_user),
+        },
+        instruction::Claim { index },
+    )
+}
+
+// Meant for simulateTransaction, decode the return data with accounts::decode_claimable_view
+pub fn get_claimable(vesting_data: &Pubkey, vesting_schedule: &Pubkey, index: u64) -> Instruction {
+    program_instruction(
+        accounts::GetClaimable {
+            vesting_data: *vesting_data,
+            vesting_schedule: *vesting_schedule,
+        },
+        instruction::GetClaimable { index },
+    )
+}
+
+pub fn get_locked_balance(
+    beneficiary: &Pubkey,
+    vesting_data: &Pubkey,
+    vesting_schedule: &Pubkey,
+    index: u64,
+) -> Instruction {
+    program_instruction(
+        accounts::GetLockedBalance {
+            beneficiary: *beneficiary,
+            vesting_data: *vesting_data,
+            vesting_schedule: *vesting_schedule,
+        },
+        instruction::GetLockedBalance { index },
+    )
+}
+
+// The beneficiary signs, `destination` can be any token account of the vested mint
+#[allow(clippy::too_many_arguments)]
+pub fn claim_to(
+    beneficiary: &Pubkey,
+    admin: &Pubkey,
+    vesting_data: &Pubkey,
+    vesting_schedule: &Pubkey,
+    vesting_vault: &Pubkey,
+    destination: &Pubkey,
+    index: u64,
+    receipt_account: Option<&Pubkey>,
+) -> Instruction {
+    program_instruction(
+        accounts::ClaimTo {
+            beneficiary: *beneficiary,
+            admin: *admin,
+            vesting_data: *vesting_data,
+            vesting_schedule: *vesting_schedule,
+            destination: *destination,
+            vesting_vault: *vesting_vault,
+            vesting_vault_authority: pda::vesting_vault_authority(admin).key,
+            token_program: anchor_spl::token::ID,
+            receipt_account: *receipt_account.unwrap_or(beneficiary),
+        },
+        instruction::ClaimTo { index },
+    )
+}
+
+pub fn set_staking_program(admin: &Pubkey, vesting_data: &Pubkey, staking_program: &Pubkey) -> Instruction {
+    program_instruction(
+        accounts::SetStakingProgram {
+            admin: *admin,
+            vesting_data: *vesting_data,
+        },
+        instruction::SetStakingProgram { staking_program: *staking_program },
+    )
+}
+
+fn stake_grant_accounts(
+    authority: &Pubkey,
+    admin: &Pubkey,
+    vesting_data: &Pubkey,
+    vesting_schedule: &Pubkey,
+    vesting_vault: &Pubkey,
+    staking_program: &Pubkey,
+    receipt_account: Option<&Pubkey>,
+) -> accounts::StakeGrant {
+    accounts::StakeGrant {
+        authority: *authority,
+        admin: *admin,
+        vesting_data: *vesting_data,
+        vesting_schedule: *vesting_schedule,
+        vesting_vault: *vesting_vault,
+        vesting_vault_authority: pda::vesting_vault_authority(admin).key,
+        staking_program: *staking_program,
+        receipt_account: *receipt_account.unwrap_or(authority),
+    }
+}
+
+// `staking_accounts` are whatever the staking program's deposit needs after the depositor and the source
+#[allow(clippy::too_many_arguments)]
+pub fn stake_grant(
+    beneficiary: &Pubkey,
+    admin: &Pubkey,
+    vesting_data: &Pubkey,
+    vesting_schedule: &Pubkey,
+    vesting_vault: &Pubkey,
+    staking_program: &Pubkey,
+    staking_accounts: &[AccountMeta],
+    index: u64,
+    amount: u64,
+    receipt_account: Option<&Pubkey>,
+) -> Instruction {
+    let accounts =
+        stake_grant_accounts(beneficiary, admin, vesting_data, vesting_schedule, vesting_vault, staking_program, receipt_account);
+    let mut ix = program_instruction(accounts, instruction::StakeGrant { index, amount });
+    ix.accounts.extend_from_slice(staking_accounts);
+    ix
+}
+
+// Signed by the beneficiary or the admin, `staking_accounts` as for the staking program's withdraw
+#[allow(clippy::too_many_arguments)]
+pub fn unstake_grant(
+    authority: &Pubkey,
+    admin: &Pubkey,
+    vesting_data: &Pubkey,
+    vesting_schedule: &Pubkey,
+    vesting_vault: &Pubkey,
+    staking_program: &Pubkey,
+    staking_accounts: &[AccountMeta],
+    index: u64,
+    amount: u64,
+    receipt_account: Option<&Pubkey>,
+) -> Instruction {
+    let accounts =
+        stake_grant_accounts(authority, admin, vesting_data, vesting_schedule, vesting_vault, staking_program, receipt_account);
+    let mut ix = program_instruction(accounts, instruction::UnstakeGrant { index, amount });
+    ix.accounts.extend_from_slice(staking_accounts);
+    ix
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn claim_from_stake(
+    beneficiary: &Pubkey,
+    admin: &Pubkey,
+    vesting_data: &Pubkey,
+    vesting_schedule: &Pubkey,
+    vesting_vault: &Pubkey,
+    destination: &Pubkey,
+    staking_program: &Pubkey,
+    staking_accounts: &[AccountMeta],
+    index: u64,
+    receipt_account: Option<&Pubkey>,
+) -> Instruction {
+    let mut ix = program_instruction(
+        accounts::ClaimFromStake {
+            beneficiary: *beneficiary,
+            admin: *admin,
+            vesting_data: *vesting_data,
+            vesting_schedule: *vesting_schedule,
+            destination: *destination,
+            vesting_vault: *vesting_vault,
+            vesting_vault_authority: pda::vesting_vault_authority(admin).key,
+            staking_program: *staking_program,
+            token_program: anchor_spl::token::ID,
+            receipt_account: *receipt_account.unwrap_or(beneficiary),
+        },
+        instruction::ClaimFromStake { index },
+    );
+    ix.accounts.extend_from_slice(staking_accounts);
+    ix
+}
+
+pub fn set_transferable(admin: &Pubkey, vesting_data: &Pubkey, transferable: bool) -> Instruction {
+    program_instruction(
+        accounts::SetTransferable {
+            admin: *admin,
+            vesting_data: *vesting_data,
+        },
+        instruction::SetTransferable { transferable },
+    )
+}
+
+// Creates the mint mint_receipt expects: no decimals, the vesting vault authority as mint authority
+// and no freeze authority. `lamports` has to cover rent for spl_token::state::Mint::LEN bytes.
+pub fn create_receipt_mint(payer: &Pubkey, receipt_mint: &Pubkey, admin: &Pubkey, lamports: u64) -> Vec<Instruction> {
+    use anchor_lang::solana_program::program_pack::Pack;
+    vec![
+        system_instruction::create_account(
+            payer,
+            receipt_mint,
+            lamports,
+            spl_token::state::Mint::LEN as u64,
+            &spl_token::ID,
+        ),
+        spl_token::instruction::initialize_mint(
+            &spl_token::ID,
+            receipt_mint,
+            &pda::vesting_vault_authority(admin).key,
+            None,
+            0,
+        )
+        .unwrap(),
+    ]
+}
+
+// `receipt_account` is a token account of `receipt_mint` owned by the beneficiary
+pub fn mint_receipt(
+    beneficiary: &Pubkey,
+    admin: &Pubkey,
+    vesting_data: &Pubkey,
+    vesting_schedule: &Pubkey,
+    receipt_mint: &Pubkey,
+    receipt_account: &Pubkey,
+    index: u64,
+) -> Instruction {
+    program_instruction(
+        accounts::MintReceipt {
+            beneficiary: *beneficiary,
+            admin: *admin,
+            vesting_data: *vesting_data,
+            vesting_schedule: *vesting_schedule,
+            receipt_mint: *receipt_mint,
+            receipt_account: *receipt_account,
+            vesting_vault_authority: pda::vesting_vault_authority(admin).key,
+            token_program: anchor_spl::token::ID,
+        },
+        instruction::MintReceipt { index },
+    )
+}
+
+pub fn sync_beneficiary(vesting_data: &Pubkey, vesting_schedule: &Pubkey, receipt_account: &Pubkey, index: u64) -> Instruction {
+    program_instruction(
+        accounts::SyncBeneficiary {
+            vesting_data: *vesting_data,
+            vesting_schedule: *vesting_schedule,
+            receipt_account: *receipt_account,
+        },
+        instruction::SyncBeneficiary { index },
+    )
+}
+
+// Moves the receipt and the grant in one transaction, so the previous holder cannot claim in between
+pub fn transfer_receipt(
+    holder: &Pubkey,
+    from: &Pubkey,
+    to: &Pubkey,
+    vesting_data: &Pubkey,
+    vesting_schedule: &Pubkey,
+    index: u64,
+) -> Vec<Instruction> {
+    vec![
+        spl_token::instruction::transfer(&spl_token::ID, from, to, holder, &[], 1).unwrap(),
+        sync_beneficiary(vesting_data, vesting_schedule, to, index),
+    ]
+}
+
+pub fn change_beneficiary(
+    beneficiary: &Pubkey,
+    vesting_data: &Pubkey,
+    vesting_schedule: &Pubkey,
+    index: u64,
+    new_beneficiary: &Pubkey,
+) -> Instruction {
+    program_instruction(
+        accounts::ChangeBeneficiary {
+            beneficiary: *beneficiary,
+            vesting_data: *vesting_data,
+            vesting_schedule: *vesting_schedule,
+        },
+        instruction::ChangeBeneficiary {
+            index,
+            new_beneficiary: *new_beneficiary,
+        },
+    )
+}
+
+pub fn split_grant(
+    beneficiary: &Pubkey,
+    vesting_data: &Pubkey,
+    vesting_schedule: &Pubkey,
+    index: u64,
+    split_tokens: u64,
+    new_beneficiary: &Pubkey,
+    receipt_account: Option<&Pubkey>,
+) -> Instruction {
+    program_instruction(
+        accounts::SplitGrant {
+            beneficiary: *beneficiary,
+            vesting_data: *vesting_data,
+            vesting_schedule: *vesting_schedule,
+            receipt_account: *receipt_account.unwrap_or(beneficiary),
+        },
+        instruction::SplitGrant {
+            index,
+            split_tokens,
+            new_beneficiary: *new_beneficiary,
+        },
+    )
+}
+
+// Converts the accounts of a schedule to the current layout, the admin pays the rent of the
+// extra bytes. `claim_statuses` are the merkle claim statuses of the schedule to convert along.
+pub fn migrate(admin: &Pubkey, vesting_data: &Pubkey, vesting_schedule: &Pubkey, claim_statuses: &[Pubkey]) -> Instruction {
+    let mut ix = program_instruction(
+        accounts::Migrate {
+            admin: *admin,
+            vesting_data: *vesting_data,
+            vesting_schedule: *vesting_schedule,
+            system_program: system_program::ID,
+        },
+        instruction::Migrate {},
+    );
+    ix.accounts.extend(claim_statuses.iter().map(|claim_status| AccountMeta::new(*claim_status, false)));
+    ix
+}
+
+// One grow_schedule per chunk, the account can only grow by 10 KiB per instruction
+pub fn grow_schedule(admin: &Pubkey, vesting_data: &Pubkey, vesting_schedule: &Pubkey, additional_grants: u64) -> Vec<Instruction> {
+    let chunks = additional_grants.div_ceil(layout::GROWTH_CHUNK);
+    (0..chunks)
+        .map(|chunk| {
+            program_instruction(
+                accounts::GrowSchedule {
+                    admin: *admin,
+                    vesting_data: *vesting_data,
+                    vesting_schedule: *vesting_schedule,
+                    system_program: system_program::ID,
+                },
+                instruction::GrowSchedule {
+                    additional_grants: (additional_grants - chunk * layout::GROWTH_CHUNK).min(layout::GROWTH_CHUNK),
+                },
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::solana_program::hash;
+
+    fn sighash(name: &str) -> Vec<u8> {
+        hash::hash(format!("global:{}", name).as_bytes()).to_bytes()[..8].to_vec()
+    }
+
+    #[test]
+    fn test_claim() {
+        let admin = Pubkey::new_unique();
+        let claim_user = Pubkey::new_unique();
+        let ix = claim(
+            &admin,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &claim_user,
+            &Pubkey::new_unique(),
+            Some(3),
+            None,
+        );
+
+        assert_eq!(vesting_schedule::ID, ix.program_id);
+        assert_eq!(10, ix.accounts.len());
+        assert_eq!(admin, ix.accounts[0].pubkey);
+        assert_eq!(claim_user, ix.accounts[3].pubkey);
+        // grants without a receipt take any account
+        assert_eq!(claim_user, ix.accounts[9].pubkey);
+        assert!(!ix.accounts[9].is_writable);
+        assert_eq!(pda::vesting_vault_authority(&admin).key, ix.accounts[6].pubkey);
+        assert!(ix.accounts[1].is_writable && ix.accounts[2].is_writable);
+
+        let mut data = sighash("claim");
+        data.push(1);
+        data.extend_from_slice(&3u64.to_le_bytes());
+        assert_eq!(data, ix.data);
+    }
+
+    #[test]
+    fn test_add_user() {
+        let user = Pubkey::new_unique();
+        let admin = Pubkey::new_unique();
+        let ix = add_user(&admin, &Pubkey::new_unique(), &Pubkey::new_unique(), &user, 15, 12, 1_000_000_000);
+        assert_eq!(admin, ix.accounts[0].pubkey);
+        assert!(ix.accounts[0].is_signer && !ix.accounts[1].is_writable && ix.accounts[2].is_writable);
+
+        let mut data = sighash("add_user");
+        data.push(15);
+        data.extend_from_slice(user.as_ref());
+        data.push(12);
+        data.extend_from_slice(&1_000_000_000u64.to_le_bytes());
+        assert_eq!(data, ix.data);
+    }
+
+    #[test]
+    fn test_initialize() {
+        let admin = Pubkey::new_unique();
+        let vesting_data = Pubkey::new_unique();
+        let ix = initialize(&admin, &vesting_data, &Pubkey::new_unique(), &Pubkey::new_unique(), 1_629_158_400);
+        assert!(ix.accounts[0].is_signer && ix.accounts[0].is_writable);
+        assert!(ix.accounts[1].is_signer && ix.accounts[1].is_writable);
+        assert_eq!(vesting_data, ix.accounts[1].pubkey);
+        assert_eq!(anchor_spl::token::ID, ix.accounts[4].pubkey);
+    }
+
+    #[test]
+    fn test_remove_user() {
+        let beneficiary = Pubkey::new_unique();
+        let mut data = sighash("remove_user");
+        data.extend_from_slice(beneficiary.as_ref());
+        data.push(0);
+        let ix = remove_user(&Pubkey::new_unique(), &Pubkey::new_unique(), &Pubkey::new_unique(), &beneficiary, None);
+        assert!(ix.accounts[0].is_signer);
+        assert_eq!(data, ix.data);
+    }
+
+    #[test]
+    fn test_claim_to() {
+        let beneficiary = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+        let ix = claim_to(
+            &beneficiary,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &destination,
+            2,
+            None,
+        );
+        assert!(ix.accounts[0].is_signer);
+        assert_eq!(beneficiary, ix.accounts[0].pubkey);
+        assert_eq!(destination, ix.accounts[4].pubkey);
+        assert!(ix.accounts[4].is_writable);
+        assert_eq!(ix.data[..8], sighash("claim_to")[..]);
+        assert_eq!(
+            get_locked_balance(&beneficiary, &Pubkey::new_unique(), &Pubkey::new_unique(), 2).data[..8],
+            sighash("get_locked_balance")[..]
+        );
+    }
+
+    #[test]
+    fn test_get_claimable() {
+        let ix = get_claimable(&Pubkey::new_unique(), &Pubkey::new_unique(), 5);
+        assert_eq!(2, ix.accounts.len());
+        assert!(ix.accounts.iter().all(|meta| !meta.is_writable && !meta.is_signer));
+
+        let mut data = sighash("get_claimable");
+        data.extend_from_slice(&5u64.to_le_bytes());
+        assert_eq!(data, ix.data);
+    }
+
+    #[test]
+    fn test_stake_grant() {
+        let beneficiary = Pubkey::new_unique();
+        let staking_program = Pubkey::new_unique();
+        let stake_vault = AccountMeta::new(Pubkey::new_unique(), false);
+        let receipt_account = Pubkey::new_unique();
+        let ix = stake_grant(
+            &beneficiary,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &staking_program,
+            std::slice::from_ref(&stake_vault),
+            1,
+            500,
+            Some(&receipt_account),
+        );
+        assert_eq!(9, ix.accounts.len());
+        assert!(ix.accounts[0].is_signer);
+        assert_eq!(staking_program, ix.accounts[6].pubkey);
+        assert_eq!(receipt_account, ix.accounts[7].pubkey);
+        assert_eq!(stake_vault, ix.accounts[8]);
+
+        let mut data = sighash("stake_grant");
+        data.extend_from_slice(&1u64.to_le_bytes());
+        data.extend_from_slice(&500u64.to_le_bytes());
+        assert_eq!(data, ix.data);
+
+        let ix = claim_from_stake(
+            &beneficiary,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &staking_program,
+            &[],
+            1,
+            None,
+        );
+        assert_eq!(10, ix.accounts.len());
+        assert_eq!(ix.data[..8], sighash("claim_from_stake")[..]);
+    }
+
+    #[test]
+    fn test_split_grant() {
+        let new_beneficiary = Pubkey::new_unique();
+        let ix = split_grant(&Pubkey::new_unique(), &Pubkey::new_unique(), &Pubkey::new_unique(), 2, 250, &new_beneficiary, None);
+        assert!(ix.accounts[0].is_signer && ix.accounts[2].is_writable);
+
+        let mut data = sighash("split_grant");
+        data.extend_from_slice(&2u64.to_le_bytes());
+        data.extend_from_slice(&250u64.to_le_bytes());
+        data.extend_from_slice(new_beneficiary.as_ref());
+        assert_eq!(data, ix.data);
+    }
+
+    #[test]
+    fn test_migrate() {
+        let claim_status = Pubkey::new_unique();
+        let ix = migrate(&Pubkey::new_unique(), &Pubkey::new_unique(), &Pubkey::new_unique(), &[claim_status]);
+        assert_eq!(5, ix.accounts.len());
+        assert!(ix.accounts[0].is_signer && ix.accounts[1].is_writable && ix.accounts[2].is_writable);
+        assert_eq!(AccountMeta::new(claim_status, false), ix.accounts[4]);
+        assert_eq!(sighash("migrate"), ix.data);
+    }
+
+    #[test]
+    fn test_grow_schedule() {
+        let admin = Pubkey::new_unique();
+        let ixs = grow_schedule(&admin, &Pubkey::new_unique(), &Pubkey::new_unique(), 150);
+        assert_eq!(3, ixs.len());
+        let mut data = sighash("grow_schedule");
+        data.extend_from_slice(&22u64.to_le_bytes());
+        assert_eq!(data, ixs[2].data);
+        assert_eq!(ixs[0].data, ixs[1].data);
+        assert_eq!(64, ixs[0].data[8]);
+        assert!(ixs[0].accounts[0].is_signer && ixs[0].accounts[0].is_writable && ixs[0].accounts[2].is_writable);
+        assert_eq!(system_program::ID, ixs[0].accounts[3].pubkey);
+        assert!(grow_schedule(&admin, &Pubkey::new_unique(), &Pubkey::new_unique(), 0).is_empty());
+        assert_eq!(43_272, vesting_schedule_space());
+    }
+
+    #[test]
+    fn test_transfer_receipt() {
+        let holder = Pubkey::new_unique();
+        let to = Pubkey::new_unique();
+        let ixs = transfer_receipt(&holder, &Pubkey::new_unique(), &to, &Pubkey::new_unique(), &Pubkey::new_unique(), 4);
+        assert_eq!(2, ixs.len());
+        assert_eq!(spl_token::ID, ixs[0].program_id);
+        assert_eq!(holder, ixs[0].accounts[2].pubkey);
+
+        let mut data = sighash("sync_beneficiary");
+        data.extend_from_slice(&4u64.to_le_bytes());
+        assert_eq!(data, ixs[1].data);
+        assert_eq!(to, ixs[1].accounts[2].pubkey);
+        assert!(ixs[1].accounts.iter().all(|meta| !meta.is_signer));
+
+        let admin = Pubkey::new_unique();
+        let ixs = create_receipt_mint(&Pubkey::new_unique(), &Pubkey::new_unique(), &admin, 1);
+        let mint = spl_token::instruction::TokenInstruction::unpack(&ixs[1].data).unwrap();
+        match mint {
+            spl_token::instruction::TokenInstruction::InitializeMint { decimals, mint_authority, freeze_authority } => {
+                assert_eq!(0, decimals);
+                assert_eq!(pda::vesting_vault_authority(&admin).key, mint_authority);
+                assert!(freeze_authority.is_none());
+            }
+            _ => panic!("expected InitializeMint"),
+        }
+    }
+}
@@ -0,0 +1,45 @@
+pub mod accounts;
+pub mod import;
+pub mod instructions;
+pub mod pda;
+pub mod projection;
+
+use vesting_schedule::{vesting_operations, ErrorCode, VestingData, VestingUser};
+
+pub use vesting_schedule::ID as PROGRAM_ID;
+
+// Everything the grant has vested at `timestamp`, claimed or not.
+// Runs the same code the program runs on claim, pauses and suspensions included.
+pub fn vested_amount_at(vesting_data: &VestingData, grant: &VestingUser, timestamp: i64) -> Result<u64, ErrorCode> {
+    let now = vesting_operations::vesting_clock(vesting_data, timestamp as u64);
+    vesting_operations::vested_amount(grant, vesting_data.tge_timestamp, now)
+}
+
+// What a claim at `timestamp` would transfer to the beneficiary
+pub fn claimable_amount_at(vesting_data: &VestingData, grant: &VestingUser, timestamp: i64) -> Result<u64, ErrorCode> {
+    let now = vesting_operations::vesting_clock(vesting_data, timestamp as u64);
+    vesting_operations::claimable_amount(grant, vesting_data.tge_timestamp, now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::prelude::Pubkey;
+    use vesting_schedule::tokenoperation::schedule::calculate_entitled_amount;
+
+    #[test]
+    fn test_vested_amount_matches_program() {
+        let vesting_data = VestingData { tge_timestamp: 1_000, ..Default::default() };
+        let mut grant = VestingUser::new(20, Pubkey::new_unique(), 12, 1_000_000);
+
+        for timestamp in [0, 1_000, 1_001, 1_000 + 4 * 732 * 60 * 60, 1_000 + 20 * 732 * 60 * 60] {
+            let expected = calculate_entitled_amount(1_000_000, 20, 12, 0, 1_000, timestamp as u64, 0, 0).unwrap();
+            assert_eq!(expected, vested_amount_at(&vesting_data, &grant, timestamp).unwrap());
+        }
+
+        grant.claimed_tokens = 250_000;
+        let timestamp = 1_000 + 4 * 732 * 60 * 60;
+        assert_eq!(466_666, vested_amount_at(&vesting_data, &grant, timestamp).unwrap());
+        assert_eq!(216_666, claimable_amount_at(&vesting_data, &grant, timestamp).unwrap());
+    }
+}
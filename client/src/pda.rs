@@ -0,0 +1,11 @@
+use anchor_lang::prelude::Pubkey;
+use vesting_schedule::utils::{self, pda::VestingPdaAddress};
+
+// Owner of the vesting vault, derived from the admin that initialized the schedule
+pub fn vesting_vault_authority(admin: &Pubkey) -> VestingPdaAddress {
+    utils::pda::make_vesting_pda_pubkey(admin, utils::utils::TOKEN_MINT_TAG, &vesting_schedule::ID)
+}
+
+pub fn claim_status(vesting_data: &Pubkey, beneficiary: &Pubkey) -> VestingPdaAddress {
+    utils::pda::make_claim_status_pda_pubkey(vesting_data, beneficiary, &vesting_schedule::ID)
+}
@@ -0,0 +1,223 @@
+// Unlock calendar: what a set of grants vests per period over a date range, per grant,
+// per cohort (grants with the same TGE percentage and unlocking period) and in total.
+// Every number comes from vested_amount_at, so from the program's calculate_entitled_amount.
+use crate::vested_amount_at;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use vesting_schedule::{ErrorCode, VestingData, VestingUser};
+
+pub const DAY: i64 = 24 * 60 * 60;
+pub const WEEK: i64 = 7 * DAY;
+// the program's month
+pub const MONTH: i64 = 732 * 60 * 60;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UnlockPeriod {
+    pub from: i64,
+    pub to: i64,
+    // vested during [from, to)
+    pub unlocked: u64,
+    // vested at `to`, claimed or not
+    pub cumulative: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Series {
+    pub name: String,
+    pub periods: Vec<UnlockPeriod>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SupplyPeriod {
+    pub from: i64,
+    pub to: i64,
+    pub unlocked: u64,
+    pub cumulative: u64,
+    // circulating supply outside of vesting plus everything vested so far
+    pub circulating_supply: u64,
+    // unlocked relative to the circulating supply at `from`
+    pub supply_increase_bps: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Projection {
+    pub from: i64,
+    pub to: i64,
+    pub step: i64,
+    pub grants: Vec<Series>,
+    pub cohorts: Vec<Series>,
+    pub total: Vec<SupplyPeriod>,
+}
+
+fn boundaries(from: i64, to: i64, step: i64) -> Vec<i64> {
+    let mut boundaries: Vec<i64> = (0..).map(|i| from + i * step).take_while(|t| *t < to).collect();
+    boundaries.push(to);
+    boundaries
+}
+
+fn add_series(into: &mut Vec<UnlockPeriod>, series: &[UnlockPeriod]) {
+    if into.is_empty() {
+        into.extend_from_slice(series);
+        return;
+    }
+    for (sum, period) in into.iter_mut().zip(series) {
+        sum.unlocked += period.unlocked;
+        sum.cumulative += period.cumulative;
+    }
+}
+
+// `grants` are named by the caller, e.g. by slot or sheet row. `circulating_supply` is the
+// supply at `from` that does not come from these grants.
+pub fn project(
+    vesting_data: &VestingData,
+    grants: &[(String, VestingUser)],
+    from: i64,
+    to: i64,
+    step: i64,
+    circulating_supply: u64,
+) -> Result<Projection, ErrorCode> {
+    if step <= 0 || to <= from {
+        return Err(ErrorCode::InvalidInput);
+    }
+    let boundaries = boundaries(from, to, step);
+
+    let mut grant_series = Vec::with_capacity(grants.len());
+    let mut cohorts: BTreeMap<(u8, u8), Vec<UnlockPeriod>> = BTreeMap::new();
+    let mut total: Vec<UnlockPeriod> = Vec::new();
+    for (name, grant) in grants {
+        let vested = boundaries
+            .iter()
+            .map(|timestamp| vested_amount_at(vesting_data, grant, *timestamp))
+            .collect::<Result<Vec<u64>, ErrorCode>>()?;
+        let periods: Vec<UnlockPeriod> = boundaries
+            .windows(2)
+            .zip(vested.windows(2))
+            .map(|(range, vested)| UnlockPeriod {
+                from: range[0],
+                to: range[1],
+                unlocked: vested[1] - vested[0],
+                cumulative: vested[1],
+            })
+            .collect();
+        add_series(cohorts.entry((grant.unlocked_at_tge, grant.unlocking_period)).or_default(), &periods);
+        add_series(&mut total, &periods);
+        grant_series.push(Series { name: name.clone(), periods });
+    }
+
+    let vested_at_from = total.first().map_or(0, |period| period.cumulative - period.unlocked);
+    let total = total
+        .iter()
+        .map(|period| {
+            let before = circulating_supply + period.cumulative - period.unlocked - vested_at_from;
+            SupplyPeriod {
+                from: period.from,
+                to: period.to,
+                unlocked: period.unlocked,
+                cumulative: period.cumulative,
+                circulating_supply: before + period.unlocked,
+                supply_increase_bps: if before == 0 {
+                    0
+                } else {
+                    (period.unlocked as u128 * 10_000 / before as u128) as u64
+                },
+            }
+        })
+        .collect();
+
+    Ok(Projection {
+        from,
+        to,
+        step,
+        grants: grant_series,
+        cohorts: cohorts
+            .into_iter()
+            .map(|((unlocked_at_tge, unlocking_period), periods)| Series {
+                name: format!("{}% at TGE, {} months", unlocked_at_tge, unlocking_period),
+                periods,
+            })
+            .collect(),
+        total,
+    })
+}
+
+// One row per series and period, the total rows carry the supply columns
+pub fn to_csv(projection: &Projection) -> String {
+    let mut out = String::from("series,from,to,unlocked,cumulative,circulating_supply,supply_increase_bps\n");
+    for (kind, series) in [("grant", &projection.grants), ("cohort", &projection.cohorts)] {
+        for series in series.iter() {
+            for period in &series.periods {
+                out += &format!(
+                    "\"{} {}\",{},{},{},{},,\n",
+                    kind, series.name, period.from, period.to, period.unlocked, period.cumulative
+                );
+            }
+        }
+    }
+    for period in &projection.total {
+        out += &format!(
+            "total,{},{},{},{},{},{}\n",
+            period.from, period.to, period.unlocked, period.cumulative, period.circulating_supply, period.supply_increase_bps
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::prelude::Pubkey;
+    use vesting_schedule::tokenoperation::schedule::calculate_entitled_amount;
+
+    const TGE: i64 = 1_629_158_400;
+
+    #[test]
+    fn test_project() {
+        let vesting_data = VestingData { tge_timestamp: TGE as u64, ..Default::default() };
+        let grants = vec![
+            ("a".to_string(), VestingUser::new(20, Pubkey::new_unique(), 12, 1_200_000)),
+            ("b".to_string(), VestingUser::new(20, Pubkey::new_unique(), 12, 600_000)),
+            ("c".to_string(), VestingUser::new(100, Pubkey::new_unique(), 0, 500_000)),
+        ];
+        let projection = project(&vesting_data, &grants, TGE - MONTH, TGE + 3 * MONTH + 10, MONTH, 1_000_000).unwrap();
+
+        assert_eq!(5, projection.total.len());
+        assert_eq!((TGE + 3 * MONTH, TGE + 3 * MONTH + 10), (projection.total[4].from, projection.total[4].to));
+        assert_eq!(2, projection.cohorts.len());
+        assert_eq!("20% at TGE, 12 months", projection.cohorts[0].name);
+
+        for (name, grant) in &grants {
+            let series = projection.grants.iter().find(|series| &series.name == name).unwrap();
+            for period in &series.periods {
+                let expected = calculate_entitled_amount(
+                    grant.planned_tokens,
+                    grant.unlocked_at_tge as u64,
+                    grant.unlocking_period as u64,
+                    0,
+                    TGE as u64,
+                    period.to as u64,
+                    0,
+                    0,
+                )
+                .unwrap();
+                assert_eq!(expected, period.cumulative);
+            }
+        }
+
+        // the period starting at the TGE gets the TGE unlock and the first month
+        assert_eq!(0, projection.total[0].unlocked);
+        assert_eq!(240_000 + 80_000 + 120_000 + 40_000 + 500_000, projection.total[1].unlocked);
+        assert_eq!(80_000 + 40_000, projection.total[2].unlocked);
+        assert_eq!(1_000_000 + 980_000 + 120_000, projection.total[2].circulating_supply);
+        assert_eq!(120_000 * 10_000 / 1_980_000, projection.total[2].supply_increase_bps);
+
+        let unlocked: u64 = projection.cohorts.iter().map(|cohort| cohort.periods[1].unlocked).sum();
+        assert_eq!(projection.total[1].unlocked, unlocked);
+
+        let csv = to_csv(&projection);
+        assert_eq!(1 + 3 * 5 + 2 * 5 + 5, csv.lines().count());
+        assert!(csv.contains(&format!("total,{},{},0,0,1000000,0\n", TGE - MONTH, TGE)));
+
+        assert!(project(&vesting_data, &grants, TGE, TGE, MONTH, 0).is_err());
+        assert!(project(&vesting_data, &grants, TGE, TGE + 1, 0, 0).is_err());
+    }
+}
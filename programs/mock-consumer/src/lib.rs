@@ -0,0 +1,195 @@
+// Minimal staking program that only exists to exercise the vesting_schedule CPI entry points:
+// it records a beneficiary's locked balance and stakes claimed tokens in a vault it owns.
+// It also implements the deposit/withdraw interface vesting_schedule stakes locked tokens with.
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::get_return_data;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use std::convert::TryInto;
+use vesting_schedule::program::VestingSchedule as VestingScheduleProgram;
+use vesting_schedule::LockedBalance;
+declare_id!("GSrv8FunnQAMyWpx8esQkvrEJ61bXqZ6sFcAUx1rJ1US");
+
+pub const POSITION_TAG: &[u8] = b"position";
+pub const STAKE_AUTHORITY_TAG: &[u8] = b"stake_authority";
+pub const DEPOSIT_TAG: &[u8] = b"deposit";
+
+#[program]
+pub mod mock_consumer {
+    use super::*;
+
+    pub fn record_locked_balance(ctx: Context<RecordLockedBalance>, index: u64) -> ProgramResult {
+        let cpi_accounts = vesting_schedule::cpi::accounts::GetLockedBalance {
+            beneficiary: ctx.accounts.beneficiary.clone(),
+            vesting_data: ctx.accounts.vesting_data.clone(),
+            vesting_schedule: ctx.accounts.vesting_schedule.clone(),
+        };
+        let cpi_program = ctx.accounts.vesting_program.to_account_info();
+        vesting_schedule::cpi::get_locked_balance(CpiContext::new(cpi_program, cpi_accounts), index)?;
+        let balance = LockedBalance::from_return_data().ok_or(ProgramError::InvalidAccountData)?;
+
+        let position = &mut ctx.accounts.position;
+        position.beneficiary = balance.beneficiary;
+        position.locked = balance.locked;
+        Ok(())
+    }
+
+    pub fn stake_claimed(ctx: Context<StakeClaimed>, index: u64) -> ProgramResult {
+        let cpi_accounts = vesting_schedule::cpi::accounts::ClaimTo {
+            beneficiary: ctx.accounts.beneficiary.clone(),
+            admin: ctx.accounts.admin.clone(),
+            vesting_data: ctx.accounts.vesting_data.clone(),
+            vesting_schedule: ctx.accounts.vesting_schedule.clone(),
+            destination: ctx.accounts.stake_vault.to_account_info(),
+            vesting_vault: ctx.accounts.vesting_vault.clone(),
+            vesting_vault_authority: ctx.accounts.vesting_vault_authority.clone(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            // the mock only stakes grants without a receipt
+            receipt_account: ctx.accounts.beneficiary.clone(),
+        };
+        let cpi_program = ctx.accounts.vesting_program.to_account_info();
+        vesting_schedule::cpi::claim_to(CpiContext::new(cpi_program, cpi_accounts), index)?;
+        let amount = match get_return_data() {
+            Some((program_id, data)) if program_id == vesting_schedule::ID && data.len() == 8 => {
+                u64::from_le_bytes(data[..].try_into().unwrap())
+            }
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        ctx.accounts.position.staked += amount;
+        Ok(())
+    }
+
+    pub fn open_deposit(ctx: Context<OpenDeposit>, depositor: Pubkey) -> ProgramResult {
+        ctx.accounts.deposit.depositor = depositor;
+        Ok(())
+    }
+
+    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> ProgramResult {
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.source.clone(),
+            to: ctx.accounts.stake_vault.to_account_info(),
+            authority: ctx.accounts.depositor.clone(),
+        };
+        let moved = amount.saturating_sub(ctx.accounts.deposit.skim);
+        token::transfer(CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts), moved)?;
+
+        ctx.accounts.deposit.amount += amount;
+        Ok(())
+    }
+
+    pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> ProgramResult {
+        let deposit = &mut ctx.accounts.deposit;
+        deposit.amount = deposit.amount.checked_sub(amount).ok_or(ProgramError::InsufficientFunds)?;
+
+        let (_, bump) = Pubkey::find_program_address(&[STAKE_AUTHORITY_TAG], ctx.program_id);
+        let seeds = [STAKE_AUTHORITY_TAG, &[bump]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.stake_vault.to_account_info(),
+            to: ctx.accounts.destination.clone(),
+            authority: ctx.accounts.stake_authority.clone(),
+        };
+        let signer = &[&seeds[..]];
+        token::transfer(
+            CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer),
+            amount.saturating_sub(deposit.skim),
+        )
+    }
+}
+
+#[derive(Accounts)]
+pub struct RecordLockedBalance<'info> {
+    #[account(signer, mut)]
+    pub beneficiary: AccountInfo<'info>,
+    #[account(
+        init_if_needed,
+        payer = beneficiary,
+        seeds = [POSITION_TAG, beneficiary.key.as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    pub vesting_data: AccountInfo<'info>,
+    pub vesting_schedule: AccountInfo<'info>,
+    pub vesting_program: Program<'info, VestingScheduleProgram>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct StakeClaimed<'info> {
+    #[account(signer)]
+    pub beneficiary: AccountInfo<'info>,
+    #[account(mut, has_one = beneficiary)]
+    pub position: Account<'info, Position>,
+    #[account(seeds = [STAKE_AUTHORITY_TAG], bump)]
+    pub stake_authority: AccountInfo<'info>,
+    #[account(mut, constraint = stake_vault.owner == stake_authority.key())]
+    pub stake_vault: Account<'info, TokenAccount>,
+    pub admin: AccountInfo<'info>,
+    #[account(mut)]
+    pub vesting_data: AccountInfo<'info>,
+    #[account(mut)]
+    pub vesting_schedule: AccountInfo<'info>,
+    #[account(mut)]
+    pub vesting_vault: AccountInfo<'info>,
+    pub vesting_vault_authority: AccountInfo<'info>,
+    pub token_program: Program<'info, Token>,
+    pub vesting_program: Program<'info, VestingScheduleProgram>,
+}
+
+#[derive(Accounts)]
+#[instruction(depositor: Pubkey)]
+pub struct OpenDeposit<'info> {
+    #[account(signer, mut)]
+    pub payer: AccountInfo<'info>,
+    #[account(init, payer = payer, seeds = [DEPOSIT_TAG, depositor.as_ref()], bump)]
+    pub deposit: Account<'info, StakeDeposit>,
+    pub system_program: Program<'info, System>,
+}
+
+// The two leading accounts are what vesting_schedule passes, the rest are remaining accounts there
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(signer)]
+    pub depositor: AccountInfo<'info>,
+    #[account(mut)]
+    pub source: AccountInfo<'info>,
+    #[account(mut, has_one = depositor)]
+    pub deposit: Account<'info, StakeDeposit>,
+    #[account(mut, constraint = stake_vault.owner == stake_authority.key())]
+    pub stake_vault: Account<'info, TokenAccount>,
+    #[account(seeds = [STAKE_AUTHORITY_TAG], bump)]
+    pub stake_authority: AccountInfo<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(signer)]
+    pub depositor: AccountInfo<'info>,
+    #[account(mut)]
+    pub destination: AccountInfo<'info>,
+    #[account(mut, has_one = depositor)]
+    pub deposit: Account<'info, StakeDeposit>,
+    #[account(mut, constraint = stake_vault.owner == stake_authority.key())]
+    pub stake_vault: Account<'info, TokenAccount>,
+    #[account(seeds = [STAKE_AUTHORITY_TAG], bump)]
+    pub stake_authority: AccountInfo<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[account]
+#[derive(Debug, Default)]
+pub struct StakeDeposit {
+    pub depositor: Pubkey,
+    pub amount: u64,
+    // tokens held back on every deposit and withdraw, to play a misbehaving staking program
+    pub skim: u64,
+}
+
+#[account]
+#[derive(Debug, Default)]
+pub struct Position {
+    pub beneficiary: Pubkey,
+    // unvested tokens as last reported by vesting_schedule
+    pub locked: u64,
+    pub staked: u64,
+}
@@ -0,0 +1,446 @@
+// mock_consumer talking to vesting_schedule through CPI, both run as native processors.
+// mock_consumer is also the whitelisted staking program for the staking tests.
+use anchor_lang::{AccountDeserialize, AccountSerialize, InstructionData, ToAccountMetas};
+use mock_consumer::{Position, StakeDeposit, DEPOSIT_TAG, POSITION_TAG, STAKE_AUTHORITY_TAG};
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::account::Account;
+use solana_sdk::clock::Clock;
+use solana_sdk::program_error::ProgramError;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+use solana_sdk::system_program;
+use std::time::{SystemTime, UNIX_EPOCH};
+use vesting_schedule::{VestingData, VestingScheduleBuf, VestingUser};
+use vesting_schedule_client::{accounts, instructions, projection::MONTH};
+
+const PLANNED_TOKENS: u64 = 1_000_000;
+
+struct Fixture {
+    context: ProgramTestContext,
+    admin: Pubkey,
+    vesting_data: Pubkey,
+    vesting_schedule: Pubkey,
+    vesting_vault: Pubkey,
+    beneficiary: Keypair,
+    stake_vault: Pubkey,
+    // token account of the beneficiary
+    destination: Pubkey,
+    // three months after TGE, with slack for the clock moving on between slots
+    now: i64,
+}
+
+fn program_account(owner: &Pubkey, data: Vec<u8>) -> Account {
+    Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: *owner,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+fn token_account(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Account {
+    let mut data = vec![0; spl_token::state::Account::LEN];
+    let account = spl_token::state::Account {
+        mint: *mint,
+        owner: *owner,
+        amount,
+        state: spl_token::state::AccountState::Initialized,
+        ..Default::default()
+    };
+    spl_token::state::Account::pack(account, &mut data).unwrap();
+    program_account(&spl_token::ID, data)
+}
+
+impl Fixture {
+    // Native CPI in program-test 1.9 cannot create accounts, so everything initialize,
+    // add_user and the position init would create is put into genesis instead
+    async fn new() -> Self {
+        let mut program_test = ProgramTest::new(
+            "vesting_schedule",
+            vesting_schedule::ID,
+            processor!(vesting_schedule::process_instruction),
+        );
+        program_test.add_program("mock_consumer", mock_consumer::ID, processor!(mock_consumer::entry));
+        // native spl-token, the bundled BPF build does not run under the interpreter here
+        program_test.add_program("spl_token", spl_token::ID, processor!(spl_token::processor::Processor::process));
+
+        let admin = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let vesting_data = Pubkey::new_unique();
+        let vesting_schedule = Pubkey::new_unique();
+        let vesting_vault = Pubkey::new_unique();
+        let stake_vault = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+        let beneficiary = Keypair::new();
+        let vesting_vault_authority = vesting_schedule_client::pda::vesting_vault_authority(&admin);
+        let stake_authority = Pubkey::find_program_address(&[STAKE_AUTHORITY_TAG], &mock_consumer::ID).0;
+        let position = Pubkey::find_program_address(&[POSITION_TAG, beneficiary.pubkey().as_ref()], &mock_consumer::ID).0;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+        let tge_timestamp = now - 3 * MONTH;
+
+        let mut mint_data = vec![0; spl_token::state::Mint::LEN];
+        let mint_state = spl_token::state::Mint {
+            supply: PLANNED_TOKENS,
+            decimals: 9,
+            is_initialized: true,
+            ..Default::default()
+        };
+        spl_token::state::Mint::pack(mint_state, &mut mint_data).unwrap();
+        program_test.add_account(mint, program_account(&spl_token::ID, mint_data));
+        program_test.add_account(vesting_vault, token_account(&mint, &vesting_vault_authority.key, PLANNED_TOKENS));
+        program_test.add_account(stake_vault, token_account(&mint, &stake_authority, 0));
+        program_test.add_account(destination, token_account(&mint, &beneficiary.pubkey(), 0));
+
+        let mut data = Vec::new();
+        VestingData {
+            admin,
+            vesting_schedule,
+            tge_timestamp: tge_timestamp as u64,
+            vesting_vault,
+            vesting_vault_authority: vesting_vault_authority.key,
+            vesting_vault_authority_seed: vesting_vault_authority.seed,
+            staking_program: mock_consumer::ID,
+            ..Default::default()
+        }
+        .try_serialize(&mut data)
+        .unwrap();
+        program_test.add_account(vesting_data, program_account(&vesting_schedule::ID, data));
+
+        let mut schedule = VestingScheduleBuf::new(300);
+        schedule.borrow_mut().len = 1;
+        schedule.borrow_mut().data[0] = VestingUser::new(20, beneficiary.pubkey(), 12, PLANNED_TOKENS);
+        program_test.add_account(vesting_schedule, program_account(&vesting_schedule::ID, schedule.account_data().to_vec()));
+
+        let mut data = Vec::new();
+        Position { beneficiary: beneficiary.pubkey(), ..Default::default() }
+            .try_serialize(&mut data)
+            .unwrap();
+        program_test.add_account(position, program_account(&mock_consumer::ID, data));
+
+        let deposit = Pubkey::find_program_address(&[DEPOSIT_TAG, vesting_vault_authority.key.as_ref()], &mock_consumer::ID).0;
+        let mut data = Vec::new();
+        StakeDeposit { depositor: vesting_vault_authority.key, ..Default::default() }
+            .try_serialize(&mut data)
+            .unwrap();
+        program_test.add_account(deposit, program_account(&mock_consumer::ID, data));
+
+        Fixture {
+            context: program_test.start_with_context().await,
+            admin,
+            vesting_data,
+            vesting_schedule,
+            vesting_vault,
+            beneficiary,
+            stake_vault,
+            destination,
+            now: tge_timestamp + 3 * MONTH + 30,
+        }
+    }
+
+    async fn process(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> Result<(), String> {
+        let mut clock: Clock = self.context.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp = self.now;
+        self.context.set_sysvar(&clock);
+
+        let mut all_signers = vec![&self.context.payer];
+        all_signers.extend_from_slice(signers);
+        let recent_blockhash = self.context.banks_client.get_latest_blockhash().await.unwrap();
+        let transaction =
+            Transaction::new_signed_with_payer(instructions, Some(&self.context.payer.pubkey()), &all_signers, recent_blockhash);
+        self.context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .map_err(|err| err.to_string())
+    }
+
+    async fn account_data(&mut self, address: Pubkey) -> Vec<u8> {
+        self.context.banks_client.get_account(address).await.unwrap().unwrap().data
+    }
+
+    async fn token_amount(&mut self, address: Pubkey) -> u64 {
+        spl_token::state::Account::unpack(&self.account_data(address).await).unwrap().amount
+    }
+
+    async fn grant(&mut self) -> VestingUser {
+        accounts::decode_vesting_schedule(&self.account_data(self.vesting_schedule).await).unwrap().borrow().data[0]
+    }
+
+    // Makes the staking program move `skim` tokens less than it is asked to
+    async fn set_skim(&mut self, skim: u64) {
+        let address = self.deposit();
+        let mut deposit = StakeDeposit::try_deserialize(&mut &self.account_data(address).await[..]).unwrap();
+        deposit.skim = skim;
+        let mut data = Vec::new();
+        deposit.try_serialize(&mut data).unwrap();
+        self.context.set_account(&address, &program_account(&mock_consumer::ID, data).into());
+    }
+
+    fn deposit(&self) -> Pubkey {
+        let depositor = vesting_schedule_client::pda::vesting_vault_authority(&self.admin).key;
+        Pubkey::find_program_address(&[DEPOSIT_TAG, depositor.as_ref()], &mock_consumer::ID).0
+    }
+
+    // What mock_consumer's deposit and withdraw need after the depositor and the token account
+    fn staking_accounts(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.deposit(), false),
+            AccountMeta::new(self.stake_vault, false),
+            AccountMeta::new_readonly(Pubkey::find_program_address(&[STAKE_AUTHORITY_TAG], &mock_consumer::ID).0, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+        ]
+    }
+
+    fn stake_grant(&self, staking_program: &Pubkey, amount: u64) -> Instruction {
+        instructions::stake_grant(
+            &self.beneficiary.pubkey(),
+            &self.admin,
+            &self.vesting_data,
+            &self.vesting_schedule,
+            &self.vesting_vault,
+            staking_program,
+            &self.staking_accounts(),
+            0,
+            amount,
+            None,
+        )
+    }
+
+    fn unstake_grant(&self, authority: &Pubkey, amount: u64) -> Instruction {
+        instructions::unstake_grant(
+            authority,
+            &self.admin,
+            &self.vesting_data,
+            &self.vesting_schedule,
+            &self.vesting_vault,
+            &mock_consumer::ID,
+            &self.staking_accounts(),
+            0,
+            amount,
+            None,
+        )
+    }
+
+    fn claim_to(&self) -> Instruction {
+        instructions::claim_to(
+            &self.beneficiary.pubkey(),
+            &self.admin,
+            &self.vesting_data,
+            &self.vesting_schedule,
+            &self.vesting_vault,
+            &self.destination,
+            0,
+            None,
+        )
+    }
+
+    fn claim_from_stake(&self) -> Instruction {
+        instructions::claim_from_stake(
+            &self.beneficiary.pubkey(),
+            &self.admin,
+            &self.vesting_data,
+            &self.vesting_schedule,
+            &self.vesting_vault,
+            &self.destination,
+            &mock_consumer::ID,
+            &self.staking_accounts(),
+            0,
+            None,
+        )
+    }
+
+    fn position(&self) -> Pubkey {
+        Pubkey::find_program_address(&[POSITION_TAG, self.beneficiary.pubkey().as_ref()], &mock_consumer::ID).0
+    }
+
+    fn record_locked_balance(&self, beneficiary: &Pubkey, index: u64) -> Instruction {
+        Instruction {
+            program_id: mock_consumer::ID,
+            accounts: mock_consumer::accounts::RecordLockedBalance {
+                beneficiary: *beneficiary,
+                position: Pubkey::find_program_address(&[POSITION_TAG, beneficiary.as_ref()], &mock_consumer::ID).0,
+                vesting_data: self.vesting_data,
+                vesting_schedule: self.vesting_schedule,
+                vesting_program: vesting_schedule::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: mock_consumer::instruction::RecordLockedBalance { index }.data(),
+        }
+    }
+
+    fn stake_claimed(&self, index: u64) -> Instruction {
+        let admin = self.admin;
+        Instruction {
+            program_id: mock_consumer::ID,
+            accounts: mock_consumer::accounts::StakeClaimed {
+                beneficiary: self.beneficiary.pubkey(),
+                position: self.position(),
+                stake_authority: Pubkey::find_program_address(&[STAKE_AUTHORITY_TAG], &mock_consumer::ID).0,
+                stake_vault: self.stake_vault,
+                admin,
+                vesting_data: self.vesting_data,
+                vesting_schedule: self.vesting_schedule,
+                vesting_vault: self.vesting_vault,
+                vesting_vault_authority: vesting_schedule_client::pda::vesting_vault_authority(&admin).key,
+                token_program: spl_token::ID,
+                vesting_program: vesting_schedule::ID,
+            }
+            .to_account_metas(None),
+            data: mock_consumer::instruction::StakeClaimed { index }.data(),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_record_locked_balance_and_stake_claimed() {
+    let mut fixture = Fixture::new().await;
+    let beneficiary = Keypair::from_bytes(&fixture.beneficiary.to_bytes()).unwrap();
+
+    let record = fixture.record_locked_balance(&beneficiary.pubkey(), 0);
+    fixture.process(&[record], &[&beneficiary]).await.unwrap();
+    let position = Position::try_deserialize(&mut &fixture.account_data(fixture.position()).await[..]).unwrap();
+    assert_eq!(beneficiary.pubkey(), position.beneficiary);
+    // 20% at TGE and a quarter of the remaining 80% after three of twelve months
+    assert_eq!(600_000, position.locked);
+
+    let stake = fixture.stake_claimed(0);
+    fixture.process(&[stake], &[&beneficiary]).await.unwrap();
+    let position = Position::try_deserialize(&mut &fixture.account_data(fixture.position()).await[..]).unwrap();
+    assert_eq!(400_000, position.staked);
+    let stake_vault = spl_token::state::Account::unpack(&fixture.account_data(fixture.stake_vault).await).unwrap();
+    assert_eq!(400_000, stake_vault.amount);
+    let schedule = accounts::decode_vesting_schedule(&fixture.account_data(fixture.vesting_schedule).await).unwrap();
+    assert_eq!(400_000, { schedule.borrow().data[0].claimed_tokens });
+}
+
+#[tokio::test]
+async fn test_cpi_entry_points_check_the_beneficiary() {
+    let mut fixture = Fixture::new().await;
+    let stranger = Keypair::new();
+
+    // slot 0 belongs to someone else, and slot 1 is empty
+    let get_locked_balance =
+        instructions::get_locked_balance(&stranger.pubkey(), &fixture.vesting_data, &fixture.vesting_schedule, 0);
+    assert!(fixture.process(&[get_locked_balance], &[]).await.is_err());
+    let beneficiary = Keypair::from_bytes(&fixture.beneficiary.to_bytes()).unwrap();
+    let record = fixture.record_locked_balance(&beneficiary.pubkey(), 1);
+    assert!(fixture.process(&[record], &[&beneficiary]).await.is_err());
+
+    let claim_to = instructions::claim_to(
+        &stranger.pubkey(),
+        &fixture.admin,
+        &fixture.vesting_data,
+        &fixture.vesting_schedule,
+        &fixture.vesting_vault,
+        &fixture.stake_vault,
+        0,
+        None,
+    );
+    assert!(fixture.process(&[claim_to], &[&stranger]).await.is_err());
+    let vesting_vault = spl_token::state::Account::unpack(&fixture.account_data(fixture.vesting_vault).await).unwrap();
+    assert_eq!(PLANNED_TOKENS, vesting_vault.amount);
+}
+
+#[tokio::test]
+async fn test_stake_locked_tokens_and_claim_from_stake() {
+    let mut fixture = Fixture::new().await;
+    let beneficiary = Keypair::from_bytes(&fixture.beneficiary.to_bytes()).unwrap();
+
+    // 600_000 are locked after three months, 500_000 of them go to the staking program
+    let stake = fixture.stake_grant(&mock_consumer::ID, 500_000);
+    fixture.process(&[stake], &[&beneficiary]).await.unwrap();
+    assert_eq!(500_000, fixture.token_amount(fixture.stake_vault).await);
+    assert_eq!(500_000, fixture.token_amount(fixture.vesting_vault).await);
+    assert_eq!(500_000, { fixture.grant().await.staked_tokens });
+    let deposit = StakeDeposit::try_deserialize(&mut &fixture.account_data(fixture.deposit()).await[..]).unwrap();
+    assert_eq!(500_000, deposit.amount);
+    let stake = fixture.stake_grant(&mock_consumer::ID, 100_001);
+    assert!(fixture.process(&[stake], &[&beneficiary]).await.is_err());
+
+    // the vested 400_000 are still in the vault
+    let claim_to = fixture.claim_to();
+    fixture.process(&[claim_to], &[&beneficiary]).await.unwrap();
+    assert_eq!(400_000, fixture.token_amount(fixture.destination).await);
+
+    // fully vested, the vault only holds 100_000 of the remaining 600_000
+    fixture.now += 10 * MONTH;
+    // a new bank, or the repeated claim_to is taken for the processed one
+    fixture.context.warp_to_slot(100).unwrap();
+    let claim_to = fixture.claim_to();
+    assert!(fixture.process(&[claim_to], &[&beneficiary]).await.is_err());
+    let claim_from_stake = fixture.claim_from_stake();
+    fixture.process(&[claim_from_stake], &[&beneficiary]).await.unwrap();
+
+    assert_eq!(PLANNED_TOKENS, fixture.token_amount(fixture.destination).await);
+    assert_eq!(0, fixture.token_amount(fixture.vesting_vault).await);
+    assert_eq!(0, fixture.token_amount(fixture.stake_vault).await);
+    let grant = fixture.grant().await;
+    assert_eq!((PLANNED_TOKENS, 0), ({ grant.claimed_tokens }, { grant.staked_tokens }));
+    let vesting_data = accounts::decode_vesting_data(&fixture.account_data(fixture.vesting_data).await).unwrap();
+    assert_eq!((PLANNED_TOKENS, 0), (vesting_data.total_issued_so_far, vesting_data.total_staked));
+}
+
+#[tokio::test]
+async fn test_unstake_and_staking_program_whitelist() {
+    let mut fixture = Fixture::new().await;
+    let beneficiary = Keypair::from_bytes(&fixture.beneficiary.to_bytes()).unwrap();
+    let stranger = Keypair::new();
+
+    // any other executable is turned away
+    let stake = fixture.stake_grant(&spl_token::ID, 300_000);
+    assert!(fixture.process(&[stake], &[&beneficiary]).await.is_err());
+    let stake = fixture.stake_grant(&mock_consumer::ID, 300_000);
+    fixture.process(&[stake], &[&beneficiary]).await.unwrap();
+
+    let unstake = fixture.unstake_grant(&stranger.pubkey(), 100_000);
+    assert!(fixture.process(&[unstake], &[&stranger]).await.is_err());
+    let unstake = fixture.unstake_grant(&beneficiary.pubkey(), 100_000);
+    fixture.process(&[unstake], &[&beneficiary]).await.unwrap();
+    assert_eq!(200_000, fixture.token_amount(fixture.stake_vault).await);
+    assert_eq!(800_000, fixture.token_amount(fixture.vesting_vault).await);
+    assert_eq!(200_000, { fixture.grant().await.staked_tokens });
+
+    let unstake = fixture.unstake_grant(&beneficiary.pubkey(), 200_001);
+    assert!(fixture.process(&[unstake], &[&beneficiary]).await.is_err());
+}
+
+#[tokio::test]
+async fn test_staking_program_moving_the_wrong_amount() {
+    let mut fixture = Fixture::new().await;
+    let beneficiary = Keypair::from_bytes(&fixture.beneficiary.to_bytes()).unwrap();
+    let mismatch = match ProgramError::from(vesting_schedule::ErrorCode::StakeTransferMismatch) {
+        ProgramError::Custom(code) => format!("custom program error: {:#x}", code),
+        error => panic!("not a program error code: {}", error),
+    };
+
+    fixture.set_skim(1).await;
+    let stake = fixture.stake_grant(&mock_consumer::ID, 300_000);
+    let error = fixture.process(&[stake], &[&beneficiary]).await.unwrap_err();
+    assert!(error.contains(&mismatch), "{}", error);
+    assert_eq!(PLANNED_TOKENS, fixture.token_amount(fixture.vesting_vault).await);
+    assert_eq!(0, { fixture.grant().await.staked_tokens });
+
+    fixture.set_skim(0).await;
+    let stake = fixture.stake_grant(&mock_consumer::ID, 200_000);
+    fixture.process(&[stake], &[&beneficiary]).await.unwrap();
+
+    // short on the way back, into the vault and into a claim
+    fixture.set_skim(1).await;
+    let unstake = fixture.unstake_grant(&beneficiary.pubkey(), 100_000);
+    let error = fixture.process(&[unstake], &[&beneficiary]).await.unwrap_err();
+    assert!(error.contains(&mismatch), "{}", error);
+    fixture.now += 10 * MONTH;
+    let claim_from_stake = fixture.claim_from_stake();
+    let error = fixture.process(&[claim_from_stake], &[&beneficiary]).await.unwrap_err();
+    assert!(error.contains(&mismatch), "{}", error);
+
+    assert_eq!(200_000, fixture.token_amount(fixture.stake_vault).await);
+    assert_eq!(0, fixture.token_amount(fixture.destination).await);
+    let grant = fixture.grant().await;
+    assert_eq!((0, 200_000), ({ grant.claimed_tokens }, { grant.staked_tokens }));
+}
@@ -0,0 +1,43 @@
+use crate::{vesting_operations, Accelerate, GrantAccelerated, ScheduleAccelerated, VestingScheduleMut};
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<Accelerate>, index: Option<u64>, bps: u16) -> ProgramResult {
+    let mut data = ctx.accounts.vesting_schedule.try_borrow_mut_data()?;
+    let schedule = &mut VestingScheduleMut::load(&mut data)?;
+    let timestamp = Clock::get()?.unix_timestamp;
+    let now = vesting_operations::vesting_clock(&ctx.accounts.vesting_data, timestamp as u64);
+    let tge_timestamp = ctx.accounts.vesting_data.tge_timestamp;
+
+    match index {
+        Some(index) => {
+            let accelerated = vesting_operations::accelerate_grant(
+                schedule,
+                index as usize,
+                bps,
+                tge_timestamp,
+                now,
+            )?;
+            let grant = schedule.data[index as usize];
+            emit!(GrantAccelerated {
+                index,
+                user: grant.user,
+                bps,
+                accelerated_tokens: accelerated,
+                total_accelerated_tokens: grant.accelerated_tokens,
+                timestamp,
+            });
+        }
+        None => {
+            let (grants, accelerated) =
+                vesting_operations::accelerate_schedule(schedule, bps, tge_timestamp, now)?;
+            emit!(ScheduleAccelerated {
+                bps,
+                grants,
+                accelerated_tokens: accelerated,
+                timestamp,
+            });
+        }
+    }
+
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use crate::{vesting_operations, AddUser, VestingUser};
+use crate::{vesting_operations, AddUser, VestingScheduleMut, VestingUser};
 use anchor_lang::prelude::*;
 
 pub fn process(
@@ -15,10 +15,8 @@ pub fn process(
         planned_tokens,
     );
 
-    vesting_operations::add_user_active(
-        active_user,
-        &mut ctx.accounts.vesting_schedule.load_mut()?,
-    )?;
+    let mut data = ctx.accounts.vesting_schedule.try_borrow_mut_data()?;
+    vesting_operations::add_user_active(active_user, &mut VestingScheduleMut::load(&mut data)?)?;
 
     Ok(())
 }
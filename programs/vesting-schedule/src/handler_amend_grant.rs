@@ -0,0 +1,45 @@
+use crate::{vesting_operations, AmendGrant, GrantAmended, VestingScheduleMut};
+use anchor_lang::prelude::*;
+
+pub fn process(
+    ctx: Context<AmendGrant>,
+    index: u64,
+    unlocked_at_tge: u8,
+    unlocking_period: u8,
+    planned_tokens: u64,
+) -> ProgramResult {
+    let mut data = ctx.accounts.vesting_schedule.try_borrow_mut_data()?;
+    let schedule = &mut VestingScheduleMut::load(&mut data)?;
+    let now = Clock::get()?.unix_timestamp;
+    let beneficiary = &ctx.accounts.beneficiary;
+    let beneficiary_signed = beneficiary.is_signer
+        && schedule.data.get(index as usize).map(|grant| grant.user) == Some(*beneficiary.key);
+
+    let previous = vesting_operations::amend_grant(
+        schedule,
+        index as usize,
+        unlocked_at_tge,
+        unlocking_period,
+        planned_tokens,
+        beneficiary_signed,
+        ctx.accounts.vesting_data.tge_timestamp,
+        vesting_operations::vesting_clock(&ctx.accounts.vesting_data, now as u64),
+    )?;
+
+    let amended = schedule.data[index as usize];
+    emit!(GrantAmended {
+        index,
+        user: amended.user,
+        amendment_count: amended.amendment_count,
+        old_planned_tokens: previous.planned_tokens,
+        new_planned_tokens: amended.planned_tokens,
+        old_unlocked_at_tge: previous.unlocked_at_tge,
+        new_unlocked_at_tge: amended.unlocked_at_tge,
+        old_unlocking_period: previous.unlocking_period,
+        new_unlocking_period: amended.unlocking_period,
+        claimed_tokens: amended.claimed_tokens,
+        timestamp: now,
+    });
+
+    Ok(())
+}
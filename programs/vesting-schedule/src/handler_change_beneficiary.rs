@@ -0,0 +1,26 @@
+use crate::{utils, vesting_operations, BeneficiaryChanged, ChangeBeneficiary, ErrorCode, VestingScheduleMut};
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<ChangeBeneficiary>, index: u64, new_beneficiary: Pubkey) -> ProgramResult {
+    let beneficiary = ctx.accounts.beneficiary.key();
+    let mut data = ctx.accounts.vesting_schedule.try_borrow_mut_data()?;
+    let schedule = &mut VestingScheduleMut::load(&mut data)?;
+    let grant = schedule
+        .data
+        .get_mut(index as usize)
+        .filter(|grant| {
+            grant.status == utils::utils::EventStatus::PendingToken as u8
+                && grant.user == beneficiary
+        })
+        .ok_or(ErrorCode::InvalidInput)?;
+    let old_beneficiary = vesting_operations::change_beneficiary(grant, &ctx.accounts.vesting_data, new_beneficiary)?;
+
+    emit!(BeneficiaryChanged {
+        index,
+        old_beneficiary,
+        new_beneficiary,
+        by_receipt: false,
+    });
+
+    Ok(())
+}
@@ -1,18 +1,28 @@
-use crate::{vesting_operations, Claim};
+use crate::utils::runtime::{SysvarClock, VaultTransfer};
+use crate::{tokenoperation, vesting_operations, Claim, VestingScheduleMut};
 use anchor_lang::prelude::*;
 
-pub fn process(ctx: Context<Claim>,index: u64) -> ProgramResult {
-    let schedule = &mut ctx.accounts.vesting_schedule.load_mut()?;
+pub fn process(ctx: Context<Claim>, index: Option<u64>) -> ProgramResult {
+    let mut data = ctx.accounts.vesting_schedule.try_borrow_mut_data()?;
+    let schedule = &mut VestingScheduleMut::load(&mut data)?;
+    let token_program = ctx.accounts.token_program.to_account_info();
+    let claim_user_ata = ctx.accounts.claim_user_ata.to_account_info();
     let vesting_data = &mut ctx.accounts.vesting_data;
+    let mut ledger = VaultTransfer {
+        owner: &ctx.accounts.owner,
+        to_vault: &claim_user_ata,
+        from_vault: &ctx.accounts.vesting_vault,
+        from_vault_authority: &ctx.accounts.vesting_vault_authority,
+        from_vault_authority_seed: vesting_data.vesting_vault_authority_seed,
+        token_program: &token_program,
+    };
     vesting_operations::claim(
-        schedule, 
-        index, 
+        schedule,
+        index,
         ctx.accounts.claim_user.key(),
-        &ctx.accounts.owner,
-        &ctx.accounts.claim_user_ata,
-        &ctx.accounts.vesting_vault,
-        &ctx.accounts.vesting_vault_authority,
+        tokenoperation::tokenoper::receipt_holding(&ctx.accounts.receipt_account),
         vesting_data,
-        &ctx.accounts.token_program.to_account_info()
+        &SysvarClock,
+        &mut ledger,
     )
 }
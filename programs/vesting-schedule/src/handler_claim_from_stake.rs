@@ -0,0 +1,48 @@
+use crate::{tokenoperation, vesting_operations, ClaimFromStake, ClaimedFromStake, VestingScheduleMut};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::set_return_data;
+
+pub fn process<'info>(ctx: Context<'_, '_, '_, 'info, ClaimFromStake<'info>>, index: u64) -> ProgramResult {
+    let beneficiary = ctx.accounts.beneficiary.key();
+    let mut data = ctx.accounts.vesting_schedule.try_borrow_mut_data()?;
+    let schedule = &mut VestingScheduleMut::load(&mut data)?;
+    let vesting_data = &mut ctx.accounts.vesting_data;
+    let receipt = tokenoperation::tokenoper::receipt_holding(&ctx.accounts.receipt_account);
+    let grant = vesting_operations::beneficiary_grant(schedule, index, beneficiary, receipt)?;
+    let timestamp = Clock::get()?.unix_timestamp as u64;
+    let (from_vault, from_stake) = vesting_operations::claim_grant_from_stake(grant, vesting_data, timestamp)?;
+
+    if from_vault > 0 {
+        tokenoperation::tokenoper::vesting_transfer(
+            from_vault,
+            &ctx.accounts.admin,
+            &ctx.accounts.destination,
+            &ctx.accounts.vesting_vault,
+            &ctx.accounts.vesting_vault_authority,
+            vesting_data.vesting_vault_authority_seed,
+            &ctx.accounts.token_program.to_account_info(),
+        )?;
+    }
+    if from_stake > 0 {
+        tokenoperation::staking::withdraw(
+            from_stake,
+            &ctx.accounts.admin,
+            &ctx.accounts.destination,
+            &ctx.accounts.vesting_vault,
+            &ctx.accounts.vesting_vault_authority,
+            vesting_data.vesting_vault_authority_seed,
+            &ctx.accounts.staking_program,
+            ctx.remaining_accounts,
+        )?;
+    }
+    emit!(ClaimedFromStake {
+        index,
+        beneficiary,
+        destination: ctx.accounts.destination.key(),
+        from_vault,
+        from_stake,
+    });
+
+    set_return_data(&(from_vault + from_stake).to_le_bytes());
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use crate::utils::pda;
+use crate::{tokenoperation, vesting_operations, ClaimMerkle, ErrorCode};
+use anchor_lang::prelude::*;
+
+pub fn process(
+    ctx: Context<ClaimMerkle>,
+    planned_tokens: u64,
+    unlocked_at_tge: u8,
+    unlocking_period: u8,
+    proof: Vec<[u8; 32]>,
+) -> ProgramResult {
+    // init_if_needed does not check the address of an account that already exists
+    let claim_status_pda = pda::make_claim_status_pda_pubkey(
+        &ctx.accounts.vesting_data.key(),
+        ctx.accounts.claim_user.key,
+        ctx.program_id,
+    );
+    if ctx.accounts.claim_status.key() != claim_status_pda.key {
+        return Err(ErrorCode::InvalidInput.into());
+    }
+
+    let claim_status = &mut ctx.accounts.claim_status;
+    let vesting_data = &mut ctx.accounts.vesting_data;
+    vesting_operations::open_merkle_claim_status(
+        claim_status,
+        vesting_data,
+        vesting_data.key(),
+        ctx.accounts.claim_user.key(),
+        planned_tokens,
+        unlocked_at_tge,
+        unlocking_period,
+        &proof,
+    )?;
+
+    let mut grant = claim_status.grant();
+    let timestamp = Clock::get()?.unix_timestamp;
+    let can_claim_now = vesting_operations::claim_grant(&mut grant, vesting_data, timestamp as u64)?;
+    claim_status.claimed_tokens = grant.claimed_tokens;
+
+    msg!("Can Claim Now {}", can_claim_now);
+
+    if can_claim_now > 0 {
+        tokenoperation::tokenoper::vesting_transfer(
+            can_claim_now,
+            &ctx.accounts.admin,
+            &ctx.accounts.claim_user_ata,
+            &ctx.accounts.vesting_vault,
+            &ctx.accounts.vesting_vault_authority,
+            vesting_data.vesting_vault_authority_seed,
+            &ctx.accounts.token_program.to_account_info(),
+        )?;
+    }
+
+    Ok(())
+}
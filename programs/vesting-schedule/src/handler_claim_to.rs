@@ -0,0 +1,36 @@
+use crate::{tokenoperation, vesting_operations, ClaimTo, ClaimedTo, VestingScheduleMut};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::set_return_data;
+
+pub fn process(ctx: Context<ClaimTo>, index: u64) -> ProgramResult {
+    let beneficiary = ctx.accounts.beneficiary.key();
+    let mut data = ctx.accounts.vesting_schedule.try_borrow_mut_data()?;
+    let schedule = &mut VestingScheduleMut::load(&mut data)?;
+    let vesting_data = &mut ctx.accounts.vesting_data;
+    // unlike claim this fails loudly, a CPI caller has to know nothing was transferred
+    let receipt = tokenoperation::tokenoper::receipt_holding(&ctx.accounts.receipt_account);
+    let grant = vesting_operations::beneficiary_grant(schedule, index, beneficiary, receipt)?;
+    let timestamp = Clock::get()?.unix_timestamp as u64;
+    let amount = vesting_operations::claim_grant_from_vault(grant, vesting_data, timestamp)?;
+
+    if amount > 0 {
+        tokenoperation::tokenoper::vesting_transfer(
+            amount,
+            &ctx.accounts.admin,
+            &ctx.accounts.destination,
+            &ctx.accounts.vesting_vault,
+            &ctx.accounts.vesting_vault_authority,
+            vesting_data.vesting_vault_authority_seed,
+            &ctx.accounts.token_program.to_account_info(),
+        )?;
+    }
+    emit!(ClaimedTo {
+        index,
+        beneficiary,
+        destination: ctx.accounts.destination.key(),
+        amount,
+    });
+
+    set_return_data(&amount.to_le_bytes());
+    Ok(())
+}
@@ -0,0 +1,18 @@
+use crate::{utils, vesting_operations, ErrorCode, GetClaimable, VestingScheduleRef};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::set_return_data;
+
+pub fn process(ctx: Context<GetClaimable>, index: u64) -> ProgramResult {
+    let data = ctx.accounts.vesting_schedule.try_borrow_data()?;
+    let schedule = VestingScheduleRef::load(&data)?;
+    let grant = schedule
+        .data
+        .get(index as usize)
+        .filter(|grant| grant.status != utils::utils::EventStatus::Inactive as u8)
+        .ok_or(ErrorCode::InvalidInput)?;
+    let timestamp = Clock::get()?.unix_timestamp as u64;
+    let view = vesting_operations::claimable_view(grant, &ctx.accounts.vesting_data, timestamp)?;
+
+    set_return_data(&view.try_to_vec()?);
+    Ok(())
+}
@@ -0,0 +1,22 @@
+use crate::{utils, vesting_operations, ErrorCode, GetLockedBalance, VestingScheduleRef};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::set_return_data;
+
+pub fn process(ctx: Context<GetLockedBalance>, index: u64) -> ProgramResult {
+    let data = ctx.accounts.vesting_schedule.try_borrow_data()?;
+    let schedule = VestingScheduleRef::load(&data)?;
+    // callers pass the beneficiary so a wrong index cannot report someone else's balance
+    let grant = schedule
+        .data
+        .get(index as usize)
+        .filter(|grant| {
+            grant.status != utils::utils::EventStatus::Inactive as u8
+                && grant.user == ctx.accounts.beneficiary.key()
+        })
+        .ok_or(ErrorCode::InvalidInput)?;
+    let timestamp = Clock::get()?.unix_timestamp as u64;
+    let balance = vesting_operations::locked_balance(grant, &ctx.accounts.vesting_data, timestamp)?;
+
+    set_return_data(&balance.try_to_vec()?);
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use crate::utils::layout;
+use crate::{ErrorCode, GrowSchedule, ScheduleGrown, VestingScheduleRef};
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<GrowSchedule>, additional_grants: u64) -> ProgramResult {
+    if additional_grants == 0 || additional_grants > layout::GROWTH_CHUNK {
+        return Err(ErrorCode::InvalidInput.into());
+    }
+    let account = &ctx.accounts.vesting_schedule;
+    let capacity = VestingScheduleRef::load(&account.try_borrow_data()?)?.capacity;
+    let new_capacity = capacity.checked_add(additional_grants).ok_or(ErrorCode::InvalidInput)?;
+
+    layout::resize(
+        account,
+        layout::schedule_size(new_capacity),
+        &ctx.accounts.admin,
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+    layout::grow(&mut account.try_borrow_mut_data()?, capacity, new_capacity);
+    emit!(ScheduleGrown { capacity: new_capacity });
+
+    Ok(())
+}
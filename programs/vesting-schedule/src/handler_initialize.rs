@@ -1,6 +1,6 @@
 use super::*;
 use crate::utils::pda::VestingPdaAddress;
-use crate::{utils, vesting_operations, Initialize};
+use crate::{utils, vesting_operations, Initialize, VestingScheduleMut};
 
 pub fn process(ctx: Context<Initialize>, tge_timestamp: u64) -> ProgramResult {
     let pda_vesting_vault = utils::pda::make_vesting_pda_pubkey(
@@ -11,10 +11,22 @@ pub fn process(ctx: Context<Initialize>, tge_timestamp: u64) -> ProgramResult {
 
     transfer_set_authority(&ctx, &pda_vesting_vault);
 
+    let vesting_schedule = &ctx.accounts.vesting_schedule;
+    if !Rent::get()?.is_exempt(vesting_schedule.lamports(), vesting_schedule.data_len()) {
+        return Err(ProgramError::AccountNotRentExempt);
+    }
+    let mut data = vesting_schedule.try_borrow_mut_data()?;
+    let schedule = &mut VestingScheduleMut::init(&mut data)?;
     let vesting_data = &mut ctx.accounts.vesting_data;
-    let schedule = &mut ctx.accounts.vesting_schedule.load_init()?;
 
-    vesting_operations::initialize_vesting_data(vesting_data, ctx.accounts.vesting_vault.key(), pda_vesting_vault, tge_timestamp);
+    vesting_operations::initialize_vesting_data(
+        vesting_data,
+        ctx.accounts.admin.key(),
+        ctx.accounts.vesting_schedule.key(),
+        ctx.accounts.vesting_vault.key(),
+        pda_vesting_vault,
+        tge_timestamp,
+    );
     vesting_operations::initialize_users(schedule);
 
     Ok(())
@@ -0,0 +1,77 @@
+use crate::utils::layout::AccountLayout;
+use crate::utils::{layout, utils};
+use crate::{ErrorCode, LayoutMigrated, MerkleClaimStatus, Migrate, VestingData, VestingSchedule};
+use anchor_lang::prelude::*;
+use anchor_lang::{AccountDeserialize, AccountSerialize, Discriminator};
+
+pub fn process<'info>(ctx: Context<'_, '_, '_, 'info, Migrate<'info>>) -> ProgramResult {
+    let accounts = &ctx.accounts;
+    let data_version = layout::version_of(&accounts.vesting_data.try_borrow_data()?, AccountLayout::VestingData)
+        .ok_or(ErrorCode::InvalidInput)?;
+    let upgraded = layout::upgrade(&accounts.vesting_data.try_borrow_data()?, AccountLayout::VestingData)
+        .ok_or(ErrorCode::InvalidInput)?;
+    let vesting_data = VestingData::try_deserialize(&mut &upgraded[..])?;
+    if data_version == 1 {
+        // v1 kept neither the admin nor the schedule. The vault authority was derived from the admin,
+        // and a schedule that is not v1 any more already belongs to some vesting data.
+        let authority = Pubkey::create_program_address(
+            &[
+                accounts.admin.key.as_ref(),
+                utils::TOKEN_MINT_TAG.as_bytes(),
+                &[vesting_data.vesting_vault_authority_seed],
+            ],
+            ctx.program_id,
+        )
+        .map_err(|_| ErrorCode::InvalidInput)?;
+        let schedule_version =
+            layout::version_of(&accounts.vesting_schedule.try_borrow_data()?, AccountLayout::VestingSchedule);
+        if authority != vesting_data.vesting_vault_authority || schedule_version != Some(1) {
+            return Err(ErrorCode::InvalidInput.into());
+        }
+    } else if vesting_data.admin != accounts.admin.key() || vesting_data.vesting_schedule != accounts.vesting_schedule.key() {
+        return Err(ErrorCode::InvalidInput.into());
+    }
+    if accounts.vesting_schedule.try_borrow_data()?[..8] != VestingSchedule::discriminator() {
+        return Err(ErrorCode::InvalidInput.into());
+    }
+
+    migrate(&accounts.vesting_data, AccountLayout::VestingData, &ctx)?;
+    if data_version == 1 {
+        let mut data = accounts.vesting_data.try_borrow_mut_data()?;
+        let mut vesting_data = VestingData::try_deserialize(&mut &data[..])?;
+        vesting_data.admin = accounts.admin.key();
+        vesting_data.vesting_schedule = accounts.vesting_schedule.key();
+        vesting_data.try_serialize(&mut &mut data[..])?;
+    }
+    migrate(&accounts.vesting_schedule, AccountLayout::VestingSchedule, &ctx)?;
+    for claim_status in ctx.remaining_accounts {
+        let upgraded = layout::upgrade(&claim_status.try_borrow_data()?, AccountLayout::MerkleClaimStatus)
+            .ok_or(ErrorCode::InvalidInput)?;
+        if claim_status.owner != ctx.program_id
+            || MerkleClaimStatus::try_deserialize(&mut &upgraded[..])?.vesting_data != accounts.vesting_data.key()
+        {
+            return Err(ErrorCode::InvalidInput.into());
+        }
+        migrate(claim_status, AccountLayout::MerkleClaimStatus, &ctx)?;
+    }
+
+    Ok(())
+}
+
+fn migrate<'info>(account: &AccountInfo<'info>, account_layout: AccountLayout, ctx: &Context<'_, '_, '_, 'info, Migrate<'info>>) -> ProgramResult {
+    let from_version = layout::migrate(
+        account,
+        account_layout,
+        &ctx.accounts.admin,
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+    if from_version != layout::LAYOUT_VERSION {
+        emit!(LayoutMigrated {
+            account: account.key(),
+            from_version,
+            to_version: layout::LAYOUT_VERSION,
+        });
+    }
+
+    Ok(())
+}
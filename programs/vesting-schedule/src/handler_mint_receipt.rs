@@ -0,0 +1,35 @@
+use crate::{tokenoperation, utils, vesting_operations, ErrorCode, MintReceipt, ReceiptMinted, VestingScheduleMut};
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<MintReceipt>, index: u64) -> ProgramResult {
+    let beneficiary = ctx.accounts.beneficiary.key();
+    let receipt_mint = ctx.accounts.receipt_mint.key();
+    let mut data = ctx.accounts.vesting_schedule.try_borrow_mut_data()?;
+    let schedule = &mut VestingScheduleMut::load(&mut data)?;
+    let vesting_data = &ctx.accounts.vesting_data;
+    let grant = schedule
+        .data
+        .get_mut(index as usize)
+        .filter(|grant| {
+            grant.status == utils::utils::EventStatus::PendingToken as u8
+                && grant.user == beneficiary
+        })
+        .ok_or(ErrorCode::InvalidInput)?;
+    vesting_operations::attach_receipt(grant, vesting_data, receipt_mint)?;
+
+    tokenoperation::tokenoper::mint_receipt(
+        &ctx.accounts.admin,
+        &ctx.accounts.receipt_mint.to_account_info(),
+        &ctx.accounts.receipt_account.to_account_info(),
+        &ctx.accounts.vesting_vault_authority,
+        vesting_data.vesting_vault_authority_seed,
+        &ctx.accounts.token_program.to_account_info(),
+    )?;
+    emit!(ReceiptMinted {
+        index,
+        user: beneficiary,
+        receipt_mint,
+    });
+
+    Ok(())
+}
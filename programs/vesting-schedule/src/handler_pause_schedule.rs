@@ -0,0 +1,9 @@
+use crate::{vesting_operations, PauseSchedule};
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<PauseSchedule>, stop_clock: bool) -> ProgramResult {
+    let now = Clock::get()?.unix_timestamp;
+    vesting_operations::pause_schedule(&mut ctx.accounts.vesting_data, stop_clock, now as u64)?;
+
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use crate::{tokenoperation, vesting_operations, ExpiredReclaimed, ReclaimExpired, VestingScheduleMut};
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<ReclaimExpired>, index: Option<u64>) -> ProgramResult {
+    let mut data = ctx.accounts.vesting_schedule.try_borrow_mut_data()?;
+    let schedule = &mut VestingScheduleMut::load(&mut data)?;
+    let vesting_data = &mut ctx.accounts.vesting_data;
+    let timestamp = Clock::get()?.unix_timestamp;
+
+    let (grants, reclaimed) = match index {
+        Some(index) => (
+            1,
+            vesting_operations::expire_grant(schedule, index as usize, vesting_data, timestamp as u64)?,
+        ),
+        None => vesting_operations::expire_schedule(schedule, vesting_data, timestamp as u64)?,
+    };
+
+    if reclaimed > 0 {
+        tokenoperation::tokenoper::vesting_transfer(
+            reclaimed,
+            &ctx.accounts.admin,
+            &ctx.accounts.treasury,
+            &ctx.accounts.vesting_vault,
+            &ctx.accounts.vesting_vault_authority,
+            vesting_data.vesting_vault_authority_seed,
+            &ctx.accounts.token_program.to_account_info(),
+        )?;
+    }
+
+    emit!(ExpiredReclaimed {
+        grants,
+        reclaimed_tokens: reclaimed,
+        treasury: ctx.accounts.treasury.key(),
+        timestamp,
+    });
+
+    Ok(())
+}
@@ -1,11 +1,11 @@
-use crate::{vesting_operations, RemoveUser};
+use crate::{vesting_operations, RemoveUser, VestingScheduleMut};
 use anchor_lang::prelude::*;
 
-pub fn process(ctx: Context<RemoveUser>, index: u64) -> ProgramResult {
-    vesting_operations::remove_user_active(
-        &mut ctx.accounts.vesting_schedule.load_mut()?,
-        index as usize,
-    );
+pub fn process(ctx: Context<RemoveUser>, beneficiary: Pubkey, index: Option<u64>) -> ProgramResult {
+    let mut data = ctx.accounts.vesting_schedule.try_borrow_mut_data()?;
+    let schedule = &mut VestingScheduleMut::load(&mut data)?;
+    let index = vesting_operations::find_grant(schedule.data, &beneficiary, index, true)?;
+    vesting_operations::remove_user_active(schedule, index)?;
 
     Ok(())
 }
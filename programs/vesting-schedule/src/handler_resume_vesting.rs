@@ -0,0 +1,34 @@
+use crate::{vesting_operations, SuspendVesting, VestingResumed, VestingScheduleMut};
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<SuspendVesting>, index: u64) -> ProgramResult {
+    let mut data = ctx.accounts.vesting_schedule.try_borrow_mut_data()?;
+    let schedule = &mut VestingScheduleMut::load(&mut data)?;
+    let now = vesting_operations::vesting_clock(
+        &ctx.accounts.vesting_data,
+        Clock::get()?.unix_timestamp as u64,
+    );
+    let suspended_at = schedule
+        .data
+        .get(index as usize)
+        .map(|grant| grant.suspended_at)
+        .unwrap_or_default();
+
+    vesting_operations::resume_vesting(
+        schedule,
+        index as usize,
+        ctx.accounts.vesting_data.tge_timestamp,
+        now,
+    )?;
+
+    let grant = schedule.data[index as usize];
+    emit!(VestingResumed {
+        index,
+        user: grant.user,
+        suspended_at,
+        resumed_at: now,
+        suspended_seconds: grant.suspended_seconds,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,19 @@
+use crate::{vesting_operations, SetClaimDeadline, VestingScheduleMut};
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<SetClaimDeadline>, index: Option<u64>, claim_deadline: u64) -> ProgramResult {
+    match index {
+        Some(index) => vesting_operations::set_grant_claim_deadline(
+            &mut VestingScheduleMut::load(&mut ctx.accounts.vesting_schedule.try_borrow_mut_data()?)?,
+            index as usize,
+            claim_deadline,
+            ctx.accounts.vesting_data.tge_timestamp,
+        )?,
+        None => vesting_operations::set_schedule_claim_deadline(
+            &mut ctx.accounts.vesting_data,
+            claim_deadline,
+        )?,
+    }
+
+    Ok(())
+}
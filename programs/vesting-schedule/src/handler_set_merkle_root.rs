@@ -0,0 +1,8 @@
+use crate::{vesting_operations, SetMerkleRoot};
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<SetMerkleRoot>, merkle_root: [u8; 32]) -> ProgramResult {
+    vesting_operations::set_merkle_root(&mut ctx.accounts.vesting_data, merkle_root)?;
+
+    Ok(())
+}
@@ -0,0 +1,8 @@
+use crate::{vesting_operations, SetStakingProgram};
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<SetStakingProgram>, staking_program: Pubkey) -> ProgramResult {
+    vesting_operations::set_staking_program(&mut ctx.accounts.vesting_data, staking_program)?;
+
+    Ok(())
+}
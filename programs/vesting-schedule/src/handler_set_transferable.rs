@@ -0,0 +1,8 @@
+use crate::SetTransferable;
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<SetTransferable>, transferable: bool) -> ProgramResult {
+    ctx.accounts.vesting_data.transferable = transferable;
+
+    Ok(())
+}
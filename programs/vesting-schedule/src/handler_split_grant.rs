@@ -0,0 +1,30 @@
+use crate::{tokenoperation, vesting_operations, GrantSplit, SplitGrant, VestingScheduleMut};
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<SplitGrant>, index: u64, split_tokens: u64, new_beneficiary: Pubkey) -> ProgramResult {
+    let beneficiary = ctx.accounts.beneficiary.key();
+    let mut data = ctx.accounts.vesting_schedule.try_borrow_mut_data()?;
+    let schedule = &mut VestingScheduleMut::load(&mut data)?;
+    let receipt = tokenoperation::tokenoper::receipt_holding(&ctx.accounts.receipt_account);
+    vesting_operations::beneficiary_grant(schedule, index, beneficiary, receipt)?;
+    let timestamp = Clock::get()?.unix_timestamp as u64;
+    let new_index = vesting_operations::split_grant(
+        schedule,
+        index as usize,
+        split_tokens,
+        new_beneficiary,
+        &ctx.accounts.vesting_data,
+        timestamp,
+    )?;
+
+    emit!(GrantSplit {
+        index,
+        new_index: new_index as u64,
+        user: beneficiary,
+        new_user: new_beneficiary,
+        planned_tokens: schedule.data[index as usize].planned_tokens,
+        new_planned_tokens: split_tokens,
+    });
+
+    Ok(())
+}
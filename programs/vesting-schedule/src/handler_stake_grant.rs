@@ -0,0 +1,32 @@
+use crate::{tokenoperation, vesting_operations, GrantStaked, StakeGrant, VestingScheduleMut};
+use anchor_lang::prelude::*;
+
+pub fn process<'info>(ctx: Context<'_, '_, '_, 'info, StakeGrant<'info>>, index: u64, amount: u64) -> ProgramResult {
+    let authority = ctx.accounts.authority.key();
+    let mut data = ctx.accounts.vesting_schedule.try_borrow_mut_data()?;
+    let schedule = &mut VestingScheduleMut::load(&mut data)?;
+    let vesting_data = &mut ctx.accounts.vesting_data;
+    // only the beneficiary decides to stake their tokens
+    let receipt = tokenoperation::tokenoper::receipt_holding(&ctx.accounts.receipt_account);
+    let grant = vesting_operations::beneficiary_grant(schedule, index, authority, receipt)?;
+    let timestamp = Clock::get()?.unix_timestamp as u64;
+    vesting_operations::stake_grant(grant, vesting_data, amount, timestamp)?;
+
+    tokenoperation::staking::deposit(
+        amount,
+        &ctx.accounts.admin,
+        &ctx.accounts.vesting_vault,
+        &ctx.accounts.vesting_vault_authority,
+        vesting_data.vesting_vault_authority_seed,
+        &ctx.accounts.staking_program,
+        ctx.remaining_accounts,
+    )?;
+    emit!(GrantStaked {
+        index,
+        user: grant.user,
+        amount,
+        staked_tokens: grant.staked_tokens,
+    });
+
+    Ok(())
+}
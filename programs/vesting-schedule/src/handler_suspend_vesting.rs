@@ -0,0 +1,21 @@
+use crate::{vesting_operations, SuspendVesting, VestingScheduleMut, VestingSuspended};
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<SuspendVesting>, index: u64) -> ProgramResult {
+    let mut data = ctx.accounts.vesting_schedule.try_borrow_mut_data()?;
+    let schedule = &mut VestingScheduleMut::load(&mut data)?;
+    let now = vesting_operations::vesting_clock(
+        &ctx.accounts.vesting_data,
+        Clock::get()?.unix_timestamp as u64,
+    );
+
+    vesting_operations::suspend_vesting(schedule, index as usize, now)?;
+
+    emit!(VestingSuspended {
+        index,
+        user: schedule.data[index as usize].user,
+        suspended_at: now,
+    });
+
+    Ok(())
+}
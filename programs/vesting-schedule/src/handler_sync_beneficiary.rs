@@ -0,0 +1,26 @@
+use crate::{utils, vesting_operations, BeneficiaryChanged, ErrorCode, SyncBeneficiary, VestingScheduleMut};
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<SyncBeneficiary>, index: u64) -> ProgramResult {
+    let receipt_account = &ctx.accounts.receipt_account;
+    let mut data = ctx.accounts.vesting_schedule.try_borrow_mut_data()?;
+    let schedule = &mut VestingScheduleMut::load(&mut data)?;
+    let grant = schedule
+        .data
+        .get_mut(index as usize)
+        .filter(|grant| grant.status == utils::utils::EventStatus::PendingToken as u8)
+        .ok_or(ErrorCode::InvalidInput)?;
+    let old_beneficiary =
+        vesting_operations::sync_beneficiary(grant, receipt_account.mint, receipt_account.owner, receipt_account.amount)?;
+
+    if old_beneficiary != receipt_account.owner {
+        emit!(BeneficiaryChanged {
+            index,
+            old_beneficiary,
+            new_beneficiary: receipt_account.owner,
+            by_receipt: true,
+        });
+    }
+
+    Ok(())
+}
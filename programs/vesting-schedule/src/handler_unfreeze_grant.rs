@@ -0,0 +1,9 @@
+use crate::{vesting_operations, FreezeGrant, VestingScheduleMut};
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<FreezeGrant>, index: u64) -> ProgramResult {
+    let mut data = ctx.accounts.vesting_schedule.try_borrow_mut_data()?;
+    vesting_operations::set_grant_frozen(&mut VestingScheduleMut::load(&mut data)?, index as usize, false)?;
+
+    Ok(())
+}
@@ -0,0 +1,9 @@
+use crate::{vesting_operations, PauseSchedule};
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<PauseSchedule>) -> ProgramResult {
+    let now = Clock::get()?.unix_timestamp;
+    vesting_operations::unpause_schedule(&mut ctx.accounts.vesting_data, now as u64)?;
+
+    Ok(())
+}
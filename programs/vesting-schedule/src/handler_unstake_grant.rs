@@ -0,0 +1,40 @@
+use crate::{tokenoperation, utils, vesting_operations, ErrorCode, GrantUnstaked, StakeGrant, VestingScheduleMut};
+use anchor_lang::prelude::*;
+
+pub fn process<'info>(ctx: Context<'_, '_, '_, 'info, StakeGrant<'info>>, index: u64, amount: u64) -> ProgramResult {
+    let authority = ctx.accounts.authority.key();
+    let mut data = ctx.accounts.vesting_schedule.try_borrow_mut_data()?;
+    let schedule = &mut VestingScheduleMut::load(&mut data)?;
+    let vesting_data = &mut ctx.accounts.vesting_data;
+    // the admin can unstake too, e.g. to reclaim a grant past its deadline
+    let grant = if authority == vesting_data.admin {
+        schedule
+            .data
+            .get_mut(index as usize)
+            .filter(|grant| grant.status == utils::utils::EventStatus::PendingToken as u8)
+            .ok_or(ErrorCode::InvalidInput)?
+    } else {
+        let receipt = tokenoperation::tokenoper::receipt_holding(&ctx.accounts.receipt_account);
+        vesting_operations::beneficiary_grant(schedule, index, authority, receipt)?
+    };
+    vesting_operations::unstake_grant(grant, vesting_data, amount)?;
+
+    tokenoperation::staking::withdraw(
+        amount,
+        &ctx.accounts.admin,
+        &ctx.accounts.vesting_vault,
+        &ctx.accounts.vesting_vault,
+        &ctx.accounts.vesting_vault_authority,
+        vesting_data.vesting_vault_authority_seed,
+        &ctx.accounts.staking_program,
+        ctx.remaining_accounts,
+    )?;
+    emit!(GrantUnstaked {
+        index,
+        user: grant.user,
+        amount,
+        staked_tokens: grant.staked_tokens,
+    });
+
+    Ok(())
+}
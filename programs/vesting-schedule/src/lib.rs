@@ -1,15 +1,52 @@
 use anchor_lang::prelude::*;
+pub mod handler_accelerate;
 pub mod handler_add_user;
+pub mod handler_amend_grant;
+pub mod handler_change_beneficiary;
 pub mod handler_initialize;
+pub mod handler_migrate;
+pub mod handler_mint_receipt;
 pub mod handler_remove_user;
+pub mod handler_resume_vesting;
+pub mod handler_set_claim_deadline;
+pub mod handler_set_merkle_root;
+pub mod handler_set_staking_program;
+pub mod handler_set_transferable;
+pub mod handler_split_grant;
+pub mod handler_stake_grant;
+pub mod handler_suspend_vesting;
+pub mod handler_sync_beneficiary;
 pub mod handler_claim;
+pub mod handler_claim_merkle;
+pub mod handler_claim_from_stake;
+pub mod handler_claim_to;
+pub mod handler_freeze_grant;
+pub mod handler_grow_schedule;
+pub mod handler_get_claimable;
+pub mod handler_get_locked_balance;
+pub mod handler_pause_schedule;
+pub mod handler_reclaim_expired;
+pub mod handler_unfreeze_grant;
+pub mod handler_unstake_grant;
+pub mod handler_unpause_schedule;
 pub mod tokenoperation;
 pub mod utils;
 pub mod vesting_operations;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::Discriminator;
 use anchor_spl::token::Token;
-use anchor_spl::token::{self, SetAuthority};
+use anchor_spl::token::{self, Mint, SetAuthority, TokenAccount};
 declare_id!("APxgpqd2EkAzJBBymwss5k7Wp1DSmjjggVx5V6EQc6cR");
 
+// What the generated `entry` does. That one is compiled out with the cpi feature, so
+// solana-program-test uses this to run the program next to programs that depend on it.
+pub fn process_instruction(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if data.len() < 8 {
+        return Err(anchor_lang::__private::ErrorCode::InstructionMissing.into());
+    }
+    dispatch(program_id, accounts, data)
+}
+
 #[program]
 pub mod vesting_schedule {
     use super::*;
@@ -19,6 +56,7 @@ pub mod vesting_schedule {
         handler_initialize::process(ctx, tge_timestamp)
     }
 
+    // Signed by the admin, like remove_user
     pub fn add_user(
         ctx: Context<AddUser>,
         unlocked_at_tge: u8,
@@ -35,23 +73,180 @@ pub mod vesting_schedule {
         )
     }
 
-    pub fn remove_user(ctx: Context<RemoveUser>, index: u64) -> ProgramResult {
-        handler_remove_user::process(ctx, index)
+    // Without an index the beneficiary's first grant is removed
+    pub fn remove_user(ctx: Context<RemoveUser>, beneficiary: Pubkey, index: Option<u64>) -> ProgramResult {
+        handler_remove_user::process(ctx, beneficiary, index)
     }
 
-    pub fn claim(ctx: Context<Claim>, index: u64) -> ProgramResult {
+    // Without an index the claim_user's first active grant is claimed
+    pub fn claim(ctx: Context<Claim>, index: Option<u64>) -> ProgramResult {
         handler_claim::process(ctx, index)
     }
+
+    // The new terms apply retroactively from TGE, see vesting_operations::amend_grant
+    pub fn amend_grant(
+        ctx: Context<AmendGrant>,
+        index: u64,
+        unlocked_at_tge: u8,
+        unlocking_period: u8,
+        planned_tokens: u64,
+    ) -> ProgramResult {
+        handler_amend_grant::process(
+            ctx,
+            index,
+            unlocked_at_tge,
+            unlocking_period,
+            planned_tokens,
+        )
+    }
+
+    pub fn pause_schedule(ctx: Context<PauseSchedule>, stop_clock: bool) -> ProgramResult {
+        handler_pause_schedule::process(ctx, stop_clock)
+    }
+
+    pub fn unpause_schedule(ctx: Context<PauseSchedule>) -> ProgramResult {
+        handler_unpause_schedule::process(ctx)
+    }
+
+    pub fn freeze_grant(ctx: Context<FreezeGrant>, index: u64) -> ProgramResult {
+        handler_freeze_grant::process(ctx, index)
+    }
+
+    pub fn unfreeze_grant(ctx: Context<FreezeGrant>, index: u64) -> ProgramResult {
+        handler_unfreeze_grant::process(ctx, index)
+    }
+
+    pub fn suspend_vesting(ctx: Context<SuspendVesting>, index: u64) -> ProgramResult {
+        handler_suspend_vesting::process(ctx, index)
+    }
+
+    pub fn resume_vesting(ctx: Context<SuspendVesting>, index: u64) -> ProgramResult {
+        handler_resume_vesting::process(ctx, index)
+    }
+
+    pub fn accelerate(ctx: Context<Accelerate>, index: Option<u64>, bps: u16) -> ProgramResult {
+        handler_accelerate::process(ctx, index, bps)
+    }
+
+    pub fn set_claim_deadline(
+        ctx: Context<SetClaimDeadline>,
+        index: Option<u64>,
+        claim_deadline: u64,
+    ) -> ProgramResult {
+        handler_set_claim_deadline::process(ctx, index, claim_deadline)
+    }
+
+    pub fn reclaim_expired(ctx: Context<ReclaimExpired>, index: Option<u64>) -> ProgramResult {
+        handler_reclaim_expired::process(ctx, index)
+    }
+
+    pub fn set_merkle_root(ctx: Context<SetMerkleRoot>, merkle_root: [u8; 32]) -> ProgramResult {
+        handler_set_merkle_root::process(ctx, merkle_root)
+    }
+
+    pub fn claim_merkle(
+        ctx: Context<ClaimMerkle>,
+        planned_tokens: u64,
+        unlocked_at_tge: u8,
+        unlocking_period: u8,
+        proof: Vec<[u8; 32]>,
+    ) -> ProgramResult {
+        handler_claim_merkle::process(ctx, planned_tokens, unlocked_at_tge, unlocking_period, proof)
+    }
+
+    // Read-only, returns a borsh encoded ClaimableView through the return data
+    pub fn get_claimable(ctx: Context<GetClaimable>, index: u64) -> ProgramResult {
+        handler_get_claimable::process(ctx, index)
+    }
+
+    // CPI entry point, returns a borsh encoded LockedBalance through the return data
+    pub fn get_locked_balance(ctx: Context<GetLockedBalance>, index: u64) -> ProgramResult {
+        handler_get_locked_balance::process(ctx, index)
+    }
+
+    // CPI entry point, claims into any token account of the vested mint, e.g. one owned
+    // by a staking program. Returns the claimed amount as a little endian u64.
+    pub fn claim_to(ctx: Context<ClaimTo>, index: u64) -> ProgramResult {
+        handler_claim_to::process(ctx, index)
+    }
+
+    pub fn set_staking_program(ctx: Context<SetStakingProgram>, staking_program: Pubkey) -> ProgramResult {
+        handler_set_staking_program::process(ctx, staking_program)
+    }
+
+    // The staking program's deposit accounts follow as remaining accounts,
+    // see tokenoperation::staking for the interface it has to implement
+    pub fn stake_grant<'info>(ctx: Context<'_, '_, '_, 'info, StakeGrant<'info>>, index: u64, amount: u64) -> ProgramResult {
+        handler_stake_grant::process(ctx, index, amount)
+    }
+
+    // Signed by the beneficiary or the admin, the tokens go back into the vault
+    pub fn unstake_grant<'info>(ctx: Context<'_, '_, '_, 'info, StakeGrant<'info>>, index: u64, amount: u64) -> ProgramResult {
+        handler_unstake_grant::process(ctx, index, amount)
+    }
+
+    // Like claim_to, but whatever the vault does not hold for the grant is withdrawn from the stake.
+    // Returns the claimed amount as a little endian u64.
+    pub fn claim_from_stake<'info>(ctx: Context<'_, '_, '_, 'info, ClaimFromStake<'info>>, index: u64) -> ProgramResult {
+        handler_claim_from_stake::process(ctx, index)
+    }
+
+    // Off by default, while off no receipt is minted and change_beneficiary refuses to move a grant.
+    // Receipts minted before keep carrying their grant, nothing can freeze them.
+    pub fn set_transferable(ctx: Context<SetTransferable>, transferable: bool) -> ProgramResult {
+        handler_set_transferable::process(ctx, transferable)
+    }
+
+    // Mints the 1 of 1 receipt of the grant to the beneficiary, whoever holds it is the beneficiary
+    // from then on. The mint is created beforehand with the vesting vault authority as mint authority.
+    // Claims, stakes and splits of the grant take the holder's receipt account and sync to it first.
+    pub fn mint_receipt(ctx: Context<MintReceipt>, index: u64) -> ProgramResult {
+        handler_mint_receipt::process(ctx, index)
+    }
+
+    // Permissionless, points a grant with a receipt at the current holder of the receipt
+    pub fn sync_beneficiary(ctx: Context<SyncBeneficiary>, index: u64) -> ProgramResult {
+        handler_sync_beneficiary::process(ctx, index)
+    }
+
+    // Signed by the current beneficiary, only for grants without a receipt
+    pub fn change_beneficiary(ctx: Context<ChangeBeneficiary>, index: u64, new_beneficiary: Pubkey) -> ProgramResult {
+        handler_change_beneficiary::process(ctx, index, new_beneficiary)
+    }
+
+    // Brings the schedule's accounts to the current layout in place, the admin pays the extra rent.
+    // Merkle claim statuses of the schedule can be passed as remaining accounts. A v1 vesting data
+    // did not record its admin or schedule: the admin is checked against the vault authority and
+    // the schedule passed along is linked to it.
+    pub fn migrate<'info>(ctx: Context<'_, '_, '_, 'info, Migrate<'info>>) -> ProgramResult {
+        handler_migrate::process(ctx)
+    }
+
+    // Signed by the beneficiary, moves `split_tokens` of the grant into a new grant on the same curve.
+    // Splitting towards another beneficiary needs the schedule to be transferable.
+    pub fn split_grant(
+        ctx: Context<SplitGrant>,
+        index: u64,
+        split_tokens: u64,
+        new_beneficiary: Pubkey,
+    ) -> ProgramResult {
+        handler_split_grant::process(ctx, index, split_tokens, new_beneficiary)
+    }
+
+    pub fn grow_schedule(ctx: Context<GrowSchedule>, additional_grants: u64) -> ProgramResult {
+        handler_grow_schedule::process(ctx, additional_grants)
+    }
 }
 
 #[derive(Accounts)]
 pub struct Initialize<'info> {
-    #[account(signer)]
+    // pays for vesting_data
+    #[account(signer, mut)]
     pub admin: AccountInfo<'info>,
     #[account(init, payer = admin)]
     pub vesting_data: ProgramAccount<'info, VestingData>,
-    #[account(zero)]
-    pub vesting_schedule: Loader<'info, VestingSchedule>,
+    #[account(mut, owner = *program_id)]
+    pub vesting_schedule: AccountInfo<'info>,
     #[account(mut)]
     pub vesting_vault: AccountInfo<'info>,
     pub token_program: Program<'info, Token>,
@@ -61,11 +256,153 @@ pub struct Initialize<'info> {
 #[derive(Accounts)]
 pub struct Claim<'info> {
     pub owner: AccountInfo<'info>,
+    #[account(mut, owner = *program_id)]
+    pub vesting_schedule: AccountInfo<'info>,
+    #[account(mut, has_one = vesting_schedule, has_one = vesting_vault)]
+    pub vesting_data: Account<'info, VestingData>,
+    pub claim_user: AccountInfo<'info>,
+    // claim does not need the beneficiary's signature, so it only ever pays them
+    #[account(mut, constraint = claim_user_ata.owner == claim_user.key())]
+    pub claim_user_ata: Account<'info, TokenAccount>,
     #[account(mut)]
-    pub vesting_schedule: Loader<'info, VestingSchedule>,
+    pub vesting_vault: AccountInfo<'info>,
+    pub vesting_vault_authority: AccountInfo<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    // the token account of claim_user holding the grant's receipt, any account for grants without one
+    pub receipt_account: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AddUser<'info> {
+    #[account(signer)]
+    pub admin: AccountInfo<'info>,
+    #[account(has_one = admin, has_one = vesting_schedule)]
+    pub vesting_data: Account<'info, VestingData>,
+    #[account(mut, owner = *program_id)]
+    pub vesting_schedule: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveUser<'info> {
+    #[account(signer)]
+    pub admin: AccountInfo<'info>,
+    #[account(has_one = admin, has_one = vesting_schedule)]
+    pub vesting_data: Account<'info, VestingData>,
+    #[account(mut, owner = *program_id)]
+    pub vesting_schedule: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AmendGrant<'info> {
+    #[account(signer)]
+    pub admin: AccountInfo<'info>,
+    // Has to sign unless the amendment is a pure top-up
+    pub beneficiary: AccountInfo<'info>,
+    #[account(has_one = admin, has_one = vesting_schedule)]
+    pub vesting_data: Account<'info, VestingData>,
+    #[account(mut, owner = *program_id)]
+    pub vesting_schedule: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PauseSchedule<'info> {
+    #[account(signer)]
+    pub admin: AccountInfo<'info>,
+    #[account(mut, has_one = admin)]
+    pub vesting_data: Account<'info, VestingData>,
+}
+
+#[derive(Accounts)]
+pub struct FreezeGrant<'info> {
+    #[account(signer)]
+    pub admin: AccountInfo<'info>,
+    #[account(has_one = admin, has_one = vesting_schedule)]
+    pub vesting_data: Account<'info, VestingData>,
+    #[account(mut, owner = *program_id)]
+    pub vesting_schedule: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SuspendVesting<'info> {
+    #[account(signer)]
+    pub admin: AccountInfo<'info>,
+    #[account(has_one = admin, has_one = vesting_schedule)]
+    pub vesting_data: Account<'info, VestingData>,
+    #[account(mut, owner = *program_id)]
+    pub vesting_schedule: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Accelerate<'info> {
+    #[account(signer)]
+    pub admin: AccountInfo<'info>,
+    #[account(has_one = admin, has_one = vesting_schedule)]
+    pub vesting_data: Account<'info, VestingData>,
+    #[account(mut, owner = *program_id)]
+    pub vesting_schedule: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetClaimDeadline<'info> {
+    #[account(signer)]
+    pub admin: AccountInfo<'info>,
+    #[account(mut, has_one = admin, has_one = vesting_schedule)]
+    pub vesting_data: Account<'info, VestingData>,
+    #[account(mut, owner = *program_id)]
+    pub vesting_schedule: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimExpired<'info> {
+    #[account(signer)]
+    pub admin: AccountInfo<'info>,
+    #[account(
+        mut,
+        has_one = admin,
+        has_one = vesting_schedule,
+        has_one = vesting_vault,
+        has_one = vesting_vault_authority
+    )]
+    pub vesting_data: Account<'info, VestingData>,
+    #[account(mut, owner = *program_id)]
+    pub vesting_schedule: AccountInfo<'info>,
     #[account(mut)]
+    pub vesting_vault: AccountInfo<'info>,
+    pub vesting_vault_authority: AccountInfo<'info>,
+    #[account(mut)]
+    pub treasury: AccountInfo<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetMerkleRoot<'info> {
+    #[account(signer)]
+    pub admin: AccountInfo<'info>,
+    #[account(mut, has_one = admin)]
     pub vesting_data: Account<'info, VestingData>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimMerkle<'info> {
+    #[account(signer, mut)]
     pub claim_user: AccountInfo<'info>,
+    #[account(
+        init_if_needed,
+        payer = claim_user,
+        seeds = [
+            utils::utils::CLAIM_STATUS_TAG.as_ref(),
+            vesting_data.key().as_ref(),
+            claim_user.key.as_ref()
+        ],
+        bump
+    )]
+    pub claim_status: Account<'info, MerkleClaimStatus>,
+    pub admin: AccountInfo<'info>,
+    #[account(mut, has_one = admin, has_one = vesting_vault, has_one = vesting_vault_authority)]
+    pub vesting_data: Account<'info, VestingData>,
     #[account(mut)]
     pub claim_user_ata: AccountInfo<'info>,
     #[account(mut)]
@@ -76,19 +413,332 @@ pub struct Claim<'info> {
 }
 
 #[derive(Accounts)]
-pub struct AddUser<'info> {
+pub struct GetClaimable<'info> {
+    #[account(has_one = vesting_schedule)]
+    pub vesting_data: Account<'info, VestingData>,
+    #[account(owner = *program_id)]
+    pub vesting_schedule: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GetLockedBalance<'info> {
+    pub beneficiary: AccountInfo<'info>,
+    #[account(has_one = vesting_schedule)]
+    pub vesting_data: Account<'info, VestingData>,
+    #[account(owner = *program_id)]
+    pub vesting_schedule: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimTo<'info> {
+    #[account(signer)]
+    pub beneficiary: AccountInfo<'info>,
+    pub admin: AccountInfo<'info>,
+    #[account(
+        mut,
+        has_one = admin,
+        has_one = vesting_schedule,
+        has_one = vesting_vault,
+        has_one = vesting_vault_authority
+    )]
+    pub vesting_data: Account<'info, VestingData>,
+    #[account(mut, owner = *program_id)]
+    pub vesting_schedule: AccountInfo<'info>,
     #[account(mut)]
-    pub vesting_schedule: Loader<'info, VestingSchedule>,
-    pub system_program: Program<'info, System>,
+    pub destination: AccountInfo<'info>,
+    #[account(mut)]
+    pub vesting_vault: AccountInfo<'info>,
+    pub vesting_vault_authority: AccountInfo<'info>,
+    pub token_program: Program<'info, Token>,
+    // the token account holding the grant's receipt, any account for grants without one
+    pub receipt_account: AccountInfo<'info>,
 }
 
 #[derive(Accounts)]
-pub struct RemoveUser<'info> {
+pub struct SetStakingProgram<'info> {
+    #[account(signer)]
+    pub admin: AccountInfo<'info>,
+    #[account(mut, has_one = admin)]
+    pub vesting_data: Account<'info, VestingData>,
+}
+
+#[derive(Accounts)]
+pub struct StakeGrant<'info> {
+    #[account(signer)]
+    pub authority: AccountInfo<'info>,
+    pub admin: AccountInfo<'info>,
+    #[account(
+        mut,
+        has_one = admin,
+        has_one = vesting_schedule,
+        has_one = vesting_vault,
+        has_one = vesting_vault_authority
+    )]
+    pub vesting_data: Account<'info, VestingData>,
+    #[account(mut, owner = *program_id)]
+    pub vesting_schedule: AccountInfo<'info>,
+    #[account(mut)]
+    pub vesting_vault: AccountInfo<'info>,
+    pub vesting_vault_authority: AccountInfo<'info>,
+    #[account(
+        executable,
+        constraint = vesting_data.staking_program != Pubkey::default()
+            && staking_program.key() == vesting_data.staking_program
+            @ ErrorCode::StakingProgramNotWhitelisted
+    )]
+    pub staking_program: AccountInfo<'info>,
+    // the token account holding the grant's receipt, any account for grants without one
+    pub receipt_account: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimFromStake<'info> {
+    #[account(signer)]
+    pub beneficiary: AccountInfo<'info>,
+    pub admin: AccountInfo<'info>,
+    #[account(
+        mut,
+        has_one = admin,
+        has_one = vesting_schedule,
+        has_one = vesting_vault,
+        has_one = vesting_vault_authority
+    )]
+    pub vesting_data: Account<'info, VestingData>,
+    #[account(mut, owner = *program_id)]
+    pub vesting_schedule: AccountInfo<'info>,
+    #[account(mut)]
+    pub destination: AccountInfo<'info>,
     #[account(mut)]
-    pub vesting_schedule: Loader<'info, VestingSchedule>,
+    pub vesting_vault: AccountInfo<'info>,
+    pub vesting_vault_authority: AccountInfo<'info>,
+    #[account(
+        executable,
+        constraint = vesting_data.staking_program != Pubkey::default()
+            && staking_program.key() == vesting_data.staking_program
+            @ ErrorCode::StakingProgramNotWhitelisted
+    )]
+    pub staking_program: AccountInfo<'info>,
+    pub token_program: Program<'info, Token>,
+    // the token account holding the grant's receipt, any account for grants without one
+    pub receipt_account: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetTransferable<'info> {
+    #[account(signer)]
+    pub admin: AccountInfo<'info>,
+    #[account(mut, has_one = admin)]
+    pub vesting_data: Account<'info, VestingData>,
+}
+
+#[derive(Accounts)]
+pub struct MintReceipt<'info> {
+    #[account(signer)]
+    pub beneficiary: AccountInfo<'info>,
+    pub admin: AccountInfo<'info>,
+    #[account(has_one = admin, has_one = vesting_schedule, has_one = vesting_vault_authority)]
+    pub vesting_data: Account<'info, VestingData>,
+    #[account(mut, owner = *program_id)]
+    pub vesting_schedule: AccountInfo<'info>,
+    #[account(
+        mut,
+        constraint = receipt_mint.decimals == 0
+            && receipt_mint.supply == 0
+            && receipt_mint.mint_authority == COption::Some(vesting_vault_authority.key())
+            && receipt_mint.freeze_authority == COption::None
+    )]
+    pub receipt_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        constraint = receipt_account.mint == receipt_mint.key() && receipt_account.owner == beneficiary.key()
+    )]
+    pub receipt_account: Account<'info, TokenAccount>,
+    pub vesting_vault_authority: AccountInfo<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SyncBeneficiary<'info> {
+    #[account(has_one = vesting_schedule)]
+    pub vesting_data: Account<'info, VestingData>,
+    #[account(mut, owner = *program_id)]
+    pub vesting_schedule: AccountInfo<'info>,
+    pub receipt_account: Account<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct ChangeBeneficiary<'info> {
+    #[account(signer)]
+    pub beneficiary: AccountInfo<'info>,
+    #[account(has_one = vesting_schedule)]
+    pub vesting_data: Account<'info, VestingData>,
+    #[account(mut, owner = *program_id)]
+    pub vesting_schedule: AccountInfo<'info>,
+}
+
+// Raw accounts, an account in an older layout does not deserialize as the current one
+#[derive(Accounts)]
+pub struct Migrate<'info> {
+    #[account(signer, mut)]
+    pub admin: AccountInfo<'info>,
+    #[account(mut, owner = *program_id)]
+    pub vesting_data: AccountInfo<'info>,
+    #[account(mut, owner = *program_id)]
+    pub vesting_schedule: AccountInfo<'info>,
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct SplitGrant<'info> {
+    #[account(signer)]
+    pub beneficiary: AccountInfo<'info>,
+    #[account(has_one = vesting_schedule)]
+    pub vesting_data: Account<'info, VestingData>,
+    #[account(mut, owner = *program_id)]
+    pub vesting_schedule: AccountInfo<'info>,
+    // the token account holding the grant's receipt, any account for grants without one
+    pub receipt_account: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GrowSchedule<'info> {
+    #[account(signer, mut)]
+    pub admin: AccountInfo<'info>,
+    #[account(has_one = admin, has_one = vesting_schedule)]
+    pub vesting_data: Account<'info, VestingData>,
+    #[account(mut, owner = *program_id)]
+    pub vesting_schedule: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct GrantAmended {
+    pub index: u64,
+    pub user: Pubkey,
+    pub amendment_count: u16,
+    pub old_planned_tokens: u64,
+    pub new_planned_tokens: u64,
+    pub old_unlocked_at_tge: u8,
+    pub new_unlocked_at_tge: u8,
+    pub old_unlocking_period: u8,
+    pub new_unlocking_period: u8,
+    pub claimed_tokens: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VestingSuspended {
+    pub index: u64,
+    pub user: Pubkey,
+    pub suspended_at: u64,
+}
+
+#[event]
+pub struct VestingResumed {
+    pub index: u64,
+    pub user: Pubkey,
+    pub suspended_at: u64,
+    pub resumed_at: u64,
+    pub suspended_seconds: u64,
+}
+
+#[event]
+pub struct GrantAccelerated {
+    pub index: u64,
+    pub user: Pubkey,
+    pub bps: u16,
+    pub accelerated_tokens: u64,
+    pub total_accelerated_tokens: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ScheduleAccelerated {
+    pub bps: u16,
+    pub grants: u64,
+    pub accelerated_tokens: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ExpiredReclaimed {
+    pub grants: u64,
+    pub reclaimed_tokens: u64,
+    pub treasury: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ClaimedTo {
+    pub index: u64,
+    pub beneficiary: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct GrantStaked {
+    pub index: u64,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub staked_tokens: u64,
+}
+
+#[event]
+pub struct GrantUnstaked {
+    pub index: u64,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub staked_tokens: u64,
+}
+
+#[event]
+pub struct ClaimedFromStake {
+    pub index: u64,
+    pub beneficiary: Pubkey,
+    pub destination: Pubkey,
+    pub from_vault: u64,
+    pub from_stake: u64,
+}
+
+#[event]
+pub struct GrantSplit {
+    pub index: u64,
+    pub new_index: u64,
+    pub user: Pubkey,
+    pub new_user: Pubkey,
+    pub planned_tokens: u64,
+    pub new_planned_tokens: u64,
+}
+
+#[event]
+pub struct ScheduleGrown {
+    pub capacity: u64,
+}
+
+#[event]
+pub struct LayoutMigrated {
+    pub account: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+}
+
+#[event]
+pub struct ReceiptMinted {
+    pub index: u64,
+    pub user: Pubkey,
+    pub receipt_mint: Pubkey,
+}
+
+#[event]
+pub struct BeneficiaryChanged {
+    pub index: u64,
+    pub old_beneficiary: Pubkey,
+    pub new_beneficiary: Pubkey,
+    // the grant moved with its receipt rather than through change_beneficiary
+    pub by_receipt: bool,
+}
+
 #[error]
 #[derive(PartialEq, Eq)]
 pub enum ErrorCode {
@@ -96,30 +746,170 @@ pub enum ErrorCode {
     InvalidInput,
     #[msg("VestingUsers Data Array is full")]
     VestingUserDataFull,
+    #[msg("Amendment would reduce entitlement below claimed tokens")]
+    AmendmentBelowClaimed,
+    #[msg("Beneficiary has to sign this amendment")]
+    BeneficiaryConsentRequired,
+    #[msg("Claims are paused for this schedule")]
+    SchedulePaused,
+    #[msg("Claims are frozen for this grant")]
+    GrantFrozen,
+    #[msg("Claim deadline has passed")]
+    ClaimDeadlinePassed,
+    #[msg("Merkle proof does not match the committed root")]
+    InvalidMerkleProof,
+    #[msg("Staking program is not whitelisted for this schedule")]
+    StakingProgramNotWhitelisted,
+    #[msg("Only tokens that are still locked can be staked")]
+    StakeExceedsLocked,
+    #[msg("Part of this claim is staked, use claim_from_stake or unstake first")]
+    ClaimNeedsUnstake,
+    #[msg("Staked tokens have to be unstaked first")]
+    StakeOutstanding,
+    #[msg("Grants of this schedule are not transferable")]
+    TransfersDisabled,
+    #[msg("Grant has a receipt, it moves with the receipt")]
+    GrantHasReceipt,
+    #[msg("Schedule account is in an older layout, migrate it first")]
+    ScheduleLayoutOutdated,
+    #[msg("No grant of this beneficiary at that index")]
+    GrantNotFound,
+    #[msg("The grant has a receipt and the beneficiary does not hold it")]
+    ReceiptNotHeld,
+    #[msg("The staking program did not move exactly the staked amount")]
+    StakeTransferMismatch,
 }
 
 #[account]
 #[derive(Debug, Default)]
 pub struct VestingData {
+    // utils::layout::LAYOUT_VERSION, accounts from before the version byte are version 1
+    pub version: u8,
+    pub admin: Pubkey,
+    pub vesting_schedule: Pubkey,
     pub tge_timestamp: u64,
     pub vesting_vault: Pubkey,
     pub vesting_vault_authority: Pubkey,
     pub vesting_vault_authority_seed: u8,
     pub total_issued_so_far: u64,
+    // while paused no claim goes through
+    pub paused: bool,
+    // whether the vesting clock stands still during the current pause
+    pub pause_stops_clock: bool,
+    pub paused_at: u64,
+    // seconds after TGE the vesting clock was stopped by past pauses
+    pub paused_seconds: u64,
+    // no claims after this timestamp, 0 means no deadline. Never set together with merkle_root
+    pub claim_deadline: u64,
+    // unclaimed tokens of expired grants swept back to the treasury
+    pub total_reclaimed: u64,
+    // root of the grants claimable through claim_merkle, see utils::merkle for the leaf format
+    pub merkle_root: [u8; 32],
+    // the one program locked tokens can be staked into, default means staking is off
+    pub staking_program: Pubkey,
+    // tokens of all grants currently held by the staking program instead of the vault
+    pub total_staked: u64,
+    // whether receipts can be minted and grants moved through change_beneficiary or split_grant
+    pub transferable: bool,
+}
+
+// Created on the first claim_merkle of a beneficiary, keeps the proven terms
+// so later claims do not need the proof again
+#[account]
+#[derive(Debug, Default)]
+pub struct MerkleClaimStatus {
+    pub version: u8,
+    pub vesting_data: Pubkey,
+    pub beneficiary: Pubkey,
+    pub planned_tokens: u64,
+    pub claimed_tokens: u64,
+    pub unlocked_at_tge: u8,
+    pub unlocking_period: u8,
 }
 
+impl MerkleClaimStatus {
+    pub fn grant(&self) -> VestingUser {
+        let mut grant = VestingUser::new(
+            self.unlocked_at_tge,
+            self.beneficiary,
+            self.unlocking_period,
+            self.planned_tokens,
+        );
+        grant.claimed_tokens = self.claimed_tokens;
+        grant
+    }
+}
+
+// Return data of get_claimable
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ClaimableView {
+    // everything vested so far, claimed or not
+    pub vested: u64,
+    pub claimed: u64,
+    // what a claim would transfer now, 0 while paused, frozen or expired
+    pub claimable: u64,
+    // when vested goes up next, None once fully vested, while suspended or while the clock is stopped
+    pub next_unlock_timestamp: Option<u64>,
+}
+
+impl ClaimableView {
+    // For programs that called get_claimable through CPI
+    pub fn from_return_data() -> Option<Self> {
+        utils::utils::return_data()
+    }
+}
+
+// Return data of get_locked_balance
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct LockedBalance {
+    pub beneficiary: Pubkey,
+    pub planned_tokens: u64,
+    pub vested: u64,
+    pub claimed: u64,
+    // not vested yet, what governance or staking programs usually count
+    pub locked: u64,
+    // vested but not claimed yet
+    pub unclaimed: u64,
+}
+
+impl LockedBalance {
+    // For programs that called get_locked_balance through CPI
+    pub fn from_return_data() -> Option<Self> {
+        utils::utils::return_data()
+    }
+}
+
+// Laid out widest field first and padded to a multiple of 8 bytes, so with the schedule header
+// being 8 byte aligned as well every u64 of every grant sits on an 8 byte boundary
 #[zero_copy]
 #[derive(Debug, PartialEq, Eq)]
 pub struct VestingUser {
-    // PendingToken : 1 InActive : 0
-    pub status: u8,
     // The solana account address
     pub user: Pubkey,
+    // mint of the receipt NFT whose holder is the beneficiary, default means no receipt
+    pub receipt_mint: Pubkey,
     // how many HBB tokens the user is entitled to
     // since the HBB token has 9 decimals, 1 token = 1.000.000.000
     pub planned_tokens: u64,
     // cumulative amount of tokens claimed
     pub claimed_tokens: u64,
+    pub suspended_at: u64,
+    // seconds after TGE spent in closed suspension intervals
+    pub suspended_seconds: u64,
+    // tokens vested ahead of the curve by acceleration
+    pub accelerated_tokens: u64,
+    // overrides the schedule claim deadline, 0 means the schedule one applies
+    pub claim_deadline: u64,
+    // part of planned_tokens - claimed_tokens that sits in the staking program, not in the vault
+    pub staked_tokens: u64,
+    // planned_tokens of the curve a split grant vests a share of, 0 means its own planned_tokens
+    pub curve_tokens: u64,
+    // where the grant's share starts within that curve, see vesting_operations::share_of_curve
+    pub share_start: u64,
+    // number of times the grant was amended after it was added
+    pub amendment_count: u16,
+    // Expired : 2 PendingToken : 1 InActive : 0
+    pub status: u8,
     // percentage 15% is represented as 15, so scaled by 100
     // some people have 20% at TGE, some people have 15% at TGE
     // 15% -> 15 -> 15/100
@@ -128,8 +918,18 @@ pub struct VestingUser {
     // after unlocking_period -> everything should be claimed
     // claimed_tokens === planned_tokens
     pub unlocking_period: u8,
+    // Frozen : 1, claims are blocked but vesting keeps accruing
+    pub frozen: u8,
+    // Suspended : 1, vesting clock of this grant stands still since suspended_at
+    pub suspended: u8,
+    pub padding: u8,
 }
 
+// Grants are read straight out of the schedule account data. VestingUser is packed and made of
+// plain integers and pubkeys, as the zero copy accounts anchor implements these for.
+unsafe impl bytemuck::Pod for VestingUser {}
+unsafe impl bytemuck::Zeroable for VestingUser {}
+
 impl VestingUser {
     pub fn new(percent: u8, pubkey: Pubkey, period: u8, client_planned_token_amount: u64) -> Self {
         Self {
@@ -139,29 +939,195 @@ impl VestingUser {
             claimed_tokens: 0,
             unlocked_at_tge: percent,
             unlocking_period: period,
+            amendment_count: 0,
+            frozen: 0,
+            suspended: 0,
+            suspended_at: 0,
+            suspended_seconds: 0,
+            accelerated_tokens: 0,
+            claim_deadline: 0,
+            staked_tokens: 0,
+            receipt_mint: Pubkey::default(),
+            curve_tokens: 0,
+            share_start: 0,
+            padding: 0,
         }
     }
 }
 
+// Header of the schedule account. After it come `capacity` grants and then a bitmap with a bit
+// per grant, set while the slot holds a grant of any status, so a clear bit is a free slot.
+// VestingScheduleMut and VestingScheduleRef give access to all of it, grow_schedule adds room.
 #[account(zero_copy)]
 pub struct VestingSchedule {
-    // TGE = TOKEN GENESIS EVENT
+    pub version: u8,
+    pub padding: [u8; 7],
+    // number of PendingToken grants
     pub len: u64,
-    pub data: [VestingUser; 300],
+    // number of grants the account has room for
+    pub capacity: u64,
+}
+
+pub struct VestingScheduleMut<'a> {
+    header: &'a mut VestingSchedule,
+    pub data: &'a mut [VestingUser],
+    used_slots: &'a mut [u8],
+}
+
+#[derive(Clone, Copy)]
+pub struct VestingScheduleRef<'a> {
+    header: &'a VestingSchedule,
+    pub data: &'a [VestingUser],
+    used_slots: &'a [u8],
+}
+
+// Checks the schedule account data, discriminator included, returns where the grants end.
+// The version is only checked for accounts the program writes to, clients read upgraded copies.
+fn schedule_grants_end(data: &[u8], check_version: bool) -> std::result::Result<usize, ProgramError> {
+    if data.len() < utils::layout::SCHEDULE_HEADER_END || data[..8] != VestingSchedule::discriminator() {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let header = bytemuck::from_bytes::<VestingSchedule>(&data[8..utils::layout::SCHEDULE_HEADER_END]);
+    if check_version && header.version != utils::layout::LAYOUT_VERSION {
+        return Err(ErrorCode::ScheduleLayoutOutdated.into());
+    }
+    if utils::layout::capacity_for(data.len()) != Some(header.capacity) {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(utils::layout::SCHEDULE_HEADER_END + header.capacity as usize * std::mem::size_of::<VestingUser>())
+}
+
+fn slot_word(used_slots: &[u8], word: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&used_slots[word * 8..word * 8 + 8]);
+    u64::from_le_bytes(bytes)
 }
 
-impl Default for VestingSchedule {
-    #[cfg(not(test))]
-    fn default() -> Self {
-        unimplemented!()
+fn next_free_slot(used_slots: &[u8], capacity: usize) -> Option<usize> {
+    (0..used_slots.len() / 8)
+        .map(|word| (word, slot_word(used_slots, word)))
+        .find(|(_, bits)| *bits != u64::MAX)
+        .map(|(word, bits)| word * 64 + bits.trailing_ones() as usize)
+        .filter(|index| *index < capacity)
+}
+
+impl<'a> VestingScheduleMut<'a> {
+    pub fn load(data: &'a mut [u8]) -> std::result::Result<Self, ProgramError> {
+        Self::view(data, true)
+    }
+
+    fn view(data: &'a mut [u8], check_version: bool) -> std::result::Result<Self, ProgramError> {
+        let grants_end = schedule_grants_end(data, check_version)?;
+        let (header, rest) = data[8..].split_at_mut(utils::layout::SCHEDULE_HEADER_END - 8);
+        let (grants, used_slots) = rest.split_at_mut(grants_end - utils::layout::SCHEDULE_HEADER_END);
+        Ok(Self {
+            header: bytemuck::from_bytes_mut(header),
+            data: bytemuck::cast_slice_mut(grants),
+            used_slots,
+        })
+    }
+
+    // Claims a freshly created, zeroed account, its size decides the capacity
+    pub fn init(data: &'a mut [u8]) -> std::result::Result<Self, ProgramError> {
+        if data.len() < 8 || data[..8] != [0; 8] {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        let capacity = utils::layout::capacity_for(data.len()).ok_or(ProgramError::InvalidAccountData)?;
+        data[..8].copy_from_slice(&VestingSchedule::discriminator());
+        let header = bytemuck::from_bytes_mut::<VestingSchedule>(&mut data[8..utils::layout::SCHEDULE_HEADER_END]);
+        header.version = utils::layout::LAYOUT_VERSION;
+        header.capacity = capacity;
+        Self::load(data)
+    }
+
+    pub fn is_slot_used(&self, index: usize) -> bool {
+        slot_word(self.used_slots, index / 64) & (1 << (index % 64)) != 0
+    }
+
+    pub fn set_slot_used(&mut self, index: usize, used: bool) {
+        let bit = 1u64 << (index % 64);
+        let bits = slot_word(self.used_slots, index / 64);
+        let bits = if used { bits | bit } else { bits & !bit };
+        self.used_slots[index / 64 * 8..index / 64 * 8 + 8].copy_from_slice(&bits.to_le_bytes());
     }
 
-    #[cfg(test)]
-    #[inline(never)]
-    fn default() -> Self {
-        let data: [VestingUser; utils::utils::MAX_VESTING_USERS] =
-            unsafe { std::mem::MaybeUninit::zeroed().assume_init() };
+    pub fn next_free_slot(&self) -> Option<usize> {
+        next_free_slot(self.used_slots, self.data.len())
+    }
+}
+
+impl<'a> VestingScheduleRef<'a> {
+    pub fn load(data: &'a [u8]) -> std::result::Result<Self, ProgramError> {
+        Self::view(data, true)
+    }
+
+    fn view(data: &'a [u8], check_version: bool) -> std::result::Result<Self, ProgramError> {
+        let grants_end = schedule_grants_end(data, check_version)?;
+        Ok(Self {
+            header: bytemuck::from_bytes(&data[8..utils::layout::SCHEDULE_HEADER_END]),
+            data: bytemuck::cast_slice(&data[utils::layout::SCHEDULE_HEADER_END..grants_end]),
+            used_slots: &data[grants_end..],
+        })
+    }
 
-        VestingSchedule { data, len: 0 }
+    pub fn is_slot_used(&self, index: usize) -> bool {
+        slot_word(self.used_slots, index / 64) & (1 << (index % 64)) != 0
     }
-}
\ No newline at end of file
+
+    pub fn next_free_slot(&self) -> Option<usize> {
+        next_free_slot(self.used_slots, self.data.len())
+    }
+}
+
+impl std::ops::Deref for VestingScheduleMut<'_> {
+    type Target = VestingSchedule;
+
+    fn deref(&self) -> &VestingSchedule {
+        self.header
+    }
+}
+
+impl std::ops::DerefMut for VestingScheduleMut<'_> {
+    fn deref_mut(&mut self) -> &mut VestingSchedule {
+        self.header
+    }
+}
+
+impl std::ops::Deref for VestingScheduleRef<'_> {
+    type Target = VestingSchedule;
+
+    fn deref(&self) -> &VestingSchedule {
+        self.header
+    }
+}
+
+// A schedule account held in memory, what clients decode into and tests build schedules with
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VestingScheduleBuf(Vec<u8>);
+
+impl VestingScheduleBuf {
+    // An empty schedule in the current layout
+    pub fn new(capacity: u64) -> Self {
+        let mut data = vec![0; utils::layout::schedule_size(capacity)];
+        VestingScheduleMut::init(&mut data).unwrap();
+        Self(data)
+    }
+
+    // Account data in the current layout, the version byte may still name the layout it was read from
+    pub fn from_account_data(data: &[u8]) -> std::result::Result<Self, ProgramError> {
+        VestingScheduleRef::view(data, false)?;
+        Ok(Self(data.to_vec()))
+    }
+
+    pub fn account_data(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn borrow(&self) -> VestingScheduleRef<'_> {
+        VestingScheduleRef::view(&self.0, false).unwrap()
+    }
+
+    pub fn borrow_mut(&mut self) -> VestingScheduleMut<'_> {
+        VestingScheduleMut::view(&mut self.0, false).unwrap()
+    }
+}
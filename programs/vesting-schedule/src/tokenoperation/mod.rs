@@ -1,2 +1,3 @@
 pub mod schedule;
 pub mod tokenoper;
+pub mod staking;
@@ -1,4 +1,5 @@
 use crate::ErrorCode;
+#[allow(clippy::too_many_arguments)]
 pub fn calculate_entitled_amount(
     total_amount_for_user: u64,
     tge_unlock_percent: u64,
@@ -6,37 +7,39 @@ pub fn calculate_entitled_amount(
     tokens_claimed: u64,
     tge_timestamp: u64,
     now_timestamp: u64,
+    suspended_seconds: u64,
+    accelerated_tokens: u64,
 ) -> Result<u64, ErrorCode> {
     if vesting_period == 0 && tge_unlock_percent < 100 || tge_unlock_percent > 100 {
-        return Err(ErrorCode::InvalidInput.into());
+        return Err(ErrorCode::InvalidInput);
     }
 
     if now_timestamp <= tge_timestamp {
         return Ok(0);
     }
 
-    let amount_at_tge = total_amount_for_user
-        .checked_mul(tge_unlock_percent)
-        .unwrap()
-        .checked_div(100)
-        .unwrap();
-    let total_minutes_in_period = 305 * vesting_period * 60 * 24 / 10;
-    let total_minutes_so_far = now_timestamp
-        .checked_sub(tge_timestamp)
-        .unwrap()
-        .checked_div(60)
-        .unwrap();
-    let amount_after_tge = total_amount_for_user
-        .checked_sub(amount_at_tge)
-        .unwrap()
-        .checked_mul(u64::min(total_minutes_so_far, total_minutes_in_period))
-        .unwrap()
-        .checked_div(total_minutes_in_period)
-        .unwrap_or(0);
-
-    let amount_entitled = amount_at_tge.checked_add(amount_after_tge).unwrap();
-    let amount_now = amount_entitled.checked_sub(tokens_claimed).unwrap();
-    Ok(amount_now)
+    // in u128 amount * percent and amount * minutes cannot overflow for any u64 input
+    let total_amount_for_user = total_amount_for_user as u128;
+    let amount_at_tge = total_amount_for_user * tge_unlock_percent as u128 / 100;
+    let total_minutes_in_period = 305 * vesting_period as u128 * 60 * 24 / 10;
+    // time the grant spent suspended after TGE does not vest
+    let total_minutes_so_far = ((now_timestamp - tge_timestamp).saturating_sub(suspended_seconds) / 60) as u128;
+    let amount_after_tge = ((total_amount_for_user - amount_at_tge)
+        * u128::min(total_minutes_so_far, total_minutes_in_period))
+    .checked_div(total_minutes_in_period)
+    .unwrap_or(0);
+
+    // accelerated tokens are vested on top of the curve, which then simply
+    // reaches the full amount earlier
+    let amount_entitled = u128::min(
+        amount_at_tge + amount_after_tge + accelerated_tokens as u128,
+        total_amount_for_user,
+    );
+    // claiming more than is entitled can only come from a corrupted grant
+    let amount_now = amount_entitled
+        .checked_sub(tokens_claimed as u128)
+        .ok_or(ErrorCode::InvalidInput)?;
+    Ok(amount_now as u64)
 }
 #[cfg(test)]
 mod tests {
@@ -46,34 +49,34 @@ mod tests {
 
     #[test]
     fn test_vesting_period() {
-        let err = super::calculate_entitled_amount(1_000_000, 20, 0, 0, 1, 4);
-        assert_eq!(err.err(), Some(ErrorCode::InvalidInput.into()));
+        let err = super::calculate_entitled_amount(1_000_000, 20, 0, 0, 1, 4, 0, 0);
+        assert_eq!(err.err(), Some(ErrorCode::InvalidInput));
     }
 
     #[test]
     fn test_vesting_period_all() {
-        let actual = super::calculate_entitled_amount(1_000_000, 100, 0, 0, 1, 4).unwrap();
+        let actual = super::calculate_entitled_amount(1_000_000, 100, 0, 0, 1, 4, 0, 0).unwrap();
         let expected = 1_000_000;
         assert_eq!(actual, expected);
     }
 
     #[test]
     fn test_nothing_yet_before() {
-        let actual = super::calculate_entitled_amount(1_000_000, 20, 12, 0, 1, 0).unwrap();
+        let actual = super::calculate_entitled_amount(1_000_000, 20, 12, 0, 1, 0, 0, 0).unwrap();
         let expected = 0;
         assert_eq!(actual, expected);
     }
 
     #[test]
     fn test_nothing_yet_same() {
-        let actual = super::calculate_entitled_amount(1_000_000, 20, 12, 0, 0, 0).unwrap();
+        let actual = super::calculate_entitled_amount(1_000_000, 20, 12, 0, 0, 0, 0, 0).unwrap();
         let expected = 0;
         assert_eq!(actual, expected);
     }
 
     #[test]
     fn test_only_tge() {
-        let actual = super::calculate_entitled_amount(1_000_000, 20, 12, 0, 0, 1).unwrap();
+        let actual = super::calculate_entitled_amount(1_000_000, 20, 12, 0, 0, 1, 0, 0).unwrap();
         let expected = 200_000;
         println!("Actual {}, expected {}", actual, expected);
         assert_eq!(actual, expected);
@@ -95,6 +98,8 @@ mod tests {
             0,
             tge_timestamp,
             now,
+            0,
+            0,
         )
         .unwrap();
         let expected = 466_666;
@@ -118,6 +123,8 @@ mod tests {
             0,
             tge_timestamp,
             now,
+            0,
+            0,
         )
         .unwrap();
         let expected = 600_000;
@@ -141,6 +148,8 @@ mod tests {
             0,
             tge_timestamp,
             now,
+            0,
+            0,
         )
         .unwrap();
         let expected = 1_000_000;
@@ -148,6 +157,32 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn test_suspended_time_excluded() {
+        let month = 732 * 60 * 60;
+        let actual = super::calculate_entitled_amount(1_000_000, 20, 12, 0, 0, 8 * month, 2 * month, 0).unwrap();
+        let expected = super::calculate_entitled_amount(1_000_000, 20, 12, 0, 0, 6 * month, 0, 0).unwrap();
+        assert_eq!(actual, expected);
+
+        // suspended the whole time since TGE, only the TGE unlock is vested
+        let actual = super::calculate_entitled_amount(1_000_000, 20, 12, 0, 0, 8 * month, 9 * month, 0).unwrap();
+        assert_eq!(actual, 200_000);
+    }
+
+    #[test]
+    fn test_accelerated_tokens() {
+        let month = 732 * 60 * 60;
+        let actual = super::calculate_entitled_amount(1_000_000, 20, 12, 0, 0, 6 * month, 0, 100_000).unwrap();
+        assert_eq!(actual, 700_000);
+
+        let actual = super::calculate_entitled_amount(1_000_000, 20, 12, 0, 0, 6 * month, 0, 500_000).unwrap();
+        assert_eq!(actual, 1_000_000);
+
+        // nothing is claimable before TGE, accelerated or not
+        let actual = super::calculate_entitled_amount(1_000_000, 20, 12, 0, 1, 0, 0, 500_000).unwrap();
+        assert_eq!(actual, 0);
+    }
+
     #[test]
     fn test_step_claim() {
         let tge_timestamp = 0;
@@ -166,6 +201,8 @@ mod tests {
             0,
             tge_timestamp,
             now,
+            0,
+            0,
         )
         .unwrap();
 
@@ -178,6 +215,8 @@ mod tests {
             first_actual,
             tge_timestamp,
             now,
+            0,
+            0,
         )
         .unwrap();
 
@@ -190,6 +229,8 @@ mod tests {
             second_actual + first_actual,
             tge_timestamp,
             now,
+            0,
+            0,
         )
         .unwrap();
 
@@ -200,6 +241,8 @@ mod tests {
             0,
             tge_timestamp,
             now,
+            0,
+            0,
         )
         .unwrap();
 
@@ -225,6 +268,8 @@ mod tests {
             token_claimed,
             0,
             now,
+            0,
+            0,
         )
         .unwrap();
         // try again
@@ -235,6 +280,8 @@ mod tests {
             token_claimed + can_claim_now,
             0,
             now,
+            0,
+            0,
         )
         .unwrap();
         assert_eq!(extra, 0);
@@ -257,8 +304,10 @@ mod tests {
                 token_claimed,
                 tge_timestamp as u64,
                 now_timestamp as u64,
+                0,
+                0,
             );
-            assert_eq!(err.err(), Some(ErrorCode::InvalidInput.into()));
+            assert_eq!(err.err(), Some(ErrorCode::InvalidInput));
         } else {
             let actual = super::calculate_entitled_amount(
                 total_user_amount,
@@ -267,6 +316,8 @@ mod tests {
                 token_claimed,
                 tge_timestamp as u64,
                 now_timestamp as u64,
+                0,
+                0,
             )
             .unwrap();
             assert!(actual <= total_user_amount);
@@ -291,8 +342,10 @@ mod tests {
                 token_claimed,
                 tge_timestamp as u64,
                 now_timestamp as u64,
+                0,
+                0,
             );
-            assert_eq!(err.err(), Some(ErrorCode::InvalidInput.into()));
+            assert_eq!(err.err(), Some(ErrorCode::InvalidInput));
         } else {
             let can_claim_now = super::calculate_entitled_amount(
                 total_user_amount,
@@ -301,6 +354,8 @@ mod tests {
                 token_claimed,
                 tge_timestamp as u64,
                 now_timestamp as u64,
+                0,
+                0,
             )
             .unwrap();
             let extra = super::calculate_entitled_amount(
@@ -310,9 +365,94 @@ mod tests {
                 token_claimed + can_claim_now,
                 tge_timestamp as u64,
                 now_timestamp as u64,
+                0,
+                0,
             )
             .unwrap();
             assert!(extra == 0);
         }
     }
+
+    const MONTH: u64 = 732 * 60 * 60;
+
+    // valid curves only, u64 amounts and periods as they come
+    #[allow(clippy::too_many_arguments)]
+    fn entitled(total: u64, percent: u8, period: u64, claimed: u64, tge: u64, now: u64, suspended: u64, accelerated: u64) -> u64 {
+        let percent = percent as u64 % 101;
+        let period = if percent < 100 { period.max(1) } else { period };
+        super::calculate_entitled_amount(total, percent, period, claimed, tge, now, suspended, accelerated).unwrap()
+    }
+
+    #[quickcheck]
+    #[allow(clippy::too_many_arguments)]
+    fn test_entitlement_never_decreases(
+        total: u64,
+        percent: u8,
+        period: u64,
+        tge: u64,
+        first: u64,
+        second: u64,
+        suspended: u64,
+        accelerated: u64,
+    ) -> bool {
+        let (earlier, later) = (first.min(second), first.max(second));
+        let before = entitled(total, percent, period, 0, tge, earlier, suspended, accelerated);
+        let after = entitled(total, percent, period, 0, tge, later, suspended, accelerated);
+        before <= after && after <= total
+    }
+
+    #[quickcheck]
+    fn test_everything_vested_at_end_of_period(total: u64, percent: u8, period: u16, tge: u32, after_end: u32) -> bool {
+        let period = period as u64 + 1;
+        let now = tge as u64 + period * MONTH + after_end as u64;
+        entitled(total, percent, period, 0, tge as u64, now, 0, 0) == total
+    }
+
+    #[quickcheck]
+    fn test_step_claims_add_up_to_one_claim(
+        total: u64,
+        percent: u8,
+        period: u8,
+        tge: u32,
+        mut instants: Vec<u32>,
+        accelerated: u64,
+    ) -> bool {
+        instants.sort_unstable();
+        let mut claimed = 0u64;
+        for now in instants.iter() {
+            claimed += entitled(total, percent, period as u64, claimed, tge as u64, *now as u64 * 60, 0, accelerated);
+        }
+        let last = instants.last().map_or(0, |now| *now as u64 * 60);
+        claimed == entitled(total, percent, period as u64, 0, tge as u64, last, 0, accelerated)
+    }
+
+    #[quickcheck]
+    #[allow(clippy::too_many_arguments)]
+    fn test_any_input_errors_instead_of_panicking(
+        total: u64,
+        percent: u64,
+        period: u64,
+        claimed: u64,
+        tge: u64,
+        now: u64,
+        suspended: u64,
+        accelerated: u64,
+    ) -> bool {
+        match super::calculate_entitled_amount(total, percent, period, claimed, tge, now, suspended, accelerated) {
+            Ok(amount) => amount <= total,
+            Err(err) => err == ErrorCode::InvalidInput,
+        }
+    }
+
+    #[test]
+    fn test_u64_scale_amounts() {
+        let actual = super::calculate_entitled_amount(u64::MAX, 20, 12, 0, 0, 6 * MONTH, 0, 0).unwrap();
+        assert_eq!(actual, (u64::MAX as u128 * 6 / 10) as u64);
+        let actual = super::calculate_entitled_amount(u64::MAX, 0, u64::MAX, 0, 0, u64::MAX, 0, u64::MAX).unwrap();
+        assert_eq!(actual, u64::MAX);
+
+        // more claimed than the curve ever vests
+        let err = super::calculate_entitled_amount(1_000_000, 20, 12, 1_000_001, 0, 20 * MONTH, 0, 0);
+        assert_eq!(err.err(), Some(ErrorCode::InvalidInput));
+    }
 }
@@ -0,0 +1,147 @@
+use crate::utils;
+use crate::utils::pda;
+use crate::ErrorCode;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_spl::token::TokenAccount;
+
+// Interface a whitelisted staking program has to implement, in Anchor's encoding:
+//
+//   deposit(amount: u64)   accounts: depositor (signer), source token account (mut), ..
+//   withdraw(amount: u64)  accounts: depositor (signer), destination token account (mut), ..
+//
+// The depositor is the vesting vault authority, so one position holds the staked tokens of
+// every grant of the schedule and the per grant split is kept in VestingUser::staked_tokens.
+// Whatever else the staking program needs is passed through from the remaining accounts.
+// The staking program runs with the vault authority's signature, so the token balances are
+// checked afterwards: the grant's bookkeeping only holds if exactly `amount` moved.
+pub const DEPOSIT_SIGHASH: &str = "global:deposit";
+pub const WITHDRAW_SIGHASH: &str = "global:withdraw";
+
+pub fn instruction_data(sighash: &str, amount: u64) -> Vec<u8> {
+    let mut data = hash::hash(sighash.as_bytes()).to_bytes()[..8].to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+    data
+}
+
+// Moves `amount` from the vesting vault into the staking program
+#[allow(clippy::too_many_arguments)]
+pub fn deposit<'info>(
+    amount: u64,
+    owner: &AccountInfo<'info>,
+    vesting_vault: &AccountInfo<'info>,
+    vesting_vault_authority: &AccountInfo<'info>,
+    vesting_vault_authority_seed: u8,
+    staking_program: &AccountInfo<'info>,
+    staking_accounts: &[AccountInfo<'info>],
+) -> ProgramResult {
+    let vault_before = token_amount(vesting_vault)?;
+    invoke_staking_program(
+        instruction_data(DEPOSIT_SIGHASH, amount),
+        owner,
+        vesting_vault,
+        vesting_vault_authority,
+        vesting_vault_authority_seed,
+        staking_program,
+        staking_accounts,
+    )?;
+    if vault_before.checked_sub(token_amount(vesting_vault)?) != Some(amount) {
+        return Err(ErrorCode::StakeTransferMismatch.into());
+    }
+    Ok(())
+}
+
+// Moves `amount` out of the staking program into `destination`, the vesting vault itself
+// or a token account the tokens are claimed to
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw<'info>(
+    amount: u64,
+    owner: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+    vesting_vault: &AccountInfo<'info>,
+    vesting_vault_authority: &AccountInfo<'info>,
+    vesting_vault_authority_seed: u8,
+    staking_program: &AccountInfo<'info>,
+    staking_accounts: &[AccountInfo<'info>],
+) -> ProgramResult {
+    let vault_before = token_amount(vesting_vault)?;
+    let destination_before = token_amount(destination)?;
+    invoke_staking_program(
+        instruction_data(WITHDRAW_SIGHASH, amount),
+        owner,
+        destination,
+        vesting_vault_authority,
+        vesting_vault_authority_seed,
+        staking_program,
+        staking_accounts,
+    )?;
+    // the vault only changes when the tokens come back into it
+    let vault_expected = if destination.key == vesting_vault.key {
+        vault_before.checked_add(amount)
+    } else {
+        Some(vault_before)
+    };
+    if token_amount(destination)?.checked_sub(destination_before) != Some(amount)
+        || Some(token_amount(vesting_vault)?) != vault_expected
+    {
+        return Err(ErrorCode::StakeTransferMismatch.into());
+    }
+    Ok(())
+}
+
+fn token_amount(account: &AccountInfo) -> std::result::Result<u64, ProgramError> {
+    if *account.owner != spl_token::ID {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(TokenAccount::try_deserialize(&mut &account.try_borrow_data()?[..])?.amount)
+}
+
+fn invoke_staking_program<'info>(
+    data: Vec<u8>,
+    owner: &AccountInfo<'info>,
+    token_account: &AccountInfo<'info>,
+    vesting_vault_authority: &AccountInfo<'info>,
+    vesting_vault_authority_seed: u8,
+    staking_program: &AccountInfo<'info>,
+    staking_accounts: &[AccountInfo<'info>],
+) -> ProgramResult {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(vesting_vault_authority.key(), true),
+        AccountMeta::new(token_account.key(), false),
+    ];
+    accounts.extend(staking_accounts.iter().map(|account| AccountMeta {
+        pubkey: account.key(),
+        is_signer: account.is_signer,
+        is_writable: account.is_writable,
+    }));
+    let instruction = Instruction {
+        program_id: staking_program.key(),
+        accounts,
+        data,
+    };
+
+    let mut account_infos = vec![vesting_vault_authority.clone(), token_account.clone()];
+    account_infos.extend_from_slice(staking_accounts);
+    account_infos.push(staking_program.clone());
+
+    let authority_seeds = pda::make_vesting_pda_seeds(owner.key, utils::utils::TOKEN_MINT_TAG);
+    let bump = [vesting_vault_authority_seed];
+    let seeds = [authority_seeds[0].as_ref(), authority_seeds[1].as_ref(), bump.as_ref()];
+    invoke_signed(&instruction, &account_infos, &[&seeds[..]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instruction_data() {
+        let data = instruction_data(WITHDRAW_SIGHASH, 42);
+        assert_eq!(16, data.len());
+        assert_eq!(hash::hash(b"global:withdraw").to_bytes()[..8], data[..8]);
+        assert_eq!(42u64.to_le_bytes(), data[8..]);
+        assert_ne!(data[..8], instruction_data(DEPOSIT_SIGHASH, 42)[..8]);
+    }
+}
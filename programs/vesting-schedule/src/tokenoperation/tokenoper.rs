@@ -1,7 +1,10 @@
 use crate::utils;
 use crate::utils::pda::{self};
-use anchor_lang::{prelude::AccountInfo, CpiContext};
-use anchor_spl::token::{self, Transfer};
+use crate::vesting_operations::ReceiptHolding;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::{prelude::AccountInfo, AccountDeserialize, CpiContext};
+use spl_token::instruction::AuthorityType;
+use anchor_spl::token::{self, MintTo, SetAuthority, TokenAccount, Transfer};
 
 #[allow(clippy::too_many_arguments)]
 pub fn vesting_transfer<'info>(
@@ -12,7 +15,7 @@ pub fn vesting_transfer<'info>(
     from_vault_authority: &AccountInfo<'info>,
     from_vault_authority_seed: u8,
     token_program: &AccountInfo<'info>,
-) {
+) -> ProgramResult {
     let from_vault_seed: u8 = from_vault_authority_seed;
     let from_vault_authority_bump = vec![from_vault_seed];
     let from_vault_authority_pda_seeds =
@@ -31,5 +34,50 @@ pub fn vesting_transfer<'info>(
     };
 
     let cpi_ctx = CpiContext::new(token_program.clone(), cpi_transfer_accounts).with_signer(signer);
-    token::transfer(cpi_ctx, amount).unwrap();
+    token::transfer(cpi_ctx, amount)
+}
+
+// Mints the single receipt token and drops the mint authority, so there is never a second one
+pub fn mint_receipt<'info>(
+    owner: &AccountInfo<'info>,
+    receipt_mint: &AccountInfo<'info>,
+    receipt_account: &AccountInfo<'info>,
+    vesting_vault_authority: &AccountInfo<'info>,
+    vesting_vault_authority_seed: u8,
+    token_program: &AccountInfo<'info>,
+) -> ProgramResult {
+    let authority_pda_seeds = pda::make_vesting_pda_seeds(owner.key, utils::utils::TOKEN_MINT_TAG);
+    let bump = [vesting_vault_authority_seed];
+    let seeds = [authority_pda_seeds[0].as_ref(), authority_pda_seeds[1].as_ref(), bump.as_ref()];
+    let signer = &[&seeds[..]];
+
+    let cpi_mint_accounts = MintTo {
+        mint: receipt_mint.clone(),
+        to: receipt_account.clone(),
+        authority: vesting_vault_authority.clone(),
+    };
+    token::mint_to(CpiContext::new(token_program.clone(), cpi_mint_accounts).with_signer(signer), 1)?;
+
+    let cpi_authority_accounts = SetAuthority {
+        account_or_mint: receipt_mint.clone(),
+        current_authority: vesting_vault_authority.clone(),
+    };
+    token::set_authority(
+        CpiContext::new(token_program.clone(), cpi_authority_accounts).with_signer(signer),
+        AuthorityType::MintTokens,
+        None,
+    )
+}
+
+// What the account passed as a grant's receipt holds, None unless it is a token account
+pub fn receipt_holding(account: &AccountInfo) -> Option<ReceiptHolding> {
+    if *account.owner != spl_token::ID {
+        return None;
+    }
+    let token_account = TokenAccount::try_deserialize(&mut &account.try_borrow_data().ok()?[..]).ok()?;
+    Some(ReceiptHolding {
+        mint: token_account.mint,
+        holder: token_account.owner,
+        amount: token_account.amount,
+    })
 }
@@ -0,0 +1,340 @@
+use crate::utils::utils::EventStatus;
+use crate::{MerkleClaimStatus, VestingData, VestingSchedule, VestingUser};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::invoke;
+use anchor_lang::solana_program::system_instruction;
+use std::mem::size_of;
+
+// Layout of the program's accounts, the version byte follows the discriminator.
+//
+// 1: the first deployed layout, without a version byte and told apart by size. VestingData had
+//    no admin and no schedule, the schedule held 300 packed GrantV1. No MerkleClaimStatus yet.
+// 2: the version byte. Schedule grants aligned, the capacity in the header and the used slot
+//    bitmap behind the grants, so the schedule can grow.
+pub const LAYOUT_VERSION: u8 = 2;
+pub const VERSION_OFFSET: usize = 8;
+
+// v1 schedules had room for this many grants
+const LEGACY_CAPACITY: usize = 300;
+// Discriminator and len of a v1 schedule, and the fields of a v1 VestingData after the discriminator
+const SCHEDULE_V1_HEADER_LEN: usize = 8 + 8;
+const GRANT_V1_LEN: usize = 51;
+const VESTING_DATA_V1_LEN: usize = 8 + 32 + 32 + 1 + 8;
+// Where the v1 fields of VestingData went: behind the version, the admin and the schedule
+const VESTING_DATA_V1_FIELDS: usize = VERSION_OFFSET + 1 + 32 + 32;
+
+const GRANT_SIZE: usize = size_of::<VestingUser>();
+pub const SCHEDULE_HEADER_END: usize = 8 + size_of::<VestingSchedule>();
+// Most grants one grow_schedule adds, an account can grow by 10 KiB per instruction
+pub const GROWTH_CHUNK: u64 = 64;
+
+// Size of a schedule account with room for `capacity` grants
+pub fn schedule_size(capacity: u64) -> usize {
+    SCHEDULE_HEADER_END + capacity as usize * GRANT_SIZE + slot_words(capacity) * 8
+}
+
+fn slot_words(capacity: u64) -> usize {
+    (capacity as usize).div_ceil(64)
+}
+
+// The capacity of a schedule account of that size, None if no capacity gives that size.
+// Every 64 grants take 64 * GRANT_SIZE + 8 bytes, so only two capacities come close.
+pub fn capacity_for(size: usize) -> Option<u64> {
+    let capacity = (size.checked_sub(SCHEDULE_HEADER_END)? * 64 / (64 * GRANT_SIZE + 8)) as u64;
+    [capacity, capacity.saturating_sub(1)]
+        .iter()
+        .copied()
+        .find(|capacity| schedule_size(*capacity) == size)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccountLayout {
+    VestingData,
+    VestingSchedule,
+    MerkleClaimStatus,
+}
+
+impl AccountLayout {
+    // Size of an account migrated to the current layout, discriminator included
+    pub fn size(self) -> usize {
+        match self {
+            AccountLayout::VestingData => 8 + VestingData::default().try_to_vec().unwrap().len(),
+            AccountLayout::VestingSchedule => schedule_size(LEGACY_CAPACITY as u64),
+            AccountLayout::MerkleClaimStatus => 8 + MerkleClaimStatus::default().try_to_vec().unwrap().len(),
+        }
+    }
+
+    // None for accounts that did not exist in v1
+    pub fn v1_size(self) -> Option<usize> {
+        match self {
+            AccountLayout::VestingData => Some(8 + VESTING_DATA_V1_LEN),
+            AccountLayout::VestingSchedule => Some(SCHEDULE_V1_HEADER_LEN + LEGACY_CAPACITY * GRANT_V1_LEN),
+            AccountLayout::MerkleClaimStatus => None,
+        }
+    }
+}
+
+// A grant as v1 stored it, packed
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
+struct GrantV1 {
+    status: u8,
+    user: Pubkey,
+    planned_tokens: u64,
+    claimed_tokens: u64,
+    unlocked_at_tge: u8,
+    unlocking_period: u8,
+}
+
+impl From<GrantV1> for VestingUser {
+    fn from(grant: GrantV1) -> Self {
+        VestingUser {
+            status: grant.status,
+            user: grant.user,
+            planned_tokens: grant.planned_tokens,
+            claimed_tokens: grant.claimed_tokens,
+            unlocked_at_tge: grant.unlocked_at_tge,
+            unlocking_period: grant.unlocking_period,
+            ..bytemuck::Zeroable::zeroed()
+        }
+    }
+}
+
+// Version of the account's layout
+pub fn version_of(data: &[u8], layout: AccountLayout) -> Option<u8> {
+    if Some(data.len()) == layout.v1_size() {
+        Some(1)
+    } else if data.len() >= layout.size() || (layout == AccountLayout::VestingSchedule && data.len() > VERSION_OFFSET) {
+        // schedules can be sized for any capacity since v2
+        Some(data[VERSION_OFFSET])
+    } else {
+        None
+    }
+}
+
+// The account in the current layout, keeping the version it was read with.
+// A v1 VestingData comes back without its admin and schedule, migrate fills them in.
+pub fn upgrade(data: &[u8], layout: AccountLayout) -> Option<Vec<u8>> {
+    let version = version_of(data, layout)?;
+    let mut upgraded = data.to_vec();
+    if (1..LAYOUT_VERSION).contains(&version) {
+        upgraded.resize(layout.size(), 0);
+        convert(&mut upgraded, version, layout).ok()?;
+        upgraded[VERSION_OFFSET] = version;
+    }
+    Some(upgraded)
+}
+
+// Rewrites the account from `version` to the current layout in place,
+// `data` has to have the size of the migrated account already
+pub fn convert(data: &mut [u8], version: u8, layout: AccountLayout) -> ProgramResult {
+    if version == 0 || version > LAYOUT_VERSION || data.len() != layout.size() {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    match (layout, version) {
+        (AccountLayout::VestingData, 1) => vesting_data_v1_to_v2(data),
+        (AccountLayout::VestingSchedule, 1) => schedule_v1_to_v2(data)?,
+        // claim statuses came with v2
+        (AccountLayout::MerkleClaimStatus, 1) => return Err(ProgramError::InvalidAccountData),
+        _ => {}
+    }
+    data[VERSION_OFFSET] = LAYOUT_VERSION;
+    Ok(())
+}
+
+// The v1 fields move behind the version, the admin and the schedule, which are left zero.
+// Everything added after them starts out zero as well.
+fn vesting_data_v1_to_v2(data: &mut [u8]) {
+    data.copy_within(VERSION_OFFSET..VERSION_OFFSET + VESTING_DATA_V1_LEN, VESTING_DATA_V1_FIELDS);
+    data[VERSION_OFFSET..VESTING_DATA_V1_FIELDS].fill(0);
+    data[VESTING_DATA_V1_FIELDS + VESTING_DATA_V1_LEN..].fill(0);
+}
+
+// A v1 schedule ends its header with the len, followed by the packed grants.
+// Every grant moves further into the account, so going from the last one backwards
+// no grant is overwritten before it was read.
+fn schedule_v1_to_v2(data: &mut [u8]) -> ProgramResult {
+    let mut len = [0u8; 8];
+    len.copy_from_slice(&data[SCHEDULE_V1_HEADER_LEN - 8..SCHEDULE_V1_HEADER_LEN]);
+    let mut used_slots = [0u64; 5];
+    for index in (0..LEGACY_CAPACITY).rev() {
+        let start = SCHEDULE_V1_HEADER_LEN + index * GRANT_V1_LEN;
+        let grant: VestingUser = GrantV1::try_from_slice(&data[start..start + GRANT_V1_LEN])?.into();
+        if grant.status != EventStatus::Inactive as u8 {
+            used_slots[index / 64] |= 1 << (index % 64);
+        }
+        let start = SCHEDULE_HEADER_END + index * GRANT_SIZE;
+        data[start..start + GRANT_SIZE].copy_from_slice(bytemuck::bytes_of(&grant));
+    }
+
+    let header = bytemuck::from_bytes_mut::<VestingSchedule>(&mut data[8..SCHEDULE_HEADER_END]);
+    header.padding = [0; 7];
+    header.len = u64::from_le_bytes(len);
+    header.capacity = LEGACY_CAPACITY as u64;
+    let bitmap = SCHEDULE_HEADER_END + LEGACY_CAPACITY * GRANT_SIZE;
+    for (word, bits) in used_slots.iter().enumerate() {
+        data[bitmap + word * 8..bitmap + word * 8 + 8].copy_from_slice(&bits.to_le_bytes());
+    }
+    Ok(())
+}
+
+// Makes room for `new_capacity` grants in a schedule that already has the size for it:
+// the bitmap moves behind the new grants, which start out as free slots
+pub fn grow(data: &mut [u8], capacity: u64, new_capacity: u64) {
+    let grants_end = SCHEDULE_HEADER_END + capacity as usize * GRANT_SIZE;
+    let new_grants_end = SCHEDULE_HEADER_END + new_capacity as usize * GRANT_SIZE;
+    let bitmap_size = slot_words(capacity) * 8;
+    data.copy_within(grants_end..grants_end + bitmap_size, new_grants_end);
+    data[grants_end..new_grants_end].fill(0);
+    data[new_grants_end + bitmap_size..].fill(0);
+    bytemuck::from_bytes_mut::<VestingSchedule>(&mut data[8..SCHEDULE_HEADER_END]).capacity = new_capacity;
+}
+
+// Reallocates the account to `size`, `payer` tops up the rent
+pub fn resize<'info>(
+    account: &AccountInfo<'info>,
+    size: usize,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> ProgramResult {
+    let rent = Rent::get()?.minimum_balance(size);
+    let lamports = account.lamports();
+    if rent > lamports {
+        invoke(
+            &system_instruction::transfer(payer.key, account.key, rent - lamports),
+            &[payer.clone(), account.clone(), system_program.clone()],
+        )?;
+    }
+    account.realloc(size, false)
+}
+
+// Converts the account to the current layout in place, returns the version it had.
+// `payer` tops up the rent for the bytes the account grows by.
+pub fn migrate<'info>(
+    account: &AccountInfo<'info>,
+    layout: AccountLayout,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> std::result::Result<u8, ProgramError> {
+    let version = version_of(&account.try_borrow_data()?, layout).ok_or(ProgramError::InvalidAccountData)?;
+    if version == LAYOUT_VERSION {
+        return Ok(version);
+    }
+
+    resize(account, layout.size(), payer, system_program)?;
+    convert(&mut account.try_borrow_mut_data()?, version, layout)?;
+    Ok(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{VestingScheduleBuf, VestingScheduleRef};
+    use anchor_lang::AccountDeserialize;
+    use bytemuck::Zeroable;
+
+    // Account data the first deployed program wrote: an admin of [1; 32] with a vault of [2; 32],
+    // grants in slots 0, 2 and 299 and the one in slot 1 removed again
+    const VESTING_DATA_V1: &[u8] = include_bytes!("../../tests/fixtures/vesting_data_v1.bin");
+    const VESTING_SCHEDULE_V1: &[u8] = include_bytes!("../../tests/fixtures/vesting_schedule_v1.bin");
+
+    #[test]
+    fn test_vesting_data_v1() {
+        let layout = AccountLayout::VestingData;
+        assert_eq!(Some(1), version_of(VESTING_DATA_V1, layout));
+        assert!(VestingData::try_deserialize(&mut &VESTING_DATA_V1[..]).is_err());
+
+        let upgraded = VestingData::try_deserialize(&mut &upgrade(VESTING_DATA_V1, layout).unwrap()[..]).unwrap();
+        assert_eq!((1, Pubkey::default(), Pubkey::default()), (upgraded.version, upgraded.admin, upgraded.vesting_schedule));
+        assert_eq!((1_629_158_400, 450_000), (upgraded.tge_timestamp, upgraded.total_issued_so_far));
+        let authority = Pubkey::find_program_address(&[&[1; 32], b"tmt"], &crate::ID);
+        assert_eq!((Pubkey::new(&[2; 32]), authority), (upgraded.vesting_vault, (upgraded.vesting_vault_authority, upgraded.vesting_vault_authority_seed)));
+
+        // what migrate does to the account data after growing it, stale bytes included
+        let mut data = VESTING_DATA_V1.to_vec();
+        data.resize(layout.size(), 0xff);
+        convert(&mut data, 1, layout).unwrap();
+        let migrated = VestingData::try_deserialize(&mut &data[..]).unwrap();
+        let expected = VestingData { version: LAYOUT_VERSION, ..upgraded };
+        assert_eq!(expected.try_to_vec().unwrap(), migrated.try_to_vec().unwrap());
+        assert!(!migrated.paused && !migrated.transferable && migrated.staking_program == Pubkey::default());
+        assert_eq!(Some(LAYOUT_VERSION), version_of(&data, layout));
+        assert_eq!(None, version_of(&VESTING_DATA_V1[..VESTING_DATA_V1.len() - 1], layout));
+    }
+
+    #[test]
+    fn test_merkle_claim_status_has_no_v1() {
+        let layout = AccountLayout::MerkleClaimStatus;
+        assert_eq!(None, layout.v1_size());
+        let mut data = vec![1; layout.size()];
+        assert_eq!(Err(ProgramError::InvalidAccountData), convert(&mut data, 1, layout));
+        assert_eq!(None, version_of(&data[..layout.size() - 1], layout));
+    }
+
+    #[test]
+    fn test_vesting_schedule_v1() {
+        let layout = AccountLayout::VestingSchedule;
+        assert_eq!(layout.v1_size(), Some(VESTING_SCHEDULE_V1.len()));
+        assert_eq!(GRANT_V1_LEN, GrantV1::default().try_to_vec().unwrap().len());
+        assert_eq!(0, GRANT_SIZE % 8);
+        assert_eq!(Some(1), version_of(VESTING_SCHEDULE_V1, layout));
+
+        let mut data = VESTING_SCHEDULE_V1.to_vec();
+        data.resize(layout.size(), 0xff);
+        convert(&mut data, 1, layout).unwrap();
+        let schedule = VestingScheduleRef::load(&data).unwrap();
+        assert_eq!((LAYOUT_VERSION, 3, 300), (schedule.version, { schedule.len }, { schedule.capacity }));
+        assert!(schedule.is_slot_used(0) && schedule.is_slot_used(2) && schedule.is_slot_used(299));
+        assert_eq!(Some(1), schedule.next_free_slot());
+
+        let grant = |status, user, planned_tokens, claimed_tokens, unlocked_at_tge, unlocking_period| VestingUser {
+            status,
+            user: Pubkey::new(&[user; 32]),
+            planned_tokens,
+            claimed_tokens,
+            unlocked_at_tge,
+            unlocking_period,
+            ..Zeroable::zeroed()
+        };
+        assert_eq!(grant(1, 10, 1_000_000, 200_000, 20, 12), schedule.data[0]);
+        assert_eq!(grant(0, 11, 500_000, 0, 10, 6), schedule.data[1]);
+        assert_eq!(grant(1, 12, 750_000, 250_000, 100, 0), schedule.data[2]);
+        assert_eq!(grant(1, 13, 42, 0, 0, 1), schedule.data[299]);
+        assert!(schedule.data[3..299].iter().all(|grant| *grant == VestingUser::zeroed()));
+
+        // the decoders read it the same before it is migrated
+        let mut upgraded = upgrade(VESTING_SCHEDULE_V1, layout).unwrap();
+        assert_eq!(1, upgraded[VERSION_OFFSET]);
+        upgraded[VERSION_OFFSET] = LAYOUT_VERSION;
+        assert!(upgraded == data);
+    }
+
+    #[test]
+    fn test_grow_schedule() {
+        for capacity in [0, 1, 63, 64, 65, 300, 1_000, 100_000] {
+            assert_eq!(Some(capacity), capacity_for(schedule_size(capacity)));
+            assert_eq!(None, capacity_for(schedule_size(capacity) + 8));
+        }
+        // a v1 schedule is never mistaken for one of a capacity
+        assert_eq!(None, capacity_for(AccountLayout::VestingSchedule.v1_size().unwrap()));
+        assert!(schedule_size(300 + GROWTH_CHUNK) - schedule_size(300) <= 10 * 1024);
+
+        let mut buffer = VestingScheduleBuf::new(300);
+        let mut schedule = buffer.borrow_mut();
+        for index in [0, 64, 299] {
+            schedule.data[index] = VestingUser::new(20, Pubkey::new_unique(), 12, 1_000);
+            schedule.set_slot_used(index, true);
+        }
+        let grants = schedule.data.to_vec();
+
+        let mut data = buffer.account_data().to_vec();
+        data.resize(schedule_size(364), 0xff);
+        grow(&mut data, 300, 364);
+        let schedule = VestingScheduleRef::load(&data).unwrap();
+        assert_eq!(364, { schedule.capacity });
+        assert_eq!(grants[..], schedule.data[..300]);
+        assert!(schedule.is_slot_used(0) && schedule.is_slot_used(64) && schedule.is_slot_used(299));
+        assert!((300..364).all(|index| !schedule.is_slot_used(index)));
+        assert!(schedule.data[300..].iter().all(|grant| *grant == VestingUser::zeroed()));
+        assert_eq!(Some(1), schedule.next_free_slot());
+    }
+}
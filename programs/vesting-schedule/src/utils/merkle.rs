@@ -0,0 +1,107 @@
+// Merkle commitment for schedules with too many beneficiaries to store on-chain.
+//
+// Leaf = keccak256(0x00 || beneficiary (32 bytes) || planned_tokens (u64 LE)
+//                  || unlocked_at_tge (u8) || unlocking_period (u8))
+// Node = keccak256(0x01 || min(left, right) || max(left, right))
+//
+// Children are sorted before hashing so a proof is just the list of sibling
+// hashes from the leaf up. An odd node at the end of a level is carried up
+// unchanged. The prefixes keep a leaf from ever being passed off as a node.
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::keccak;
+
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+pub fn leaf_hash(beneficiary: &Pubkey, planned_tokens: u64, unlocked_at_tge: u8, unlocking_period: u8) -> [u8; 32] {
+    keccak::hashv(&[
+        LEAF_PREFIX,
+        beneficiary.as_ref(),
+        &planned_tokens.to_le_bytes(),
+        &[unlocked_at_tge],
+        &[unlocking_period],
+    ])
+    .to_bytes()
+}
+
+pub fn node_hash(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+    keccak::hashv(&[NODE_PREFIX, left, right]).to_bytes()
+}
+
+pub fn verify_proof(root: &[u8; 32], leaf: [u8; 32], proof: &[[u8; 32]]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| node_hash(&node, sibling));
+    &computed == root
+}
+
+// Builds the tree off-chain from the leaves in the order given
+pub struct MerkleTree {
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+impl MerkleTree {
+    pub fn new(leaves: Vec<[u8; 32]>) -> Self {
+        let mut levels = vec![leaves];
+        while levels.last().unwrap().len() > 1 {
+            let next = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => node_hash(left, right),
+                    [single] => *single,
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(next);
+        }
+        MerkleTree { levels }
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.levels.last().unwrap().first().copied().unwrap_or_default()
+    }
+
+    pub fn proof(&self, mut index: usize) -> Vec<[u8; 32]> {
+        let mut proof = Vec::new();
+        for level in &self.levels[..self.levels.len() - 1] {
+            let sibling = index ^ 1;
+            if sibling < level.len() {
+                proof.push(level[sibling]);
+            }
+            index /= 2;
+        }
+        proof
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(count: u8) -> Vec<[u8; 32]> {
+        (0..count)
+            .map(|i| leaf_hash(&Pubkey::new_from_array([i; 32]), 1_000_000 * i as u64, 20, 12))
+            .collect()
+    }
+
+    #[test]
+    fn test_proofs_verify() {
+        for count in 1..=9 {
+            let leaves = leaves(count);
+            let tree = MerkleTree::new(leaves.clone());
+            for (index, leaf) in leaves.iter().enumerate() {
+                assert!(verify_proof(&tree.root(), *leaf, &tree.proof(index)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_wrong_leaf_rejected() {
+        let leaves = leaves(5);
+        let tree = MerkleTree::new(leaves.clone());
+        let forged = leaf_hash(&Pubkey::new_from_array([2; 32]), 3_000_000, 20, 12);
+        assert!(!verify_proof(&tree.root(), forged, &tree.proof(2)));
+        assert!(!verify_proof(&tree.root(), leaves[2], &tree.proof(3)));
+    }
+}
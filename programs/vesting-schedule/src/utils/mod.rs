@@ -1,2 +1,5 @@
+pub mod layout;
+pub mod merkle;
 pub mod pda;
+pub mod runtime;
 pub mod utils;
@@ -1,3 +1,4 @@
+use crate::utils;
 use anchor_lang::prelude::Pubkey;
 
 pub struct VestingPdaAddress {
@@ -16,11 +17,20 @@ pub fn make_vesting_pda_seeds(owner: &Pubkey, tag: &str) -> [Vec<u8>; 2] {
     signer_seeds
 }
 
+pub fn make_claim_status_pda_pubkey(vesting_data: &Pubkey, beneficiary: &Pubkey, program: &Pubkey) -> VestingPdaAddress {
+    let seeds = &[
+        utils::utils::CLAIM_STATUS_TAG.as_ref(),
+        vesting_data.as_ref(),
+        beneficiary.as_ref(),
+    ];
+    let (key, seed) = Pubkey::find_program_address(seeds, program);
+    VestingPdaAddress { key, seed }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::str::FromStr;
-    use crate::{utils};
 
     #[test]
     fn test_generate_seed() {
@@ -0,0 +1,45 @@
+use crate::tokenoperation;
+use anchor_lang::prelude::*;
+
+// What the claim logic needs from the runtime, so tests can swap in a fixed clock and an
+// in-memory ledger instead of the Clock sysvar and the token program.
+pub trait TimeSource {
+    fn unix_timestamp(&self) -> std::result::Result<u64, ProgramError>;
+}
+
+pub trait TokenLedger {
+    // Pays `amount` out of the vesting vault to the claimant
+    fn transfer_from_vault(&mut self, amount: u64) -> ProgramResult;
+}
+
+pub struct SysvarClock;
+
+impl TimeSource for SysvarClock {
+    fn unix_timestamp(&self) -> std::result::Result<u64, ProgramError> {
+        Ok(Clock::get()?.unix_timestamp as u64)
+    }
+}
+
+// The accounts of a payout from the vesting vault, signed by the vault authority PDA
+pub struct VaultTransfer<'a, 'info> {
+    pub owner: &'a AccountInfo<'info>,
+    pub to_vault: &'a AccountInfo<'info>,
+    pub from_vault: &'a AccountInfo<'info>,
+    pub from_vault_authority: &'a AccountInfo<'info>,
+    pub from_vault_authority_seed: u8,
+    pub token_program: &'a AccountInfo<'info>,
+}
+
+impl TokenLedger for VaultTransfer<'_, '_> {
+    fn transfer_from_vault(&mut self, amount: u64) -> ProgramResult {
+        tokenoperation::tokenoper::vesting_transfer(
+            amount,
+            self.owner,
+            self.to_vault,
+            self.from_vault,
+            self.from_vault_authority,
+            self.from_vault_authority_seed,
+            self.token_program,
+        )
+    }
+}
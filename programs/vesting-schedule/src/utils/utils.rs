@@ -1,6 +1,18 @@
 pub const TOKEN_MINT_TAG: &str = "tmt";
-pub const MAX_VESTING_USERS: usize = 300;
+pub const CLAIM_STATUS_TAG: &str = "claim_status";
+// Grants a schedule created through the client has room for, grow_schedule adds more
+pub const INITIAL_CAPACITY: u64 = 300;
+pub const BPS_DENOMINATOR: u64 = 10_000;
 pub enum EventStatus {
     Inactive = 0,
     PendingToken = 1,
-}
\ No newline at end of file
+    Expired = 2,
+}
+// Return data this program left behind for a CPI caller
+pub fn return_data<T: anchor_lang::AnchorDeserialize>() -> Option<T> {
+    let (program_id, data) = anchor_lang::solana_program::program::get_return_data()?;
+    if program_id != crate::ID {
+        return None;
+    }
+    T::try_from_slice(&data).ok()
+}
@@ -1,105 +1,858 @@
-use crate::{utils, ErrorCode, VestingSchedule, VestingUser, tokenoperation, VestingData};
-use std::cell::RefMut;
+use crate::{utils, BeneficiaryChanged, ClaimableView, ErrorCode, LockedBalance, VestingScheduleMut, VestingUser, tokenoperation, VestingData, MerkleClaimStatus};
 use anchor_lang::prelude::*;
 use crate::utils::pda::VestingPdaAddress;
+use crate::utils::runtime::{TimeSource, TokenLedger};
 
-pub fn initialize_users(queue: &mut RefMut<VestingSchedule>) {
+pub fn initialize_users(queue: &mut VestingScheduleMut) {
+    queue.version = utils::layout::LAYOUT_VERSION;
     queue.len = 0;
 }
 
-pub fn initialize_vesting_data(vesting_data: &mut VestingData, vesting_vault_pubkey: Pubkey, pda_vesting_vault: VestingPdaAddress, tge_timestamp: u64) {
+pub fn initialize_vesting_data(
+    vesting_data: &mut VestingData,
+    admin: Pubkey,
+    vesting_schedule: Pubkey,
+    vesting_vault_pubkey: Pubkey,
+    pda_vesting_vault: VestingPdaAddress,
+    tge_timestamp: u64,
+) {
+    vesting_data.version = utils::layout::LAYOUT_VERSION;
+    vesting_data.admin = admin;
+    vesting_data.vesting_schedule = vesting_schedule;
     vesting_data.vesting_vault = vesting_vault_pubkey;
     vesting_data.vesting_vault_authority = pda_vesting_vault.key;
     vesting_data.vesting_vault_authority_seed = pda_vesting_vault.seed;
     vesting_data.total_issued_so_far = 0;
     vesting_data.tge_timestamp = tge_timestamp;
+    vesting_data.paused = false;
+    vesting_data.pause_stops_clock = false;
+    vesting_data.paused_at = 0;
+    vesting_data.paused_seconds = 0;
+    vesting_data.claim_deadline = 0;
+    vesting_data.total_reclaimed = 0;
+    vesting_data.merkle_root = [0; 32];
+}
+
+pub fn pause_schedule(vesting_data: &mut VestingData, stop_clock: bool, now: u64) -> Result<(), ErrorCode> {
+    if vesting_data.paused {
+        return Err(ErrorCode::InvalidInput);
+    }
+    vesting_data.paused = true;
+    vesting_data.pause_stops_clock = stop_clock;
+    vesting_data.paused_at = now;
+    Ok(())
+}
+
+pub fn unpause_schedule(vesting_data: &mut VestingData, now: u64) -> Result<(), ErrorCode> {
+    if !vesting_data.paused {
+        return Err(ErrorCode::InvalidInput);
+    }
+    if vesting_data.pause_stops_clock {
+        // Time before TGE never counts towards vesting anyway
+        let stopped_from = u64::max(vesting_data.paused_at, vesting_data.tge_timestamp);
+        vesting_data.paused_seconds = vesting_data
+            .paused_seconds
+            .checked_add(now.saturating_sub(stopped_from))
+            .ok_or(ErrorCode::InvalidInput)?;
+    }
+    vesting_data.paused = false;
+    vesting_data.pause_stops_clock = false;
+    vesting_data.paused_at = 0;
+    Ok(())
+}
+
+// The timestamp the entitlement curve is evaluated at,
+// with the time the clock was stopped by pauses taken out
+pub fn vesting_clock(vesting_data: &VestingData, now: u64) -> u64 {
+    let stopped_at = if vesting_data.paused && vesting_data.pause_stops_clock {
+        u64::min(now, u64::max(vesting_data.paused_at, vesting_data.tge_timestamp))
+    } else {
+        now
+    };
+    stopped_at.saturating_sub(vesting_data.paused_seconds)
+}
+
+pub fn suspend_vesting(
+    schedule: &mut VestingScheduleMut,
+    index: usize,
+    now: u64,
+) -> Result<(), ErrorCode> {
+    if index >= schedule.data.len()
+        || schedule.data[index].status != utils::utils::EventStatus::PendingToken as u8
+        || schedule.data[index].suspended != 0
+    {
+        return Err(ErrorCode::InvalidInput);
+    }
+    schedule.data[index].suspended = 1;
+    schedule.data[index].suspended_at = now;
+    Ok(())
+}
+
+pub fn resume_vesting(
+    schedule: &mut VestingScheduleMut,
+    index: usize,
+    tge_timestamp: u64,
+    now: u64,
+) -> Result<(), ErrorCode> {
+    if index >= schedule.data.len()
+        || schedule.data[index].status != utils::utils::EventStatus::PendingToken as u8
+        || schedule.data[index].suspended == 0
+    {
+        return Err(ErrorCode::InvalidInput);
+    }
+    let suspended_seconds = grant_suspended_seconds(&schedule.data[index], tge_timestamp, now);
+    let grant = &mut schedule.data[index];
+    grant.suspended_seconds = suspended_seconds;
+    grant.suspended = 0;
+    grant.suspended_at = 0;
+    Ok(())
+}
+
+// Suspended time after TGE, including a suspension that is still running
+pub fn grant_suspended_seconds(grant: &VestingUser, tge_timestamp: u64, now: u64) -> u64 {
+    if grant.suspended == 0 {
+        return grant.suspended_seconds;
+    }
+    let suspended_from = u64::max(grant.suspended_at, tge_timestamp);
+    grant
+        .suspended_seconds
+        .saturating_add(now.saturating_sub(suspended_from))
+}
+
+// Tokens of the curve the grant vests on, a split grant vests a share of the one it was split from
+pub fn curve_tokens(grant: &VestingUser) -> u64 {
+    if grant.curve_tokens == 0 {
+        grant.planned_tokens
+    } else {
+        grant.curve_tokens
+    }
+}
+
+// What the grant's curve has vested, for an unsplit grant that is what the grant has vested
+pub fn curve_amount(grant: &VestingUser, tge_timestamp: u64, now: u64) -> Result<u64, ErrorCode> {
+    tokenoperation::schedule::calculate_entitled_amount(
+        curve_tokens(grant),
+        grant.unlocked_at_tge as u64,
+        grant.unlocking_period as u64,
+        0,
+        tge_timestamp,
+        now,
+        grant_suspended_seconds(grant, tge_timestamp, now),
+        grant.accelerated_tokens,
+    )
+}
+
+// A grant owns the tokens [share_start, share_start + planned_tokens) of its curve and vests
+// floor(curve * end / total) - floor(curve * start / total) of them. The shares of all grants
+// split from one curve telescope, so together they vest exactly what the curve vests.
+pub fn share_of_curve(grant: &VestingUser, curve_amount: u64) -> u64 {
+    if grant.curve_tokens == 0 {
+        return curve_amount;
+    }
+    let total = grant.curve_tokens as u128;
+    let start = grant.share_start as u128;
+    let end = start + grant.planned_tokens as u128;
+    let curve_amount = curve_amount as u128;
+    (curve_amount * end / total - curve_amount * start / total) as u64
+}
+
+// Everything vested for the grant so far, claimed or not
+pub fn vested_amount(grant: &VestingUser, tge_timestamp: u64, now: u64) -> Result<u64, ErrorCode> {
+    Ok(share_of_curve(grant, curve_amount(grant, tge_timestamp, now)?))
+}
+
+pub fn claimable_amount(grant: &VestingUser, tge_timestamp: u64, now: u64) -> Result<u64, ErrorCode> {
+    vested_amount(grant, tge_timestamp, now)?
+        .checked_sub(grant.claimed_tokens)
+        .ok_or(ErrorCode::InvalidInput)
+}
+
+// Next time on the vesting clock at which the grant vests more than at `now`,
+// None once it is fully vested or while it is suspended
+pub fn next_unlock(grant: &VestingUser, tge_timestamp: u64, now: u64) -> Result<Option<u64>, ErrorCode> {
+    if grant.suspended != 0 || vested_amount(grant, tge_timestamp, now)? >= grant.planned_tokens {
+        return Ok(None);
+    }
+    if now <= tge_timestamp {
+        return Ok(Some(tge_timestamp + 1));
+    }
+    // smallest value of the curve at which the grant's share is more than it is now
+    let vested = vested_amount(grant, tge_timestamp, now)?;
+    let (mut low, mut high) = (curve_amount(grant, tge_timestamp, now)? + 1, curve_tokens(grant));
+    while low < high {
+        let middle = low + (high - low) / 2;
+        if share_of_curve(grant, middle) > vested {
+            high = middle;
+        } else {
+            low = middle + 1;
+        }
+    }
+    // the curve moves once a minute, but only when its linear part crosses a whole token
+    let curve_tokens = curve_tokens(grant) as u128;
+    let at_tge = curve_tokens * grant.unlocked_at_tge as u128 / 100;
+    let after_tge = curve_tokens - at_tge;
+    let minutes_in_period = 305 * grant.unlocking_period as u128 * 60 * 24 / 10;
+    let linear_target = (low as u128).saturating_sub(at_tge + grant.accelerated_tokens as u128);
+    let next_minute = (linear_target * minutes_in_period).div_ceil(after_tge);
+    Ok(Some(tge_timestamp + grant.suspended_seconds + next_minute as u64 * 60))
+}
+
+// What get_claimable reports for a grant at the wall clock `timestamp`, claimable is what
+// a claim would transfer right now
+pub fn claimable_view(grant: &VestingUser, vesting_data: &VestingData, timestamp: u64) -> Result<ClaimableView, ErrorCode> {
+    let now = vesting_clock(vesting_data, timestamp);
+    let vested = vested_amount(grant, vesting_data.tge_timestamp, now)?;
+    let blocked = vesting_data.paused
+        || grant.frozen != 0
+        || grant.status != utils::utils::EventStatus::PendingToken as u8
+        || is_expired(grant, vesting_data, timestamp);
+    let claimable = if blocked {
+        0
+    } else {
+        claimable_amount(grant, vesting_data.tge_timestamp, now)?
+    };
+    // outside a clock-stopping pause the vesting clock runs paused_seconds behind
+    let next_unlock_timestamp = if vesting_data.paused && vesting_data.pause_stops_clock {
+        None
+    } else {
+        next_unlock(grant, vesting_data.tge_timestamp, now)?.map(|next| next + timestamp - now)
+    };
+
+    Ok(ClaimableView {
+        vested,
+        claimed: grant.claimed_tokens,
+        claimable,
+        next_unlock_timestamp,
+    })
+}
+
+// What get_locked_balance reports, an expired grant has nothing locked or unclaimed anymore
+pub fn locked_balance(grant: &VestingUser, vesting_data: &VestingData, timestamp: u64) -> Result<LockedBalance, ErrorCode> {
+    let vested = vested_amount(grant, vesting_data.tge_timestamp, vesting_clock(vesting_data, timestamp))?;
+    let expired = grant.status == utils::utils::EventStatus::Expired as u8 || is_expired(grant, vesting_data, timestamp);
+    Ok(LockedBalance {
+        beneficiary: grant.user,
+        planned_tokens: grant.planned_tokens,
+        vested,
+        claimed: grant.claimed_tokens,
+        locked: if expired { 0 } else { grant.planned_tokens - vested },
+        unclaimed: if expired { 0 } else { vested.saturating_sub(grant.claimed_tokens) },
+    })
+}
+
+// Seconds on the vesting clock until the last token of the grant unlocks, keeps growing while suspended
+pub fn remaining_lockup(grant: &VestingUser, vesting_data: &VestingData, timestamp: u64) -> u64 {
+    let tge_timestamp = vesting_data.tge_timestamp;
+    let now = vesting_clock(vesting_data, timestamp);
+    // same period length calculate_entitled_amount uses
+    let period_seconds = 305 * grant.unlocking_period as u64 * 60 * 24 / 10 * 60;
+    let fully_vested_at = tge_timestamp + grant_suspended_seconds(grant, tge_timestamp, now) + period_seconds;
+    fully_vested_at.saturating_sub(now)
+}
+
+// Vests `bps` basis points of what is still unvested right away,
+// returns the amount that got accelerated
+pub fn accelerate_grant(
+    schedule: &mut VestingScheduleMut,
+    index: usize,
+    bps: u16,
+    tge_timestamp: u64,
+    now: u64,
+) -> Result<u64, ErrorCode> {
+    if bps == 0 || bps > utils::utils::BPS_DENOMINATOR as u16 {
+        return Err(ErrorCode::InvalidInput);
+    }
+    if index >= schedule.data.len()
+        || schedule.data[index].status != utils::utils::EventStatus::PendingToken as u8
+    {
+        return Err(ErrorCode::InvalidInput);
+    }
+    let grant = &mut schedule.data[index];
+    // in curve tokens, accelerated_tokens moves the grant's curve
+    let unvested = curve_tokens(grant).saturating_sub(curve_amount(grant, tge_timestamp, now)?);
+    let accelerated = (unvested as u128 * bps as u128 / utils::utils::BPS_DENOMINATOR as u128) as u64;
+    grant.accelerated_tokens = grant
+        .accelerated_tokens
+        .checked_add(accelerated)
+        .ok_or(ErrorCode::InvalidInput)?;
+    Ok(accelerated)
+}
+
+// Accelerates every active grant, returns how many grants and tokens were affected
+pub fn accelerate_schedule(
+    schedule: &mut VestingScheduleMut,
+    bps: u16,
+    tge_timestamp: u64,
+    now: u64,
+) -> Result<(u64, u64), ErrorCode> {
+    let mut grants = 0u64;
+    let mut total_accelerated = 0u64;
+    for index in 0..schedule.data.len() {
+        if schedule.data[index].status != utils::utils::EventStatus::PendingToken as u8 {
+            continue;
+        }
+        let accelerated = accelerate_grant(schedule, index, bps, tge_timestamp, now)?;
+        grants += 1;
+        total_accelerated = total_accelerated
+            .checked_add(accelerated)
+            .ok_or(ErrorCode::InvalidInput)?;
+    }
+    Ok((grants, total_accelerated))
+}
+
+// Merkle grants are not in the schedule and reclaim_expired cannot sweep them, so a schedule
+// with a merkle root takes no deadline: it would only strand their tokens in the vault
+pub fn set_schedule_claim_deadline(vesting_data: &mut VestingData, claim_deadline: u64) -> Result<(), ErrorCode> {
+    if claim_deadline != 0 && (claim_deadline <= vesting_data.tge_timestamp || vesting_data.merkle_root != [0; 32]) {
+        return Err(ErrorCode::InvalidInput);
+    }
+    vesting_data.claim_deadline = claim_deadline;
+    Ok(())
+}
+
+pub fn set_merkle_root(vesting_data: &mut VestingData, merkle_root: [u8; 32]) -> Result<(), ErrorCode> {
+    if merkle_root != [0; 32] && vesting_data.claim_deadline != 0 {
+        return Err(ErrorCode::InvalidInput);
+    }
+    vesting_data.merkle_root = merkle_root;
+    Ok(())
+}
+
+pub fn set_grant_claim_deadline(
+    schedule: &mut VestingScheduleMut,
+    index: usize,
+    claim_deadline: u64,
+    tge_timestamp: u64,
+) -> Result<(), ErrorCode> {
+    if index >= schedule.data.len()
+        || schedule.data[index].status != utils::utils::EventStatus::PendingToken as u8
+        || claim_deadline != 0 && claim_deadline <= tge_timestamp
+    {
+        return Err(ErrorCode::InvalidInput);
+    }
+    schedule.data[index].claim_deadline = claim_deadline;
+    Ok(())
+}
+
+pub fn claim_deadline(grant: &VestingUser, vesting_data: &VestingData) -> u64 {
+    if grant.claim_deadline != 0 {
+        grant.claim_deadline
+    } else {
+        vesting_data.claim_deadline
+    }
+}
+
+pub fn is_expired(grant: &VestingUser, vesting_data: &VestingData, now: u64) -> bool {
+    let deadline = claim_deadline(grant, vesting_data);
+    deadline != 0 && now > deadline
+}
+
+// Marks an expired grant so it can never be claimed again,
+// returns the unclaimed tokens that go back to the treasury
+pub fn expire_grant(
+    schedule: &mut VestingScheduleMut,
+    index: usize,
+    vesting_data: &mut VestingData,
+    now: u64,
+) -> Result<u64, ErrorCode> {
+    if index >= schedule.data.len()
+        || schedule.data[index].status != utils::utils::EventStatus::PendingToken as u8
+        || !is_expired(&schedule.data[index], vesting_data, now)
+    {
+        return Err(ErrorCode::InvalidInput);
+    }
+    // the vault does not hold staked tokens, they have to be unstaked before reclaiming
+    if schedule.data[index].staked_tokens > 0 {
+        return Err(ErrorCode::StakeOutstanding);
+    }
+    let grant = &mut schedule.data[index];
+    let unclaimed = grant.planned_tokens.saturating_sub(grant.claimed_tokens);
+    grant.status = utils::utils::EventStatus::Expired as u8;
+    schedule.len -= 1;
+    vesting_data.total_reclaimed = vesting_data
+        .total_reclaimed
+        .checked_add(unclaimed)
+        .ok_or(ErrorCode::InvalidInput)?;
+    Ok(unclaimed)
+}
+
+// Expires every grant past its deadline, returns how many grants and tokens were reclaimed
+pub fn expire_schedule(
+    schedule: &mut VestingScheduleMut,
+    vesting_data: &mut VestingData,
+    now: u64,
+) -> Result<(u64, u64), ErrorCode> {
+    let mut grants = 0u64;
+    let mut total_unclaimed = 0u64;
+    for index in 0..schedule.data.len() {
+        if schedule.data[index].status != utils::utils::EventStatus::PendingToken as u8
+            || !is_expired(&schedule.data[index], vesting_data, now)
+            || schedule.data[index].staked_tokens > 0
+        {
+            continue;
+        }
+        let unclaimed = expire_grant(schedule, index, vesting_data, now)?;
+        grants += 1;
+        total_unclaimed = total_unclaimed
+            .checked_add(unclaimed)
+            .ok_or(ErrorCode::InvalidInput)?;
+    }
+    Ok((grants, total_unclaimed))
+}
+
+pub fn set_grant_frozen(
+    schedule: &mut VestingScheduleMut,
+    index: usize,
+    frozen: bool,
+) -> Result<(), ErrorCode> {
+    if index >= schedule.data.len()
+        || schedule.data[index].status != utils::utils::EventStatus::PendingToken as u8
+    {
+        return Err(ErrorCode::InvalidInput);
+    }
+    schedule.data[index].frozen = frozen as u8;
+    Ok(())
 }
 
 pub fn add_user_active(
     add_user: VestingUser,
-    queue: &mut RefMut<VestingSchedule>,
+    queue: &mut VestingScheduleMut,
 ) -> Result<(), ErrorCode> {
     let user_index = get_next_index(queue)?;
     queue.len += 1;
-    queue.data[user_index as usize] = add_user;
+    queue.data[user_index] = add_user;
+    queue.set_slot_used(user_index, true);
+    Ok(())
+}
+
+pub fn get_next_index(queue: &mut VestingScheduleMut) -> Result<usize, ErrorCode> {
+    queue.next_free_slot().ok_or(ErrorCode::VestingUserDataFull)
+}
+
+// Slot of the beneficiary's grant, `index` picks one when they hold several.
+// Expired grants only count for the admin cleaning them up.
+pub fn find_grant(
+    grants: &[VestingUser],
+    beneficiary: &Pubkey,
+    index: Option<u64>,
+    include_expired: bool,
+) -> Result<usize, ErrorCode> {
+    let matches = |grant: &VestingUser| {
+        grant.user == *beneficiary
+            && (grant.status == utils::utils::EventStatus::PendingToken as u8
+                || (include_expired && grant.status == utils::utils::EventStatus::Expired as u8))
+    };
+    match index {
+        Some(index) => Some(index as usize).filter(|index| grants.get(*index).is_some_and(matches)),
+        None => grants.iter().position(matches),
+    }
+    .ok_or(ErrorCode::GrantNotFound)
+}
+
+pub fn remove_user_active(queue: &mut VestingScheduleMut, index: usize) -> Result<(), ErrorCode> {
+    // the vault does not hold staked tokens, they have to be unstaked before the grant goes
+    if queue.data[index].staked_tokens > 0 {
+        return Err(ErrorCode::StakeOutstanding);
+    }
+    // expired grants already left the count
+    if queue.data[index].status == utils::utils::EventStatus::PendingToken as u8 {
+        queue.len -= 1;
+    }
+    queue.data[index].status = utils::utils::EventStatus::Inactive as u8;
+    queue.set_slot_used(index, false);
     Ok(())
 }
 
-pub fn get_next_index(queue: &mut RefMut<VestingSchedule>) -> Result<usize, ErrorCode> {
-    // Gets next available index
-    // When we move to derived accounts, this will not longer be an issue
-    for (i, user) in (*queue).data.iter().enumerate() {
-        if user.status == (utils::utils::EventStatus::Inactive as u8) {
-            return Ok(i);
+pub fn len(queue: &mut VestingScheduleMut) -> usize {
+    queue.len as usize
+}
+
+// The new terms apply from TGE on, the grant vests as if it always had them: a top-up
+// halfway through the period is half vested at once, a longer period vests less so far.
+// So no amendment may take the vested amount below what was already claimed.
+#[allow(clippy::too_many_arguments)]
+pub fn amend_grant(
+    schedule: &mut VestingScheduleMut,
+    index: usize,
+    unlocked_at_tge: u8,
+    unlocking_period: u8,
+    planned_tokens: u64,
+    beneficiary_signed: bool,
+    tge_timestamp: u64,
+    now: u64,
+) -> Result<VestingUser, ErrorCode> {
+    if index >= schedule.data.len()
+        || schedule.data[index].status != utils::utils::EventStatus::PendingToken as u8
+    {
+        return Err(ErrorCode::InvalidInput);
+    }
+    let previous = schedule.data[index];
+    // a split grant's terms are a share of another curve, they cannot change on their own
+    if previous.curve_tokens != 0 {
+        return Err(ErrorCode::InvalidInput);
+    }
+
+    // Topping up an unchanged curve only ever adds to the entitlement,
+    // anything else needs the beneficiary to agree to the new terms
+    let is_top_up = planned_tokens >= previous.planned_tokens
+        && unlocked_at_tge == previous.unlocked_at_tge
+        && unlocking_period == previous.unlocking_period;
+    if !is_top_up && !beneficiary_signed {
+        return Err(ErrorCode::BeneficiaryConsentRequired);
+    }
+
+    let mut amended = previous;
+    amended.planned_tokens = planned_tokens;
+    amended.unlocked_at_tge = unlocked_at_tge;
+    amended.unlocking_period = unlocking_period;
+    if planned_tokens < previous.claimed_tokens + previous.staked_tokens
+        || vested_amount(&amended, tge_timestamp, now)? < previous.claimed_tokens
+    {
+        return Err(ErrorCode::AmendmentBelowClaimed);
+    }
+    amended.amendment_count = previous
+        .amendment_count
+        .checked_add(1)
+        .ok_or(ErrorCode::InvalidInput)?;
+    schedule.data[index] = amended;
+
+    Ok(previous)
+}
+
+// Binds a claim status to the grant proven against the schedule's merkle root,
+// an already bound status has to belong to the same beneficiary
+#[allow(clippy::too_many_arguments)]
+pub fn open_merkle_claim_status(
+    claim_status: &mut MerkleClaimStatus,
+    vesting_data: &VestingData,
+    vesting_data_key: Pubkey,
+    beneficiary: Pubkey,
+    planned_tokens: u64,
+    unlocked_at_tge: u8,
+    unlocking_period: u8,
+    proof: &[[u8; 32]],
+) -> Result<(), ErrorCode> {
+    if claim_status.beneficiary != Pubkey::default() {
+        if claim_status.vesting_data != vesting_data_key || claim_status.beneficiary != beneficiary {
+            return Err(ErrorCode::InvalidInput);
         }
+        return Ok(());
+    }
+    if vesting_data.merkle_root == [0; 32] {
+        return Err(ErrorCode::InvalidMerkleProof);
+    }
+    let leaf = utils::merkle::leaf_hash(&beneficiary, planned_tokens, unlocked_at_tge, unlocking_period);
+    if !utils::merkle::verify_proof(&vesting_data.merkle_root, leaf, proof) {
+        return Err(ErrorCode::InvalidMerkleProof);
+    }
+    claim_status.version = utils::layout::LAYOUT_VERSION;
+    claim_status.vesting_data = vesting_data_key;
+    claim_status.beneficiary = beneficiary;
+    claim_status.planned_tokens = planned_tokens;
+    claim_status.claimed_tokens = 0;
+    claim_status.unlocked_at_tge = unlocked_at_tge;
+    claim_status.unlocking_period = unlocking_period;
+    Ok(())
+}
+
+// Books what the grant can claim right now against the grant and the schedule totals,
+// moving the tokens is left to the caller
+pub fn claim_grant(grant: &mut VestingUser, vesting_data: &mut VestingData, timestamp: u64) -> Result<u64, ErrorCode> {
+    if vesting_data.paused {
+        return Err(ErrorCode::SchedulePaused);
+    }
+    if grant.frozen != 0 {
+        return Err(ErrorCode::GrantFrozen);
+    }
+    if is_expired(grant, vesting_data, timestamp) {
+        return Err(ErrorCode::ClaimDeadlinePassed);
+    }
+    let now = vesting_clock(vesting_data, timestamp);
+    let can_claim_now = claimable_amount(grant, vesting_data.tge_timestamp, now)?;
+
+    vesting_data.total_issued_so_far = vesting_data
+        .total_issued_so_far
+        .checked_add(can_claim_now)
+        .ok_or(ErrorCode::InvalidInput)?;
+    grant.claimed_tokens = grant
+        .claimed_tokens
+        .checked_add(can_claim_now)
+        .ok_or(ErrorCode::InvalidInput)?;
+    Ok(can_claim_now)
+}
+
+// What the vault still holds for the grant, the rest of what it is owed is staked
+pub fn vault_share(grant: &VestingUser) -> u64 {
+    grant
+        .planned_tokens
+        .saturating_sub(grant.claimed_tokens)
+        .saturating_sub(grant.staked_tokens)
+}
+
+// claim_grant for claims paid out of the vault only, fails instead of paying out tokens
+// the vault holds for other grants while this one's are staked
+pub fn claim_grant_from_vault(grant: &mut VestingUser, vesting_data: &mut VestingData, timestamp: u64) -> Result<u64, ErrorCode> {
+    let vault_share = vault_share(grant);
+    let amount = claim_grant(grant, vesting_data, timestamp)?;
+    if amount > vault_share {
+        return Err(ErrorCode::ClaimNeedsUnstake);
+    }
+    Ok(amount)
+}
+
+// claim_grant for claim_from_stake, returns what comes out of the vault and what has to be
+// withdrawn from the staking program. The vault goes first so the stake keeps earning.
+pub fn claim_grant_from_stake(
+    grant: &mut VestingUser,
+    vesting_data: &mut VestingData,
+    timestamp: u64,
+) -> Result<(u64, u64), ErrorCode> {
+    let vault_share = vault_share(grant);
+    let amount = claim_grant(grant, vesting_data, timestamp)?;
+    let from_vault = amount.min(vault_share);
+    let from_stake = amount - from_vault;
+    if from_stake > 0 {
+        unstake_grant(grant, vesting_data, from_stake)?;
+    }
+    Ok((from_vault, from_stake))
+}
+
+// Books `amount` of the grant's locked tokens as staked, vesting carries on as before
+pub fn stake_grant(grant: &mut VestingUser, vesting_data: &mut VestingData, amount: u64, timestamp: u64) -> Result<(), ErrorCode> {
+    if amount == 0 {
+        return Err(ErrorCode::InvalidInput);
+    }
+    if is_expired(grant, vesting_data, timestamp) {
+        return Err(ErrorCode::ClaimDeadlinePassed);
+    }
+    let vested = vested_amount(grant, vesting_data.tge_timestamp, vesting_clock(vesting_data, timestamp))?;
+    let staked_tokens = grant
+        .staked_tokens
+        .checked_add(amount)
+        .ok_or(ErrorCode::InvalidInput)?;
+    if staked_tokens > grant.planned_tokens.saturating_sub(vested) {
+        return Err(ErrorCode::StakeExceedsLocked);
+    }
+    grant.staked_tokens = staked_tokens;
+    vesting_data.total_staked = vesting_data
+        .total_staked
+        .checked_add(amount)
+        .ok_or(ErrorCode::InvalidInput)?;
+    Ok(())
+}
+
+pub fn unstake_grant(grant: &mut VestingUser, vesting_data: &mut VestingData, amount: u64) -> Result<(), ErrorCode> {
+    if amount == 0 || amount > grant.staked_tokens {
+        return Err(ErrorCode::InvalidInput);
     }
-    Err(ErrorCode::VestingUserDataFull)
+    grant.staked_tokens -= amount;
+    vesting_data.total_staked = vesting_data
+        .total_staked
+        .checked_sub(amount)
+        .ok_or(ErrorCode::InvalidInput)?;
+    Ok(())
 }
 
-pub fn remove_user_active(queue: &mut RefMut<VestingSchedule>, index: usize) {
-    let mut remove_user: VestingUser = (*queue).data[index];
+// Moves `split_tokens` of the grant at `index` into a new grant for `new_beneficiary` on the same curve,
+// claimed tokens go along proportionally. Returns the index of the new grant.
+pub fn split_grant(
+    schedule: &mut VestingScheduleMut,
+    index: usize,
+    split_tokens: u64,
+    new_beneficiary: Pubkey,
+    vesting_data: &VestingData,
+    timestamp: u64,
+) -> Result<usize, ErrorCode> {
+    if index >= schedule.data.len()
+        || schedule.data[index].status != utils::utils::EventStatus::PendingToken as u8
+        || new_beneficiary == Pubkey::default()
+    {
+        return Err(ErrorCode::InvalidInput);
+    }
+    let original = schedule.data[index];
+    if split_tokens == 0 || split_tokens >= original.planned_tokens {
+        return Err(ErrorCode::InvalidInput);
+    }
+    if is_expired(&original, vesting_data, timestamp) {
+        return Err(ErrorCode::ClaimDeadlinePassed);
+    }
+    if original.staked_tokens > 0 {
+        return Err(ErrorCode::StakeOutstanding);
+    }
+    if new_beneficiary != original.user && !vesting_data.transferable {
+        return Err(ErrorCode::TransfersDisabled);
+    }
+    let new_index = get_next_index(schedule)?;
+
+    let mut kept = original;
+    kept.curve_tokens = curve_tokens(&original);
+    kept.planned_tokens = original.planned_tokens - split_tokens;
+    let mut split = kept;
+    split.user = new_beneficiary;
+    split.planned_tokens = split_tokens;
+    split.share_start = original.share_start + kept.planned_tokens;
+    split.amendment_count = 0;
+    split.receipt_mint = Pubkey::default();
+
+    // proportional, as long as neither grant ends up having claimed more than it vested
+    let curve = curve_amount(&original, vesting_data.tge_timestamp, vesting_clock(vesting_data, timestamp))?;
+    let claimed = original.claimed_tokens;
+    let proportional = (claimed as u128 * split_tokens as u128 / original.planned_tokens as u128) as u64;
+    let lowest = claimed.saturating_sub(share_of_curve(&kept, curve));
+    let highest = share_of_curve(&split, curve).min(claimed);
+    if lowest > highest {
+        return Err(ErrorCode::InvalidInput);
+    }
+    split.claimed_tokens = proportional.max(lowest).min(highest);
+    kept.claimed_tokens = claimed - split.claimed_tokens;
+
+    schedule.data[index] = kept;
+    schedule.data[new_index] = split;
+    schedule.len += 1;
+    schedule.set_slot_used(new_index, true);
+    Ok(new_index)
+}
 
-    remove_user.status = utils::utils::EventStatus::Inactive as u8;
-    queue.len -= 1;
-    queue.data[index] = remove_user;
+// Binds a freshly minted receipt to the grant, its holder is the beneficiary from then on
+pub fn attach_receipt(grant: &mut VestingUser, vesting_data: &VestingData, receipt_mint: Pubkey) -> Result<(), ErrorCode> {
+    if !vesting_data.transferable {
+        return Err(ErrorCode::TransfersDisabled);
+    }
+    if grant.receipt_mint != Pubkey::default() {
+        return Err(ErrorCode::InvalidInput);
+    }
+    grant.receipt_mint = receipt_mint;
+    Ok(())
+}
+
+// Points the grant at the owner of a token account holding its receipt, returns the previous beneficiary.
+// The receipt mint has no freeze authority, so this follows the receipt whether or not transfers are
+// on, a grant stuck with a holder that moved its receipt could be claimed by nobody.
+pub fn sync_beneficiary(grant: &mut VestingUser, receipt_mint: Pubkey, holder: Pubkey, amount: u64) -> Result<Pubkey, ErrorCode> {
+    if grant.receipt_mint == Pubkey::default() || receipt_mint != grant.receipt_mint || amount != 1 {
+        return Err(ErrorCode::InvalidInput);
+    }
+    let previous = grant.user;
+    grant.user = holder;
+    Ok(previous)
+}
+
+// What a token account passed as a grant's receipt holds
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReceiptHolding {
+    pub mint: Pubkey,
+    pub holder: Pubkey,
+    pub amount: u64,
+}
+
+// A grant with a receipt answers to whoever holds it: `beneficiary` has to hold the receipt and
+// becomes the grant's beneficiary if a transfer was not synced yet. Returns the previous
+// beneficiary if it changed, grants without a receipt are left to the caller's checks.
+pub fn sync_receipt_holder(
+    grant: &mut VestingUser,
+    beneficiary: Pubkey,
+    receipt: Option<ReceiptHolding>,
+) -> Result<Option<Pubkey>, ErrorCode> {
+    if grant.receipt_mint == Pubkey::default() {
+        return Ok(None);
+    }
+    let receipt = receipt
+        .filter(|receipt| receipt.mint == grant.receipt_mint && receipt.holder == beneficiary && receipt.amount == 1)
+        .ok_or(ErrorCode::ReceiptNotHeld)?;
+    if grant.user == beneficiary {
+        return Ok(None);
+    }
+    sync_beneficiary(grant, receipt.mint, receipt.holder, receipt.amount).map(Some)
 }
 
-pub fn len(queue: &mut RefMut<VestingSchedule>) -> usize {
-    (*queue)
+// The active grant at `index` the signing beneficiary acts on, synced to its receipt holder first
+pub fn beneficiary_grant<'a>(
+    schedule: &'a mut VestingScheduleMut,
+    index: u64,
+    beneficiary: Pubkey,
+    receipt: Option<ReceiptHolding>,
+) -> Result<&'a mut VestingUser, ErrorCode> {
+    let grant = schedule
         .data
-        .iter()
-        .filter(|event| event.status == utils::utils::EventStatus::PendingToken as u8)
-        .count()
+        .get_mut(index as usize)
+        .filter(|grant| grant.status == utils::utils::EventStatus::PendingToken as u8)
+        .ok_or(ErrorCode::InvalidInput)?;
+    if let Some(old_beneficiary) = sync_receipt_holder(grant, beneficiary, receipt)? {
+        emit!(BeneficiaryChanged {
+            index,
+            old_beneficiary,
+            new_beneficiary: beneficiary,
+            by_receipt: true,
+        });
+    }
+    if grant.user != beneficiary {
+        return Err(ErrorCode::InvalidInput);
+    }
+    Ok(grant)
+}
+
+// Same rules as a receipt transfer, and a grant with a receipt only moves with it
+pub fn change_beneficiary(grant: &mut VestingUser, vesting_data: &VestingData, new_beneficiary: Pubkey) -> Result<Pubkey, ErrorCode> {
+    if !vesting_data.transferable {
+        return Err(ErrorCode::TransfersDisabled);
+    }
+    if grant.receipt_mint != Pubkey::default() {
+        return Err(ErrorCode::GrantHasReceipt);
+    }
+    if new_beneficiary == Pubkey::default() {
+        return Err(ErrorCode::InvalidInput);
+    }
+    let previous = grant.user;
+    grant.user = new_beneficiary;
+    Ok(previous)
 }
 
+pub fn set_staking_program(vesting_data: &mut VestingData, staking_program: Pubkey) -> Result<(), ErrorCode> {
+    // the stakes have to come back before they are looked after by another program
+    if vesting_data.total_staked > 0 {
+        return Err(ErrorCode::StakeOutstanding);
+    }
+    vesting_data.staking_program = staking_program;
+    Ok(())
+}
 
-pub fn claim<'info>(
-    schedule: &mut RefMut<VestingSchedule>, 
-    index: u64, 
+pub fn claim(
+    schedule: &mut VestingScheduleMut,
+    index: Option<u64>,
     claim_user: Pubkey,
-    owner: &AccountInfo<'info>,
-    to_vault: &AccountInfo<'info>,
-    from_vault: &AccountInfo<'info>,
-    from_vault_authority: &AccountInfo<'info>,
+    receipt: Option<ReceiptHolding>,
     vesting_data: &mut VestingData,
-    token_program: &AccountInfo<'info>
+    clock: &impl TimeSource,
+    ledger: &mut impl TokenLedger,
 ) -> ProgramResult {
-    if schedule.data[index as usize].user == claim_user
-        && schedule.data[index as usize].status == utils::utils::EventStatus::PendingToken as u8
-    {
-        let user_id = index as usize;
-        let now = Clock::get().unwrap().unix_timestamp;
-        let can_claim_now = tokenoperation::schedule::calculate_entitled_amount(
-            schedule.data[user_id].planned_tokens,
-            schedule.data[user_id].unlocked_at_tge as u64,
-            schedule.data[user_id].unlocking_period as u64,
-            schedule.data[user_id].claimed_tokens,
-            vesting_data.tge_timestamp,
-            now as u64,
-        )?;
+    // the new holder of a receipt is not the grant's beneficiary until it is synced
+    let receipt_grant = receipt.filter(|receipt| receipt.holder == claim_user).and_then(|receipt| {
+        schedule.data.iter().enumerate().position(|(at, grant)| {
+            grant.status == utils::utils::EventStatus::PendingToken as u8
+                && grant.receipt_mint != Pubkey::default()
+                && grant.receipt_mint == receipt.mint
+                && index.is_none_or(|index| index as usize == at)
+        })
+    });
+    let user_id = match receipt_grant {
+        Some(user_id) => user_id,
+        None => find_grant(schedule.data, &claim_user, index, false)?,
+    };
+    if let Some(old_beneficiary) = sync_receipt_holder(&mut schedule.data[user_id], claim_user, receipt)? {
+        emit!(BeneficiaryChanged {
+            index: user_id as u64,
+            old_beneficiary,
+            new_beneficiary: claim_user,
+            by_receipt: true,
+        });
+    }
+    let timestamp = clock.unix_timestamp()?;
+    let can_claim_now = claim_grant_from_vault(&mut schedule.data[user_id], vesting_data, timestamp)?;
 
-        msg!("Can Claim Now {}", can_claim_now);
-
-        if can_claim_now > 0 {
-            tokenoperation::tokenoper::vesting_transfer(
-                can_claim_now,
-                owner,
-                to_vault,
-                from_vault,
-                from_vault_authority,
-                vesting_data.vesting_vault_authority_seed,
-                token_program,
-            );
-            vesting_data.total_issued_so_far = vesting_data
-                .total_issued_so_far
-                .checked_add(can_claim_now)
-                .unwrap();
-        }
+    msg!("Can Claim Now {}", can_claim_now);
 
-        schedule.data[user_id].claimed_tokens = schedule.data[user_id]
-            .claimed_tokens
-            .checked_add(can_claim_now)
-            .unwrap();
+    if can_claim_now > 0 {
+        ledger.transfer_from_vault(can_claim_now)?;
     }
 
     Ok(())
@@ -107,14 +860,15 @@ pub fn claim<'info>(
 
 #[cfg(test)]
 mod tests {
-    use std::cell::RefCell;
-    use crate::{VestingSchedule, VestingUser, ErrorCode, utils};
+    use crate::{VestingScheduleBuf, VestingUser, VestingData, MerkleClaimStatus, ErrorCode, utils};
+    use crate::utils::runtime::{TimeSource, TokenLedger};
     use anchor_lang::prelude::*;
+    use quickcheck_macros::quickcheck;
     use std::str::FromStr;
 
     #[test]
     pub fn test_add_user() -> Result<(), ErrorCode> {
-        let vesting_schedule = RefCell::new(VestingSchedule::default());
+        let mut vesting_schedule = VestingScheduleBuf::new(utils::utils::INITIAL_CAPACITY);
         let add_user1 = VestingUser::new(
             15,
             Pubkey::from_str("29GPMU5gtBDbd368EwquqTmo33tKgvneAK9REmmxkqm8").unwrap(),
@@ -137,10 +891,901 @@ mod tests {
         assert_eq!(utils::utils::EventStatus::PendingToken as u8, vesting_schedule.borrow_mut().data[0].status);
         assert_eq!(utils::utils::EventStatus::PendingToken as u8, vesting_schedule.borrow_mut().data[1].status);
         assert_eq!(2, super::len(&mut vesting_schedule.borrow_mut()));
-        super::remove_user_active(&mut vesting_schedule.borrow_mut(),1);
+        super::remove_user_active(&mut vesting_schedule.borrow_mut(),1)?;
         assert_eq!(1, super::len(&mut vesting_schedule.borrow_mut()));
         assert_eq!(utils::utils::EventStatus::PendingToken as u8, vesting_schedule.borrow_mut().data[0].status);
         assert_eq!(utils::utils::EventStatus::Inactive as u8, vesting_schedule.borrow_mut().data[1].status);
         Ok(())
     }
-}
\ No newline at end of file
+
+    #[test]
+    pub fn test_find_grant() -> Result<(), ErrorCode> {
+        let mut vesting_schedule = VestingScheduleBuf::new(utils::utils::INITIAL_CAPACITY);
+        let beneficiary = Pubkey::new_unique();
+        let other = VestingUser::new(20, Pubkey::new_unique(), 12, 1_000_000);
+        for user in [other, VestingUser::new(20, beneficiary, 12, 1_000_000), other, VestingUser::new(20, beneficiary, 6, 500)] {
+            super::add_user_active(user, &mut vesting_schedule.borrow_mut())?;
+        }
+        let grants = vesting_schedule.borrow().data;
+
+        assert_eq!(Ok(1), super::find_grant(grants, &beneficiary, None, false));
+        assert_eq!(Ok(3), super::find_grant(grants, &beneficiary, Some(3), false));
+        assert_eq!(Err(ErrorCode::GrantNotFound), super::find_grant(grants, &beneficiary, Some(2), false));
+        assert_eq!(Err(ErrorCode::GrantNotFound), super::find_grant(grants, &beneficiary, Some(300), false));
+        assert_eq!(Err(ErrorCode::GrantNotFound), super::find_grant(grants, &Pubkey::new_unique(), None, false));
+
+        // expired grants are left to the admin, removed ones are gone for everyone
+        vesting_schedule.borrow_mut().data[1].status = utils::utils::EventStatus::Expired as u8;
+        let grants = vesting_schedule.borrow().data;
+        assert_eq!(Ok(3), super::find_grant(grants, &beneficiary, None, false));
+        assert_eq!(Ok(1), super::find_grant(grants, &beneficiary, None, true));
+        super::remove_user_active(&mut vesting_schedule.borrow_mut(), 1)?;
+        assert_eq!(Err(ErrorCode::GrantNotFound), super::find_grant(vesting_schedule.borrow().data, &beneficiary, Some(1), true));
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_free_slots() -> Result<(), ErrorCode> {
+        let mut vesting_schedule = VestingScheduleBuf::new(utils::utils::INITIAL_CAPACITY);
+        for _ in 0..utils::utils::INITIAL_CAPACITY {
+            let add_user = VestingUser::new(20, Pubkey::new_unique(), 12, 1_000_000);
+            super::add_user_active(add_user, &mut vesting_schedule.borrow_mut())?;
+        }
+        assert!((0..300).all(|index| vesting_schedule.borrow().is_slot_used(index)));
+        let add_user = VestingUser::new(20, Pubkey::new_unique(), 12, 1_000_000);
+        assert_eq!(
+            Err(ErrorCode::VestingUserDataFull),
+            super::add_user_active(add_user, &mut vesting_schedule.borrow_mut())
+        );
+
+        // removing frees the slot, removing an expired grant does not touch the active count
+        super::remove_user_active(&mut vesting_schedule.borrow_mut(), 200)?;
+        super::remove_user_active(&mut vesting_schedule.borrow_mut(), 70)?;
+        vesting_schedule.borrow_mut().data[71].status = utils::utils::EventStatus::Expired as u8;
+        vesting_schedule.borrow_mut().len -= 1;
+        super::remove_user_active(&mut vesting_schedule.borrow_mut(), 71)?;
+        assert_eq!(297, super::len(&mut vesting_schedule.borrow_mut()));
+        assert_eq!(Ok(70), super::get_next_index(&mut vesting_schedule.borrow_mut()));
+
+        super::add_user_active(add_user, &mut vesting_schedule.borrow_mut())?;
+        super::add_user_active(add_user, &mut vesting_schedule.borrow_mut())?;
+        assert_eq!(add_user, vesting_schedule.borrow().data[71]);
+        assert_eq!(Ok(200), super::get_next_index(&mut vesting_schedule.borrow_mut()));
+        assert_eq!(299, super::len(&mut vesting_schedule.borrow_mut()));
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_amend_grant() -> Result<(), ErrorCode> {
+        let mut vesting_schedule = VestingScheduleBuf::new(utils::utils::INITIAL_CAPACITY);
+        let mut add_user = VestingUser::new(
+            20,
+            Pubkey::from_str("29GPMU5gtBDbd368EwquqTmo33tKgvneAK9REmmxkqm8").unwrap(),
+            12,
+            1_000_000,
+        );
+        add_user.claimed_tokens = 300_000;
+        super::add_user_active(add_user, &mut vesting_schedule.borrow_mut())?;
+
+        // 6 months in, 600_000 is vested
+        let now = 6 * 732 * 60 * 60;
+
+        // top-up goes through without the beneficiary
+        let previous = super::amend_grant(&mut vesting_schedule.borrow_mut(), 0, 20, 12, 2_000_000, false, 0, now)?;
+        let amended = vesting_schedule.borrow().data[0];
+        assert_eq!(1_000_000, { previous.planned_tokens });
+        assert_eq!(2_000_000, { amended.planned_tokens });
+        assert_eq!(1, { amended.amendment_count });
+        // the top-up is vested as far as the curve is, 1_200_000 of it right away
+        assert_eq!(Ok(900_000), super::claimable_amount(&amended, 0, now));
+
+        // decrease and curve changes need consent
+        let err = super::amend_grant(&mut vesting_schedule.borrow_mut(), 0, 20, 12, 1_000_000, false, 0, now);
+        assert_eq!(err.err(), Some(ErrorCode::BeneficiaryConsentRequired));
+        let err = super::amend_grant(&mut vesting_schedule.borrow_mut(), 0, 10, 18, 2_000_000, false, 0, now);
+        assert_eq!(err.err(), Some(ErrorCode::BeneficiaryConsentRequired));
+
+        // entitlement can never drop below what was already claimed
+        let err = super::amend_grant(&mut vesting_schedule.borrow_mut(), 0, 20, 12, 200_000, true, 0, now);
+        assert_eq!(err.err(), Some(ErrorCode::AmendmentBelowClaimed));
+        let err = super::amend_grant(&mut vesting_schedule.borrow_mut(), 0, 0, 18, 500_000, true, 0, now);
+        assert_eq!(err.err(), Some(ErrorCode::AmendmentBelowClaimed));
+
+        super::amend_grant(&mut vesting_schedule.borrow_mut(), 0, 50, 18, 500_000, true, 0, now)?;
+        let amended = vesting_schedule.borrow().data[0];
+        assert_eq!(500_000, { amended.planned_tokens });
+        assert_eq!(50, amended.unlocked_at_tge);
+        assert_eq!(18, amended.unlocking_period);
+        assert_eq!(300_000, { amended.claimed_tokens });
+        assert_eq!(2, { amended.amendment_count });
+        // the longer period applies to the months already gone
+        assert_eq!(Ok(33_333), super::claimable_amount(&amended, 0, now));
+
+        super::remove_user_active(&mut vesting_schedule.borrow_mut(), 0)?;
+        let err = super::amend_grant(&mut vesting_schedule.borrow_mut(), 0, 50, 18, 500_000, true, 0, now);
+        assert_eq!(err.err(), Some(ErrorCode::InvalidInput));
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_pause_schedule() -> Result<(), ErrorCode> {
+        let mut vesting_data = VestingData { tge_timestamp: 1_000, ..Default::default() };
+
+        // pause that keeps the clock running
+        super::pause_schedule(&mut vesting_data, false, 2_000)?;
+        assert_eq!(Some(ErrorCode::InvalidInput), super::pause_schedule(&mut vesting_data, false, 2_500).err());
+        assert_eq!(3_000, super::vesting_clock(&vesting_data, 3_000));
+        super::unpause_schedule(&mut vesting_data, 3_000)?;
+        assert_eq!(0, vesting_data.paused_seconds);
+        assert_eq!(Some(ErrorCode::InvalidInput), super::unpause_schedule(&mut vesting_data, 3_500).err());
+
+        // pause that stops the clock
+        super::pause_schedule(&mut vesting_data, true, 4_000)?;
+        assert_eq!(4_000, super::vesting_clock(&vesting_data, 6_000));
+        super::unpause_schedule(&mut vesting_data, 6_000)?;
+        assert_eq!(2_000, vesting_data.paused_seconds);
+        assert_eq!(5_000, super::vesting_clock(&vesting_data, 7_000));
+
+        // pauses before TGE do not shift the clock
+        let mut vesting_data = VestingData { tge_timestamp: 1_000, ..Default::default() };
+        super::pause_schedule(&mut vesting_data, true, 500)?;
+        assert_eq!(1_000, super::vesting_clock(&vesting_data, 1_500));
+        super::unpause_schedule(&mut vesting_data, 1_500)?;
+        assert_eq!(500, vesting_data.paused_seconds);
+        assert_eq!(1_500, super::vesting_clock(&vesting_data, 2_000));
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_freeze_grant() -> Result<(), ErrorCode> {
+        let mut vesting_schedule = VestingScheduleBuf::new(utils::utils::INITIAL_CAPACITY);
+        let add_user = VestingUser::new(
+            20,
+            Pubkey::from_str("29GPMU5gtBDbd368EwquqTmo33tKgvneAK9REmmxkqm8").unwrap(),
+            12,
+            1_000_000,
+        );
+        super::add_user_active(add_user, &mut vesting_schedule.borrow_mut())?;
+
+        super::set_grant_frozen(&mut vesting_schedule.borrow_mut(), 0, true)?;
+        assert_eq!(1, vesting_schedule.borrow().data[0].frozen);
+        super::set_grant_frozen(&mut vesting_schedule.borrow_mut(), 0, false)?;
+        assert_eq!(0, vesting_schedule.borrow().data[0].frozen);
+
+        let err = super::set_grant_frozen(&mut vesting_schedule.borrow_mut(), 1, true);
+        assert_eq!(err.err(), Some(ErrorCode::InvalidInput));
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_suspend_vesting() -> Result<(), ErrorCode> {
+        let mut vesting_schedule = VestingScheduleBuf::new(utils::utils::INITIAL_CAPACITY);
+        let add_user = VestingUser::new(
+            20,
+            Pubkey::from_str("29GPMU5gtBDbd368EwquqTmo33tKgvneAK9REmmxkqm8").unwrap(),
+            12,
+            1_000_000,
+        );
+        super::add_user_active(add_user, &mut vesting_schedule.borrow_mut())?;
+        let month = 732 * 60 * 60;
+
+        // claims at `now` the same way vesting_operations::claim does
+        let claim = |vesting_schedule: &mut VestingScheduleBuf, now: u64| -> u64 {
+            let grant = vesting_schedule.borrow().data[0];
+            let amount = super::claimable_amount(&grant, 0, now).unwrap();
+            vesting_schedule.borrow_mut().data[0].claimed_tokens += amount;
+            amount
+        };
+
+        // 1 month vesting, 2 months suspended, 1 month vesting, 1 month suspended
+        assert_eq!(266_666, claim(&mut vesting_schedule, month));
+        super::suspend_vesting(&mut vesting_schedule.borrow_mut(), 0, month)?;
+        assert_eq!(Some(ErrorCode::InvalidInput), super::suspend_vesting(&mut vesting_schedule.borrow_mut(), 0, month).err());
+        assert_eq!(0, claim(&mut vesting_schedule, 2 * month));
+        super::resume_vesting(&mut vesting_schedule.borrow_mut(), 0, 0, 3 * month)?;
+        assert_eq!(Some(ErrorCode::InvalidInput), super::resume_vesting(&mut vesting_schedule.borrow_mut(), 0, 0, 3 * month).err());
+        assert_eq!(2 * month, { vesting_schedule.borrow().data[0].suspended_seconds });
+        assert_eq!(0, claim(&mut vesting_schedule, 3 * month));
+        super::suspend_vesting(&mut vesting_schedule.borrow_mut(), 0, 4 * month)?;
+        assert_eq!(66_667, claim(&mut vesting_schedule, 5 * month));
+        super::resume_vesting(&mut vesting_schedule.borrow_mut(), 0, 0, 5 * month)?;
+        assert_eq!(3 * month, { vesting_schedule.borrow().data[0].suspended_seconds });
+
+        // the whole period still vests, just three months later
+        assert_eq!(600_000, claim(&mut vesting_schedule, 14 * month));
+        assert_eq!(66_667, claim(&mut vesting_schedule, 15 * month));
+        assert_eq!(1_000_000, { vesting_schedule.borrow().data[0].claimed_tokens });
+        Ok(())
+    }
+
+    struct FixedClock(u64);
+
+    impl TimeSource for FixedClock {
+        fn unix_timestamp(&self) -> std::result::Result<u64, ProgramError> {
+            Ok(self.0)
+        }
+    }
+
+    // Balance of the vesting vault and every payout out of it
+    struct Ledger {
+        vault: u64,
+        paid_out: Vec<u64>,
+    }
+
+    impl TokenLedger for Ledger {
+        fn transfer_from_vault(&mut self, amount: u64) -> ProgramResult {
+            self.vault = self.vault.checked_sub(amount).ok_or(ProgramError::InsufficientFunds)?;
+            self.paid_out.push(amount);
+            Ok(())
+        }
+    }
+
+    #[test]
+    pub fn test_claim() {
+        let month = 732 * 60 * 60;
+        let beneficiary = Pubkey::new_unique();
+        let mut vesting_schedule = VestingScheduleBuf::new(utils::utils::INITIAL_CAPACITY);
+        let other = VestingUser::new(20, Pubkey::new_unique(), 12, 1_000_000);
+        super::add_user_active(other, &mut vesting_schedule.borrow_mut()).unwrap();
+        super::add_user_active(VestingUser::new(20, beneficiary, 12, 1_000_000), &mut vesting_schedule.borrow_mut()).unwrap();
+        let mut vesting_data = VestingData { tge_timestamp: month, ..Default::default() };
+        let mut ledger = Ledger { vault: 2_000_000, paid_out: Vec::new() };
+        let claim = |vesting_schedule: &mut VestingScheduleBuf,
+                         vesting_data: &mut VestingData,
+                         ledger: &mut Ledger,
+                         claim_user: Pubkey,
+                         index: Option<u64>,
+                         now: u64| {
+            super::claim(&mut vesting_schedule.borrow_mut(), index, claim_user, None, vesting_data, &FixedClock(now), ledger)
+        };
+
+        // nothing until TGE, then the TGE share and nothing more at the same time
+        assert_eq!(Ok(()), claim(&mut vesting_schedule, &mut vesting_data, &mut ledger, beneficiary, None, month));
+        assert_eq!(Ok(()), claim(&mut vesting_schedule, &mut vesting_data, &mut ledger, beneficiary, None, month + 1));
+        assert_eq!(Ok(()), claim(&mut vesting_schedule, &mut vesting_data, &mut ledger, beneficiary, Some(1), month + 1));
+        assert_eq!(vec![200_000], ledger.paid_out);
+        assert_eq!(200_000, { vesting_schedule.borrow().data[1].claimed_tokens });
+        assert_eq!((200_000, 0), (vesting_data.total_issued_so_far, { vesting_schedule.borrow().data[0].claimed_tokens }));
+
+        // someone else's slot or no grant at all
+        let not_found = Err(ErrorCode::GrantNotFound.into());
+        assert_eq!(not_found, claim(&mut vesting_schedule, &mut vesting_data, &mut ledger, beneficiary, Some(0), 2 * month));
+        assert_eq!(not_found, claim(&mut vesting_schedule, &mut vesting_data, &mut ledger, Pubkey::new_unique(), None, 2 * month));
+
+        vesting_schedule.borrow_mut().data[1].frozen = 1;
+        let frozen = claim(&mut vesting_schedule, &mut vesting_data, &mut ledger, beneficiary, None, 2 * month);
+        assert_eq!(Err(ErrorCode::GrantFrozen.into()), frozen);
+        vesting_schedule.borrow_mut().data[1].frozen = 0;
+        vesting_data.paused = true;
+        let paused = claim(&mut vesting_schedule, &mut vesting_data, &mut ledger, beneficiary, None, 2 * month);
+        assert_eq!(Err(ErrorCode::SchedulePaused.into()), paused);
+        vesting_data.paused = false;
+        assert_eq!(vec![200_000], ledger.paid_out);
+
+        assert_eq!(Ok(()), claim(&mut vesting_schedule, &mut vesting_data, &mut ledger, beneficiary, None, 2 * month));
+        assert_eq!(Ok(()), claim(&mut vesting_schedule, &mut vesting_data, &mut ledger, other.user, None, 2 * month));
+        assert_eq!(Ok(()), claim(&mut vesting_schedule, &mut vesting_data, &mut ledger, beneficiary, None, 14 * month));
+        assert_eq!(vec![200_000, 66_666, 266_666, 733_334], ledger.paid_out);
+        assert_eq!(1_000_000, { vesting_schedule.borrow().data[1].claimed_tokens });
+        assert_eq!((1_266_666, 733_334), (vesting_data.total_issued_so_far, ledger.vault));
+
+        // a vault that cannot pay fails the claim, on-chain the bookkeeping is rolled back with it
+        ledger.vault = 1;
+        let short = claim(&mut vesting_schedule, &mut vesting_data, &mut ledger, other.user, None, 14 * month);
+        assert_eq!(Err(ProgramError::InsufficientFunds), short);
+
+        vesting_data.claim_deadline = 14 * month;
+        let expired = claim(&mut vesting_schedule, &mut vesting_data, &mut ledger, beneficiary, None, 14 * month + 1);
+        assert_eq!(Err(ErrorCode::ClaimDeadlinePassed.into()), expired);
+        super::remove_user_active(&mut vesting_schedule.borrow_mut(), 1).unwrap();
+        assert_eq!(not_found, claim(&mut vesting_schedule, &mut vesting_data, &mut ledger, beneficiary, Some(1), 14 * month));
+    }
+
+    // One instruction of a random run, beneficiaries come from a small pool so that
+    // claims and removals keep hitting grants that exist
+    #[derive(Clone, Debug)]
+    enum Operation {
+        AddUser { beneficiary: u8, unlocked_at_tge: u8, unlocking_period: u8, planned_tokens: u64 },
+        RemoveUser { beneficiary: u8, index: Option<u8> },
+        Claim { beneficiary: u8, index: Option<u8> },
+        Warp { seconds: u32 },
+    }
+
+    impl quickcheck::Arbitrary for Operation {
+        fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+            let beneficiary = u8::arbitrary(g) % 4;
+            // mostly valid curves, now and then one that claim has to reject
+            let any_unlock = u8::arbitrary(g);
+            let unlocked_at_tge = *g.choose(&[0, 20, 50, 100, any_unlock]).unwrap();
+            let index = Option::<u8>::arbitrary(g).map(|index| index % 10);
+            match u8::arbitrary(g) % 4 {
+                0 => Operation::AddUser {
+                    beneficiary,
+                    unlocked_at_tge,
+                    unlocking_period: u8::arbitrary(g) % 25,
+                    planned_tokens: u64::arbitrary(g),
+                },
+                1 => Operation::RemoveUser { beneficiary, index },
+                2 => Operation::Claim { beneficiary, index },
+                _ => Operation::Warp { seconds: u32::arbitrary(g) % (2 * 732 * 60 * 60) },
+            }
+        }
+
+        fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+            match *self {
+                Operation::Warp { seconds } => Box::new(seconds.shrink().map(|seconds| Operation::Warp { seconds })),
+                _ => quickcheck::empty_shrinker(),
+            }
+        }
+    }
+
+    // Runs add_user, remove_user and claim the way their handlers do, a failing instruction
+    // leaves no trace like a failed transaction. Set QUICKCHECK_TESTS for longer runs.
+    #[quickcheck]
+    fn test_random_operations_keep_invariants(operations: Vec<Operation>) {
+        let tge = 732 * 60 * 60;
+        let beneficiaries = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        // small enough for runs to fill it up
+        let mut vesting_schedule = VestingScheduleBuf::new(8);
+        let mut vesting_data = VestingData { tge_timestamp: tge, ..Default::default() };
+        let mut ledger = Ledger { vault: 0, paid_out: Vec::new() };
+        let mut funded = 0u64;
+        let mut now = 0;
+
+        for operation in operations {
+            let before = (vesting_schedule.clone(), vesting_data.clone(), ledger.vault, funded);
+            let result: ProgramResult = match operation {
+                Operation::AddUser { beneficiary, unlocked_at_tge, unlocking_period, planned_tokens } => {
+                    let grant = VestingUser::new(unlocked_at_tge, beneficiaries[beneficiary as usize], unlocking_period, planned_tokens);
+                    // the admin funds the vault with every grant, as far as a token supply goes
+                    match funded.checked_add(planned_tokens) {
+                        Some(total) => {
+                            funded = total;
+                            ledger.vault += planned_tokens;
+                            super::add_user_active(grant, &mut vesting_schedule.borrow_mut()).map_err(Into::into)
+                        }
+                        None => Err(ProgramError::InsufficientFunds),
+                    }
+                }
+                Operation::RemoveUser { beneficiary, index } => {
+                    let schedule = &mut vesting_schedule.borrow_mut();
+                    super::find_grant(schedule.data, &beneficiaries[beneficiary as usize], index.map(u64::from), true)
+                        .and_then(|index| super::remove_user_active(schedule, index))
+                        .map_err(Into::into)
+                }
+                Operation::Claim { beneficiary, index } => super::claim(
+                    &mut vesting_schedule.borrow_mut(),
+                    index.map(u64::from),
+                    beneficiaries[beneficiary as usize],
+                    None,
+                    &mut vesting_data,
+                    &FixedClock(now),
+                    &mut ledger,
+                ),
+                Operation::Warp { seconds } => {
+                    now += seconds as u64;
+                    Ok(())
+                }
+            };
+            if result.is_err() {
+                vesting_schedule = before.0;
+                vesting_data = before.1;
+                ledger.vault = before.2;
+                funded = before.3;
+            }
+
+            let schedule = vesting_schedule.borrow();
+            assert_eq!(funded, ledger.vault + vesting_data.total_issued_so_far);
+            assert_eq!(ledger.paid_out.iter().sum::<u64>(), vesting_data.total_issued_so_far);
+            assert!(schedule.data.iter().all(|grant| grant.claimed_tokens <= grant.planned_tokens));
+            let active = (0..schedule.data.len())
+                .filter(|index| schedule.data[*index].status == utils::utils::EventStatus::PendingToken as u8)
+                .collect::<Vec<_>>();
+            let used = (0..schedule.data.len()).filter(|index| schedule.is_slot_used(*index)).collect::<Vec<_>>();
+            assert_eq!(schedule.len as usize, active.len());
+            assert_eq!(active, used);
+        }
+    }
+
+    #[test]
+    pub fn test_claimable_view() -> Result<(), ErrorCode> {
+        let month = 732 * 60 * 60;
+        let tge = 1_000_000;
+        let mut vesting_data = VestingData { tge_timestamp: tge, ..Default::default() };
+        let mut grant = VestingUser::new(
+            20,
+            Pubkey::from_str("29GPMU5gtBDbd368EwquqTmo33tKgvneAK9REmmxkqm8").unwrap(),
+            12,
+            1_000_000,
+        );
+
+        let view = super::claimable_view(&grant, &vesting_data, tge - 10)?;
+        assert_eq!((0, 0, Some(tge + 1)), (view.vested, view.claimable, view.next_unlock_timestamp));
+
+        // 800_000 tokens over 527_040 minutes, one more token about every 40 seconds
+        grant.claimed_tokens = 100_000;
+        let view = super::claimable_view(&grant, &vesting_data, tge + 30)?;
+        assert_eq!((200_000, 100_000, Some(tge + 60)), (view.vested, view.claimable, view.next_unlock_timestamp));
+        let next = view.next_unlock_timestamp.unwrap();
+        assert_eq!(200_001, super::claimable_view(&grant, &vesting_data, next)?.vested);
+        assert_eq!(200_000, super::claimable_view(&grant, &vesting_data, next - 1)?.vested);
+
+        // tokens below the number of minutes do not unlock every minute
+        let small = VestingUser::new(0, grant.user, 12, 1_000);
+        let next = super::claimable_view(&small, &vesting_data, tge + 1)?.next_unlock_timestamp.unwrap();
+        assert_eq!(tge + 528 * 60, next);
+        assert_eq!(0, super::claimable_view(&small, &vesting_data, next - 60)?.vested);
+        assert_eq!(1, super::claimable_view(&small, &vesting_data, next)?.vested);
+
+        // a running pause shifts the next unlock, a clock stopping pause has none
+        vesting_data.paused_seconds = 3_600;
+        let view = super::claimable_view(&grant, &vesting_data, tge + 3_600 + 30)?;
+        assert_eq!(Some(tge + 3_600 + 60), view.next_unlock_timestamp);
+        super::pause_schedule(&mut vesting_data, true, tge + 4_000)?;
+        let view = super::claimable_view(&grant, &vesting_data, tge + 5_000)?;
+        assert_eq!((0, None), (view.claimable, view.next_unlock_timestamp));
+        super::unpause_schedule(&mut vesting_data, tge + 5_000)?;
+
+        grant.frozen = 1;
+        assert_eq!(0, super::claimable_view(&grant, &vesting_data, tge + month)?.claimable);
+        grant.frozen = 0;
+        grant.suspended = 1;
+        grant.suspended_at = tge + month;
+        assert_eq!(None, super::claimable_view(&grant, &vesting_data, tge + 2 * month)?.next_unlock_timestamp);
+        grant.suspended = 0;
+
+        let view = super::claimable_view(&grant, &vesting_data, tge + 13 * month)?;
+        assert_eq!((1_000_000, 900_000, None), (view.vested, view.claimable, view.next_unlock_timestamp));
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_locked_balance() -> Result<(), ErrorCode> {
+        let month = 732 * 60 * 60;
+        let mut vesting_data = VestingData { tge_timestamp: month, ..Default::default() };
+        let mut grant = VestingUser::new(20, Pubkey::new_unique(), 12, 1_000_000);
+        grant.claimed_tokens = 100_000;
+
+        let balance = super::locked_balance(&grant, &vesting_data, 4 * month)?;
+        assert_eq!(grant.user, balance.beneficiary);
+        assert_eq!((400_000, 600_000, 300_000), (balance.vested, balance.locked, balance.unclaimed));
+        assert_eq!(1_000_000, balance.locked + balance.vested);
+
+        assert_eq!(9 * month, super::remaining_lockup(&grant, &vesting_data, 4 * month));
+        assert_eq!(13 * month, super::remaining_lockup(&grant, &vesting_data, 0));
+        assert_eq!(0, super::remaining_lockup(&grant, &vesting_data, 14 * month));
+        grant.suspended = 1;
+        grant.suspended_at = 4 * month;
+        assert_eq!(9 * month, super::remaining_lockup(&grant, &vesting_data, 6 * month));
+
+        vesting_data.claim_deadline = 3 * month;
+        let balance = super::locked_balance(&grant, &vesting_data, 4 * month)?;
+        assert_eq!((400_000, 0, 0), (balance.vested, balance.locked, balance.unclaimed));
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_accelerate() -> Result<(), ErrorCode> {
+        let mut vesting_schedule = VestingScheduleBuf::new(utils::utils::INITIAL_CAPACITY);
+        for (percent, planned_tokens) in [(20, 1_000_000), (0, 2_000_000), (100, 500_000)] {
+            let add_user = VestingUser::new(
+                percent,
+                Pubkey::from_str("29GPMU5gtBDbd368EwquqTmo33tKgvneAK9REmmxkqm8").unwrap(),
+                12,
+                planned_tokens,
+            );
+            super::add_user_active(add_user, &mut vesting_schedule.borrow_mut())?;
+        }
+        super::remove_user_active(&mut vesting_schedule.borrow_mut(), 2)?;
+        let now = 6 * 732 * 60 * 60;
+
+        // half of the unvested 400_000
+        assert_eq!(200_000, super::accelerate_grant(&mut vesting_schedule.borrow_mut(), 0, 5_000, 0, now)?);
+        let grant = vesting_schedule.borrow().data[0];
+        assert_eq!(200_000, { grant.accelerated_tokens });
+        assert_eq!(800_000, super::vested_amount(&grant, 0, now)?);
+        // and the rest of the curve still vests on top of that
+        assert_eq!(1_000_000, super::vested_amount(&grant, 0, 9 * 732 * 60 * 60)?);
+
+        // the whole schedule at once, inactive grants are skipped
+        let (grants, total) = super::accelerate_schedule(&mut vesting_schedule.borrow_mut(), 10_000, 0, now)?;
+        assert_eq!(2, grants);
+        assert_eq!(200_000 + 1_000_000, total);
+        assert_eq!(1_000_000, super::vested_amount(&vesting_schedule.borrow().data[0], 0, now)?);
+        assert_eq!(2_000_000, super::vested_amount(&vesting_schedule.borrow().data[1], 0, now)?);
+        assert_eq!(0, { vesting_schedule.borrow().data[2].accelerated_tokens });
+
+        let err = super::accelerate_grant(&mut vesting_schedule.borrow_mut(), 0, 10_001, 0, now);
+        assert_eq!(err.err(), Some(ErrorCode::InvalidInput));
+        let err = super::accelerate_grant(&mut vesting_schedule.borrow_mut(), 2, 5_000, 0, now);
+        assert_eq!(err.err(), Some(ErrorCode::InvalidInput));
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_reclaim_expired() -> Result<(), ErrorCode> {
+        let mut vesting_schedule = VestingScheduleBuf::new(utils::utils::INITIAL_CAPACITY);
+        let mut vesting_data = VestingData { tge_timestamp: 1_000, ..Default::default() };
+        for planned_tokens in [1_000_000, 2_000_000, 3_000_000] {
+            let mut add_user = VestingUser::new(
+                20,
+                Pubkey::from_str("29GPMU5gtBDbd368EwquqTmo33tKgvneAK9REmmxkqm8").unwrap(),
+                12,
+                planned_tokens,
+            );
+            add_user.claimed_tokens = 400_000;
+            super::add_user_active(add_user, &mut vesting_schedule.borrow_mut())?;
+        }
+
+        assert_eq!(Some(ErrorCode::InvalidInput), super::set_schedule_claim_deadline(&mut vesting_data, 1_000).err());
+        super::set_schedule_claim_deadline(&mut vesting_data, 10_000)?;
+        super::set_grant_claim_deadline(&mut vesting_schedule.borrow_mut(), 1, 20_000, 1_000)?;
+
+        // nothing expired before the deadline
+        let err = super::expire_grant(&mut vesting_schedule.borrow_mut(), 0, &mut vesting_data, 10_000);
+        assert_eq!(err.err(), Some(ErrorCode::InvalidInput));
+        assert_eq!((0, 0), super::expire_schedule(&mut vesting_schedule.borrow_mut(), &mut vesting_data, 10_000)?);
+
+        assert_eq!(600_000, super::expire_grant(&mut vesting_schedule.borrow_mut(), 0, &mut vesting_data, 10_001)?);
+        assert_eq!(utils::utils::EventStatus::Expired as u8, vesting_schedule.borrow().data[0].status);
+        assert_eq!(2, { vesting_schedule.borrow().len });
+
+        // grant 1 has its own later deadline
+        assert_eq!((1, 2_600_000), super::expire_schedule(&mut vesting_schedule.borrow_mut(), &mut vesting_data, 15_000)?);
+        assert_eq!(utils::utils::EventStatus::PendingToken as u8, vesting_schedule.borrow().data[1].status);
+        assert_eq!((1, 1_600_000), super::expire_schedule(&mut vesting_schedule.borrow_mut(), &mut vesting_data, 20_001)?);
+
+        assert_eq!(0, super::len(&mut vesting_schedule.borrow_mut()));
+        assert_eq!(4_800_000, vesting_data.total_reclaimed);
+        // expired slots are not handed out again
+        assert_eq!(Ok(3), super::get_next_index(&mut vesting_schedule.borrow_mut()));
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_merkle_claim_status() -> Result<(), ErrorCode> {
+        let vesting_data_key = Pubkey::new_unique();
+        let beneficiaries: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).collect();
+        let leaves = beneficiaries
+            .iter()
+            .map(|beneficiary| utils::merkle::leaf_hash(beneficiary, 1_000_000, 20, 12))
+            .collect();
+        let tree = utils::merkle::MerkleTree::new(leaves);
+        let mut vesting_data = VestingData::default();
+        let mut claim_status = MerkleClaimStatus::default();
+
+        let err = super::open_merkle_claim_status(
+            &mut claim_status, &vesting_data, vesting_data_key, beneficiaries[3], 1_000_000, 20, 12, &tree.proof(3),
+        );
+        assert_eq!(err.err(), Some(ErrorCode::InvalidMerkleProof));
+
+        vesting_data.merkle_root = tree.root();
+        // other terms than the committed ones
+        let err = super::open_merkle_claim_status(
+            &mut claim_status, &vesting_data, vesting_data_key, beneficiaries[3], 2_000_000, 20, 12, &tree.proof(3),
+        );
+        assert_eq!(err.err(), Some(ErrorCode::InvalidMerkleProof));
+
+        super::open_merkle_claim_status(
+            &mut claim_status, &vesting_data, vesting_data_key, beneficiaries[3], 1_000_000, 20, 12, &tree.proof(3),
+        )?;
+        assert_eq!(beneficiaries[3], claim_status.beneficiary);
+
+        // later claims need no proof, but the status stays bound to its beneficiary
+        claim_status.claimed_tokens = 200_000;
+        super::open_merkle_claim_status(&mut claim_status, &vesting_data, vesting_data_key, beneficiaries[3], 0, 0, 0, &[])?;
+        assert_eq!(200_000, claim_status.claimed_tokens);
+        let err = super::open_merkle_claim_status(
+            &mut claim_status, &vesting_data, vesting_data_key, beneficiaries[1], 1_000_000, 20, 12, &tree.proof(1),
+        );
+        assert_eq!(err.err(), Some(ErrorCode::InvalidInput));
+
+        let mut grant = claim_status.grant();
+        assert_eq!(400_000, super::claim_grant(&mut grant, &mut vesting_data, 6 * 732 * 60 * 60)?);
+        assert_eq!(600_000, { grant.claimed_tokens });
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_merkle_root_takes_no_claim_deadline() -> Result<(), ErrorCode> {
+        let mut vesting_data = VestingData { tge_timestamp: 1_000, ..Default::default() };
+
+        super::set_merkle_root(&mut vesting_data, [7; 32])?;
+        let err = super::set_schedule_claim_deadline(&mut vesting_data, 10_000);
+        assert_eq!(err.err(), Some(ErrorCode::InvalidInput));
+        assert_eq!(0, vesting_data.claim_deadline);
+
+        // either way round
+        super::set_merkle_root(&mut vesting_data, [0; 32])?;
+        super::set_schedule_claim_deadline(&mut vesting_data, 10_000)?;
+        let err = super::set_merkle_root(&mut vesting_data, [7; 32]);
+        assert_eq!(err.err(), Some(ErrorCode::InvalidInput));
+        assert_eq!([0; 32], vesting_data.merkle_root);
+
+        super::set_schedule_claim_deadline(&mut vesting_data, 0)?;
+        super::set_merkle_root(&mut vesting_data, [7; 32])?;
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_stake_grant() -> Result<(), ErrorCode> {
+        let month = 732 * 60 * 60;
+        let mut vesting_data = VestingData { tge_timestamp: month, ..Default::default() };
+        let mut grant = VestingUser::new(20, Pubkey::new_unique(), 12, 1_000_000);
+
+        // 600_000 are still locked after three months
+        assert_eq!(Some(ErrorCode::InvalidInput), super::stake_grant(&mut grant, &mut vesting_data, 0, 4 * month).err());
+        let err = super::stake_grant(&mut grant, &mut vesting_data, 600_001, 4 * month);
+        assert_eq!(Some(ErrorCode::StakeExceedsLocked), err.err());
+        super::stake_grant(&mut grant, &mut vesting_data, 500_000, 4 * month)?;
+        let err = super::stake_grant(&mut grant, &mut vesting_data, 100_001, 4 * month);
+        assert_eq!(Some(ErrorCode::StakeExceedsLocked), err.err());
+        assert_eq!((500_000, 500_000), ({ grant.staked_tokens }, vesting_data.total_staked));
+        let err = super::set_staking_program(&mut vesting_data, Pubkey::new_unique());
+        assert_eq!(Some(ErrorCode::StakeOutstanding), err.err());
+
+        // the vested 400_000 are all in the vault
+        assert_eq!(400_000, super::claim_grant_from_vault(&mut grant, &mut vesting_data, 4 * month)?);
+        assert_eq!(100_000, super::vault_share(&grant));
+
+        // fully vested, only 100_000 of the remaining 600_000 are left in the vault
+        let mut vault_only = (grant, vesting_data.clone());
+        let err = super::claim_grant_from_vault(&mut vault_only.0, &mut vault_only.1, 14 * month);
+        assert_eq!(Some(ErrorCode::ClaimNeedsUnstake), err.err());
+        assert_eq!((100_000, 500_000), super::claim_grant_from_stake(&mut grant, &mut vesting_data, 14 * month)?);
+        assert_eq!((1_000_000, 0), ({ grant.claimed_tokens }, { grant.staked_tokens }));
+        assert_eq!((1_000_000, 0), (vesting_data.total_issued_so_far, vesting_data.total_staked));
+
+        assert_eq!(Some(ErrorCode::InvalidInput), super::unstake_grant(&mut grant, &mut vesting_data, 1).err());
+        super::set_staking_program(&mut vesting_data, Pubkey::new_unique())?;
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_staked_grant_cannot_be_reclaimed_amended_or_removed() -> Result<(), ErrorCode> {
+        let mut vesting_schedule = VestingScheduleBuf::new(utils::utils::INITIAL_CAPACITY);
+        let mut vesting_data = VestingData { tge_timestamp: 1_000, claim_deadline: 10_000, ..Default::default() };
+        for staked_tokens in [300_000, 0] {
+            let mut add_user = VestingUser::new(20, Pubkey::new_unique(), 12, 1_000_000);
+            add_user.claimed_tokens = 200_000;
+            add_user.staked_tokens = staked_tokens;
+            super::add_user_active(add_user, &mut vesting_schedule.borrow_mut())?;
+        }
+
+        let err = super::amend_grant(&mut vesting_schedule.borrow_mut(), 0, 20, 12, 499_999, true, 1_000, 2_000);
+        assert_eq!(Some(ErrorCode::AmendmentBelowClaimed), err.err());
+
+        let err = super::expire_grant(&mut vesting_schedule.borrow_mut(), 0, &mut vesting_data, 10_001);
+        assert_eq!(Some(ErrorCode::StakeOutstanding), err.err());
+        assert_eq!((1, 800_000), super::expire_schedule(&mut vesting_schedule.borrow_mut(), &mut vesting_data, 10_001)?);
+        assert_eq!(utils::utils::EventStatus::PendingToken as u8, vesting_schedule.borrow().data[0].status);
+
+        let err = super::remove_user_active(&mut vesting_schedule.borrow_mut(), 0);
+        assert_eq!(Some(ErrorCode::StakeOutstanding), err.err());
+        assert!(vesting_schedule.borrow().is_slot_used(0));
+        assert_eq!(1, { vesting_schedule.borrow().len });
+        super::remove_user_active(&mut vesting_schedule.borrow_mut(), 1)?;
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_transfer_grant() -> Result<(), ErrorCode> {
+        let mut vesting_data = VestingData::default();
+        let original = Pubkey::new_unique();
+        let buyer = Pubkey::new_unique();
+        let receipt_mint = Pubkey::new_unique();
+        let mut grant = VestingUser::new(20, original, 12, 1_000_000);
+
+        // transfers are off until the admin turns them on
+        assert_eq!(Some(ErrorCode::TransfersDisabled), super::change_beneficiary(&mut grant, &vesting_data, buyer).err());
+        let err = super::attach_receipt(&mut grant, &vesting_data, receipt_mint);
+        assert_eq!(Some(ErrorCode::TransfersDisabled), err.err());
+        vesting_data.transferable = true;
+
+        assert_eq!(Some(ErrorCode::InvalidInput), super::change_beneficiary(&mut grant, &vesting_data, Pubkey::default()).err());
+        assert_eq!(original, super::change_beneficiary(&mut grant, &vesting_data, buyer)?);
+        assert_eq!(buyer, grant.user);
+
+        // with a receipt the grant follows the receipt, and nothing else
+        super::attach_receipt(&mut grant, &vesting_data, receipt_mint)?;
+        let err = super::attach_receipt(&mut grant, &vesting_data, Pubkey::new_unique());
+        assert_eq!(Some(ErrorCode::InvalidInput), err.err());
+        let err = super::change_beneficiary(&mut grant, &vesting_data, original);
+        assert_eq!(Some(ErrorCode::GrantHasReceipt), err.err());
+        let err = super::sync_beneficiary(&mut grant, Pubkey::new_unique(), original, 1);
+        assert_eq!(Some(ErrorCode::InvalidInput), err.err());
+        let err = super::sync_beneficiary(&mut grant, receipt_mint, original, 0);
+        assert_eq!(Some(ErrorCode::InvalidInput), err.err());
+        assert_eq!(buyer, super::sync_beneficiary(&mut grant, receipt_mint, original, 1)?);
+        assert_eq!(original, grant.user);
+
+        // turning transfers off stops new receipts, not the ones already out there
+        vesting_data.transferable = false;
+        assert_eq!(original, super::sync_beneficiary(&mut grant, receipt_mint, buyer, 1)?);
+        assert_eq!(buyer, grant.user);
+        let err = super::attach_receipt(&mut VestingUser::new(20, original, 12, 1_000), &vesting_data, receipt_mint);
+        assert_eq!(Some(ErrorCode::TransfersDisabled), err.err());
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_claim_after_receipt_transfer() {
+        let month = 732 * 60 * 60;
+        let seller = Pubkey::new_unique();
+        let buyer = Pubkey::new_unique();
+        let receipt_mint = Pubkey::new_unique();
+        let mut vesting_schedule = VestingScheduleBuf::new(utils::utils::INITIAL_CAPACITY);
+        let mut grant = VestingUser::new(20, seller, 12, 1_000_000);
+        grant.receipt_mint = receipt_mint;
+        super::add_user_active(grant, &mut vesting_schedule.borrow_mut()).unwrap();
+        let mut vesting_data = VestingData { tge_timestamp: month, transferable: true, ..Default::default() };
+        let mut ledger = Ledger { vault: 1_000_000, paid_out: Vec::new() };
+        let held_by = |holder| Some(super::ReceiptHolding { mint: receipt_mint, holder, amount: 1 });
+
+        // the receipt went to the buyer without a sync_beneficiary, the seller is still on the grant
+        let mut claim = |claim_user, receipt, index| {
+            let now = FixedClock(month + 1);
+            super::claim(&mut vesting_schedule.borrow_mut(), index, claim_user, receipt, &mut vesting_data, &now, &mut ledger)
+        };
+        let not_held = Err(ErrorCode::ReceiptNotHeld.into());
+        assert_eq!(not_held, claim(seller, None, None));
+        assert_eq!(not_held, claim(seller, held_by(buyer), None));
+        let emptied = Some(super::ReceiptHolding { mint: receipt_mint, holder: seller, amount: 0 });
+        assert_eq!(not_held, claim(seller, emptied, Some(0)));
+        let forged = Some(super::ReceiptHolding { mint: Pubkey::new_unique(), holder: buyer, amount: 1 });
+        assert_eq!(Err(ErrorCode::GrantNotFound.into()), claim(buyer, forged, None));
+        assert_eq!(Ok(()), claim(buyer, held_by(buyer), Some(0)));
+        assert_eq!(Err(ErrorCode::GrantNotFound.into()), claim(seller, None, None));
+        assert_eq!(vec![200_000], ledger.paid_out);
+        assert_eq!(buyer, { vesting_schedule.borrow().data[0].user });
+
+        // the other beneficiary paths follow the receipt the same way
+        let mut schedule = vesting_schedule.borrow_mut();
+        let err = super::beneficiary_grant(&mut schedule, 0, buyer, None);
+        assert_eq!(Some(ErrorCode::ReceiptNotHeld), err.err());
+        let err = super::beneficiary_grant(&mut schedule, 1, buyer, held_by(buyer));
+        assert_eq!(Some(ErrorCode::InvalidInput), err.err());
+        assert_eq!(seller, super::beneficiary_grant(&mut schedule, 0, seller, held_by(seller)).unwrap().user);
+        assert_eq!(seller, { schedule.data[0].user });
+    }
+
+    #[test]
+    pub fn test_split_grant() -> Result<(), ErrorCode> {
+        let month = 732 * 60 * 60;
+        let mut vesting_schedule = VestingScheduleBuf::new(utils::utils::INITIAL_CAPACITY);
+        let mut vesting_data = VestingData { tge_timestamp: month, ..Default::default() };
+        let user = Pubkey::new_unique();
+        let lp = Pubkey::new_unique();
+        let mut grant = VestingUser::new(20, user, 12, 1_000_000);
+        grant.claimed_tokens = 300_000;
+        super::add_user_active(grant, &mut vesting_schedule.borrow_mut())?;
+
+        let mut split = |split_tokens, new_beneficiary, vesting_data: &VestingData| {
+            super::split_grant(&mut vesting_schedule.borrow_mut(), 0, split_tokens, new_beneficiary, vesting_data, 4 * month)
+        };
+        assert_eq!(Some(ErrorCode::InvalidInput), split(0, user, &vesting_data).err());
+        assert_eq!(Some(ErrorCode::InvalidInput), split(1_000_000, user, &vesting_data).err());
+        assert_eq!(Some(ErrorCode::TransfersDisabled), split(250_000, lp, &vesting_data).err());
+        vesting_data.transferable = true;
+        assert_eq!(1, split(250_000, lp, &vesting_data)?);
+
+        let schedule = vesting_schedule.borrow();
+        let (kept, split) = (schedule.data[0], schedule.data[1]);
+        assert_eq!(2, { schedule.len });
+        assert_eq!((750_000, 225_000), ({ kept.planned_tokens }, { kept.claimed_tokens }));
+        assert_eq!((250_000, 75_000), ({ split.planned_tokens }, { split.claimed_tokens }));
+        assert_eq!((lp, 750_000), (split.user, { split.share_start }));
+        assert_eq!((1_000_000, 1_000_000), ({ kept.curve_tokens }, { split.curve_tokens }));
+        // a quarter of the 400_000 vested after three months
+        assert_eq!(100_000, super::vested_amount(&split, month, 4 * month)?);
+        assert_eq!(25_000, super::claimable_amount(&split, month, 4 * month)?);
+        Ok(())
+    }
+
+    // An original grant at `period` months, split at `split_at` seconds after TGE,
+    // `claimed_share` of what it vested by then already claimed
+    fn split_fixture(
+        planned_tokens: u64,
+        unlocked_at_tge: u8,
+        unlocking_period: u8,
+        split_tokens: u64,
+        claimed_share: u8,
+        split_at: u32,
+    ) -> (VestingUser, VestingScheduleBuf, VestingData, u64) {
+        let tge_timestamp = 1_000_000;
+        let vesting_data = VestingData { tge_timestamp, transferable: true, ..Default::default() };
+        let planned_tokens = planned_tokens.max(2);
+        let split_tokens = split_tokens % (planned_tokens - 1) + 1;
+        let mut original = VestingUser::new(unlocked_at_tge % 101, Pubkey::new_unique(), unlocking_period % 36 + 1, planned_tokens);
+        let now = tge_timestamp + split_at as u64 % (40 * 732 * 60 * 60);
+        let vested = super::vested_amount(&original, tge_timestamp, now).unwrap();
+        original.claimed_tokens = (vested as u128 * claimed_share as u128 / 255) as u64;
+
+        let mut vesting_schedule = VestingScheduleBuf::new(utils::utils::INITIAL_CAPACITY);
+        super::add_user_active(original, &mut vesting_schedule.borrow_mut()).unwrap();
+        super::split_grant(&mut vesting_schedule.borrow_mut(), 0, split_tokens, Pubkey::new_unique(), &vesting_data, now)
+            .unwrap();
+        (original, vesting_schedule, vesting_data, now)
+    }
+
+    fn assert_same_entitlement(original: &VestingUser, grants: &[VestingUser], tge_timestamp: u64, now: u64) {
+        let vested: u64 = grants.iter().map(|grant| super::vested_amount(grant, tge_timestamp, now).unwrap()).sum();
+        assert_eq!(super::vested_amount(original, tge_timestamp, now).unwrap(), vested);
+        for grant in grants {
+            assert!(super::claimable_amount(grant, tge_timestamp, now).is_ok());
+        }
+    }
+
+    #[quickcheck]
+    fn test_split_keeps_entitlement(
+        planned_tokens: u64,
+        unlocked_at_tge: u8,
+        unlocking_period: u8,
+        split_tokens: u64,
+        claimed_share: u8,
+        split_at: u32,
+        later: Vec<u32>,
+    ) {
+        let (original, vesting_schedule, vesting_data, now) =
+            split_fixture(planned_tokens, unlocked_at_tge, unlocking_period, split_tokens, claimed_share, split_at);
+        let schedule = vesting_schedule.borrow();
+        let grants = [schedule.data[0], schedule.data[1]];
+
+        assert_eq!({ original.planned_tokens }, { grants[0].planned_tokens } + { grants[1].planned_tokens });
+        assert_eq!({ original.claimed_tokens }, { grants[0].claimed_tokens } + { grants[1].claimed_tokens });
+        assert_eq!((original.unlocked_at_tge, original.unlocking_period), (grants[1].unlocked_at_tge, grants[1].unlocking_period));
+        assert_same_entitlement(&original, &grants, vesting_data.tge_timestamp, now);
+        for offset in later {
+            assert_same_entitlement(&original, &grants, vesting_data.tge_timestamp, now + offset as u64 * 60);
+        }
+        assert_same_entitlement(&original, &grants, vesting_data.tge_timestamp, u64::MAX / 2);
+    }
+
+    #[quickcheck]
+    fn test_split_of_split_keeps_entitlement(
+        planned_tokens: u64,
+        unlocked_at_tge: u8,
+        unlocking_period: u8,
+        split_tokens: u64,
+        second_split_tokens: u64,
+        split_at: u32,
+        later: Vec<u32>,
+    ) {
+        let (original, mut vesting_schedule, vesting_data, now) =
+            split_fixture(planned_tokens, unlocked_at_tge, unlocking_period, split_tokens, 128, split_at);
+        let second = if vesting_schedule.borrow().data[1].planned_tokens > 1 { 1 } else { 0 };
+        let planned = vesting_schedule.borrow().data[second].planned_tokens;
+        if planned < 2 {
+            return;
+        }
+        let second_split_tokens = second_split_tokens % (planned - 1) + 1;
+        super::split_grant(&mut vesting_schedule.borrow_mut(), second, second_split_tokens, Pubkey::new_unique(), &vesting_data, now)
+            .unwrap();
+
+        let schedule = vesting_schedule.borrow();
+        let grants = [schedule.data[0], schedule.data[1], schedule.data[2]];
+        let claimed: u64 = grants.iter().map(|grant| grant.claimed_tokens).sum();
+        assert_eq!({ original.claimed_tokens }, claimed);
+        for offset in later {
+            assert_same_entitlement(&original, &grants, vesting_data.tge_timestamp, now + offset as u64 * 60);
+        }
+    }
+
+    #[quickcheck]
+    fn test_split_grant_next_unlock(planned_tokens: u64, unlocked_at_tge: u8, split_tokens: u64, split_at: u32) -> bool {
+        let (_, vesting_schedule, vesting_data, now) =
+            split_fixture(planned_tokens, unlocked_at_tge, 12, split_tokens, 0, split_at);
+        let grant = vesting_schedule.borrow().data[1];
+        let tge_timestamp = vesting_data.tge_timestamp;
+        let vested = super::vested_amount(&grant, tge_timestamp, now).unwrap();
+        match super::next_unlock(&grant, tge_timestamp, now).unwrap() {
+            None => vested == grant.planned_tokens,
+            Some(next) if now <= tge_timestamp => next == tge_timestamp + 1,
+            // vests more then, and not a minute earlier
+            Some(next) => {
+                super::vested_amount(&grant, tge_timestamp, next).unwrap() > vested
+                    && (next - 60 <= now || super::vested_amount(&grant, tge_timestamp, next - 60).unwrap() == vested)
+            }
+        }
+    }
+}
@@ -0,0 +1,206 @@
+// Compute units of every instruction on the BPF build, checked against tests/compute_units.txt.
+// Native processors are not metered, so this only runs under cargo test-bpf.
+#![cfg(feature = "test-bpf")]
+use solana_program_test::{ProgramTest, ProgramTestContext};
+use solana_sdk::account::Account;
+use solana_sdk::clock::Clock;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::system_program;
+use solana_sdk::transaction::Transaction;
+use std::sync::Mutex;
+use vesting_schedule_client::instructions;
+use vesting_schedule_client::projection::MONTH;
+
+const BUDGET_FILE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/compute_units.txt");
+const BUDGET_HEADER: &str = "\
+# Compute units each instruction may consume, checked by tests/compute_units.rs.
+# Recorded with 10% headroom through
+# UPDATE_COMPUTE_UNITS=1 cargo test-bpf --test compute_units
+";
+const HEADROOM_PERCENT: u64 = 10;
+// What the runtime allows an instruction anyway, a budget this high never catches a regression
+const RUNTIME_LIMIT: u64 = 200_000;
+const TGE: i64 = 1_629_158_400;
+// add_user instructions that fit into one transaction
+const ADD_USER_BATCH: usize = 12;
+
+// The runtime reports what a program consumed through the log crate only, so the test
+// installs its own logger before program-test sets up one of its own
+static CONSUMED: Mutex<Vec<u64>> = Mutex::new(Vec::new());
+
+struct ComputeUnitLog;
+
+impl log::Log for ComputeUnitLog {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.target() == "solana_runtime::message_processor::stable_log"
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let message = record.args().to_string();
+        let prefix = format!("Program {} consumed ", vesting_schedule::ID);
+        let units = message.strip_prefix(&prefix).and_then(|rest| rest.split(' ').next()?.parse().ok());
+        if let Some(units) = units {
+            CONSUMED.lock().unwrap().push(units);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: ComputeUnitLog = ComputeUnitLog;
+
+fn token_account(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Account {
+    let mut data = vec![0; spl_token::state::Account::LEN];
+    let state = spl_token::state::Account {
+        mint: *mint,
+        owner: *owner,
+        amount,
+        state: spl_token::state::AccountState::Initialized,
+        ..Default::default()
+    };
+    spl_token::state::Account::pack(state, &mut data).unwrap();
+    Account { lamports: 1_000_000_000, data, owner: spl_token::ID, executable: false, rent_epoch: 0 }
+}
+
+fn read_budgets() -> Vec<(String, u64)> {
+    let budgets = std::fs::read_to_string(BUDGET_FILE).unwrap();
+    budgets
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let mut fields = line.split_whitespace();
+            let name = fields.next().unwrap().to_string();
+            let units = fields.next().and_then(|units| units.parse().ok());
+            (name, units.unwrap_or_else(|| panic!("no compute units in {:?}", line)))
+        })
+        .collect()
+}
+
+struct Bench {
+    context: ProgramTestContext,
+    slot: u64,
+    measured: Vec<(&'static str, u64)>,
+}
+
+impl Bench {
+    // Sends the transaction at `now` and returns what vesting_schedule consumed in it
+    async fn process(&mut self, instructions: &[Instruction], signers: &[&Keypair], now: i64) -> u64 {
+        self.slot += 2;
+        self.context.warp_to_slot(self.slot).unwrap();
+        let mut clock: Clock = self.context.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp = now;
+        self.context.set_sysvar(&clock);
+
+        let mut all_signers = vec![&self.context.payer];
+        all_signers.extend_from_slice(signers);
+        let recent_blockhash = self.context.banks_client.get_latest_blockhash().await.unwrap();
+        let transaction =
+            Transaction::new_signed_with_payer(instructions, Some(&self.context.payer.pubkey()), &all_signers, recent_blockhash);
+        CONSUMED.lock().unwrap().clear();
+        self.context.banks_client.process_transaction(transaction).await.unwrap();
+        CONSUMED.lock().unwrap().iter().max().copied().expect("no compute units logged, is this the BPF build?")
+    }
+
+    async fn measure(&mut self, name: &'static str, instruction: Instruction, signers: &[&Keypair], now: i64) {
+        let units = self.process(&[instruction], signers, now).await;
+        self.measured.push((name, units));
+    }
+}
+
+#[tokio::test]
+async fn test_compute_units() {
+    log::set_logger(&LOGGER).expect("another logger is already installed");
+    let mut program_test = ProgramTest::new("vesting_schedule", vesting_schedule::ID, None);
+    program_test.prefer_bpf(true);
+    // program-test turns its own log levels on, keep the stable log on whatever RUST_LOG says
+    log::set_max_level(log::LevelFilter::Debug);
+
+    let admin = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let vesting_vault = Pubkey::new_unique();
+    let beneficiary = Pubkey::new_unique();
+    let destination = Pubkey::new_unique();
+    let mut data = vec![0; spl_token::state::Mint::LEN];
+    let state = spl_token::state::Mint { supply: 1_000_000, decimals: 6, is_initialized: true, ..Default::default() };
+    spl_token::state::Mint::pack(state, &mut data).unwrap();
+    program_test.add_account(mint, Account { lamports: 1_000_000_000, data, owner: spl_token::ID, executable: false, rent_epoch: 0 });
+    program_test.add_account(admin.pubkey(), Account::new(1_000_000_000, 0, &system_program::ID));
+    program_test.add_account(vesting_vault, token_account(&mint, &admin.pubkey(), 1_000_000));
+    program_test.add_account(destination, token_account(&mint, &beneficiary, 0));
+
+    let mut bench = Bench { context: program_test.start_with_context().await, slot: 1, measured: Vec::new() };
+    let (vesting_data, vesting_schedule) = (Keypair::new(), Keypair::new());
+    let rent = bench.context.banks_client.get_rent().await.unwrap();
+    let space = instructions::vesting_schedule_space();
+    let create_schedule = system_instruction::create_account(
+        &bench.context.payer.pubkey(),
+        &vesting_schedule.pubkey(),
+        rent.minimum_balance(space),
+        space as u64,
+        &vesting_schedule::ID,
+    );
+    bench.process(&[create_schedule], &[&vesting_schedule], TGE - MONTH).await;
+    let initialize =
+        instructions::initialize(&admin.pubkey(), &vesting_data.pubkey(), &vesting_schedule.pubkey(), &vesting_vault, TGE as u64);
+    bench.measure("initialize", initialize, &[&admin, &vesting_data], TGE - MONTH).await;
+
+    // the schedule holds INITIAL_CAPACITY grants, the beneficiary's comes last so claim and
+    // remove_user scan all of them
+    let add_user = |beneficiary: &Pubkey| {
+        instructions::add_user(&admin.pubkey(), &vesting_data.pubkey(), &vesting_schedule.pubkey(), beneficiary, 20, 12, 1_000)
+    };
+    bench.measure("add_user_empty", add_user(&Pubkey::new_unique()), &[&admin], TGE - MONTH).await;
+    let others = (0..vesting_schedule::utils::utils::INITIAL_CAPACITY - 2).map(|_| add_user(&Pubkey::new_unique())).collect::<Vec<_>>();
+    for batch in others.chunks(ADD_USER_BATCH) {
+        bench.process(batch, &[&admin], TGE - MONTH).await;
+    }
+    bench.measure("add_user_nearly_full", add_user(&beneficiary), &[&admin], TGE - MONTH).await;
+    let claim = instructions::claim(
+        &admin.pubkey(),
+        &vesting_data.pubkey(),
+        &vesting_schedule.pubkey(),
+        &vesting_vault,
+        &beneficiary,
+        &destination,
+        None,
+        None,
+    );
+    bench.measure("claim", claim, &[], TGE + 1).await;
+    let remove_user =
+        instructions::remove_user(&admin.pubkey(), &vesting_data.pubkey(), &vesting_schedule.pubkey(), &beneficiary, None);
+    bench.measure("remove_user", remove_user, &[&admin], TGE + 1).await;
+
+    if std::env::var("UPDATE_COMPUTE_UNITS").is_ok() {
+        let mut budgets = BUDGET_HEADER.to_string();
+        for (name, units) in bench.measured.iter() {
+            println!("{:<24} {:>8}", name, units);
+            budgets += &format!("{} {}\n", name, units + units * HEADROOM_PERCENT / 100);
+        }
+        std::fs::write(BUDGET_FILE, budgets).unwrap();
+        return;
+    }
+    let budgets = read_budgets();
+    let over = bench
+        .measured
+        .iter()
+        .filter_map(|(name, units)| {
+            let budget = budgets.iter().find(|(budget_name, _)| budget_name == name).map(|(_, budget)| *budget);
+            match budget {
+                Some(budget) if budget >= RUNTIME_LIMIT => {
+                    Some(format!("{} consumed {} compute units, its budget {} was never recorded", name, units, budget))
+                }
+                Some(budget) if *units <= budget => None,
+                budget => Some(format!("{} consumed {} compute units, budget {:?}", name, units, budget)),
+            }
+        })
+        .collect::<Vec<_>>();
+    assert!(over.is_empty(), "{}", over.join("\n"));
+}
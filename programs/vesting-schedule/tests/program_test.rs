@@ -0,0 +1,860 @@
+// vesting_schedule end to end under program-test: the program and spl-token run as native
+// processors on a local bank, the clock is set before every transaction.
+use anchor_lang::{AccountDeserialize, AccountSerialize};
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::account::{Account, AccountSharedData};
+use solana_sdk::clock::Clock;
+use solana_sdk::instruction::{Instruction, InstructionError};
+use solana_sdk::program_error::ProgramError;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::program_option::COption;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_instruction::SystemError;
+use solana_sdk::system_program;
+use solana_sdk::transaction::{Transaction, TransactionError};
+use solana_sdk::transport::TransportError;
+use vesting_schedule::utils::utils::{EventStatus, INITIAL_CAPACITY};
+use vesting_schedule::{vesting_operations, ErrorCode, VestingData, VestingScheduleBuf, VestingUser};
+use vesting_schedule_client::projection::MONTH;
+use vesting_schedule_client::{accounts, instructions, pda};
+
+const TGE: i64 = 1_629_158_400;
+const ALICE_TOKENS: u64 = 1_000_000;
+const BOB_TOKENS: u64 = 500_000;
+
+struct Fixture {
+    context: ProgramTestContext,
+    slot: u64,
+    now: i64,
+    admin: Keypair,
+    mint: Pubkey,
+    vesting_data: Keypair,
+    vesting_schedule: Keypair,
+    vesting_vault: Pubkey,
+    alice: Pubkey,
+    bob: Pubkey,
+    attacker: Pubkey,
+    // a schedule that is not the one of vesting_data, program-owned like any schedule
+    // someone set up for themselves through initialize
+    forged_schedule: Pubkey,
+}
+
+fn account(owner: &Pubkey, data: Vec<u8>) -> Account {
+    Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: *owner,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+fn token_account(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Account {
+    let mut data = vec![0; spl_token::state::Account::LEN];
+    let state = spl_token::state::Account {
+        mint: *mint,
+        owner: *owner,
+        amount,
+        state: spl_token::state::AccountState::Initialized,
+        ..Default::default()
+    };
+    spl_token::state::Account::pack(state, &mut data).unwrap();
+    account(&spl_token::ID, data)
+}
+
+// The token account of `owner` the fixture put into genesis
+fn token_address(owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[owner.as_ref()], &spl_token::ID).0
+}
+
+fn error_code(error: impl Into<ProgramError>) -> InstructionError {
+    match error.into() {
+        ProgramError::Custom(code) => InstructionError::Custom(code),
+        error => panic!("not a program error code: {}", error),
+    }
+}
+
+impl Fixture {
+    async fn new() -> Self {
+        let mut program_test = ProgramTest::new(
+            "vesting_schedule",
+            vesting_schedule::ID,
+            processor!(vesting_schedule::process_instruction),
+        );
+        // native spl-token, the bundled BPF build does not run under the interpreter here
+        program_test.add_program(
+            "spl_token",
+            spl_token::ID,
+            processor!(spl_token::processor::Processor::process),
+        );
+
+        let admin = Keypair::new();
+        let mint = Pubkey::new_unique();
+        let vesting_vault = Pubkey::new_unique();
+        let (alice, bob, attacker) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+
+        let mut data = vec![0; spl_token::state::Mint::LEN];
+        let state = spl_token::state::Mint {
+            supply: ALICE_TOKENS + BOB_TOKENS,
+            decimals: 6,
+            is_initialized: true,
+            ..Default::default()
+        };
+        spl_token::state::Mint::pack(state, &mut data).unwrap();
+        program_test.add_account(mint, account(&spl_token::ID, data));
+        program_test.add_account(admin.pubkey(), account(&system_program::ID, vec![]));
+        program_test.add_account(
+            vesting_vault,
+            token_account(&mint, &admin.pubkey(), ALICE_TOKENS + BOB_TOKENS),
+        );
+        for owner in [alice, bob, attacker].iter() {
+            program_test.add_account(token_address(owner), token_account(&mint, owner, 0));
+        }
+
+        let forged_schedule = Pubkey::new_unique();
+        let mut schedule = VestingScheduleBuf::new(300);
+        schedule.borrow_mut().len = 1;
+        schedule.borrow_mut().data[0] = VestingUser::new(100, attacker, 0, ALICE_TOKENS);
+        program_test.add_account(
+            forged_schedule,
+            account(&vesting_schedule::ID, schedule.account_data().to_vec()),
+        );
+
+        Fixture {
+            context: program_test.start_with_context().await,
+            slot: 1,
+            now: TGE - MONTH,
+            admin,
+            mint,
+            vesting_data: Keypair::new(),
+            vesting_schedule: Keypair::new(),
+            vesting_vault,
+            alice,
+            bob,
+            attacker,
+            forged_schedule,
+        }
+    }
+
+    // Every transaction lands in a slot of its own, so the same claim can be sent twice.
+    // A warp needs two slots: it freezes the one before the target.
+    async fn process(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), InstructionError> {
+        self.slot += 2;
+        self.context.warp_to_slot(self.slot).unwrap();
+        let mut clock: Clock = self.context.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp = self.now;
+        self.context.set_sysvar(&clock);
+
+        let mut all_signers = vec![&self.context.payer];
+        all_signers.extend_from_slice(signers);
+        let recent_blockhash = self
+            .context
+            .banks_client
+            .get_latest_blockhash()
+            .await
+            .unwrap();
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.context.payer.pubkey()),
+            &all_signers,
+            recent_blockhash,
+        );
+        match self
+            .context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+        {
+            Ok(()) => Ok(()),
+            Err(TransportError::TransactionError(TransactionError::InstructionError(_, error))) => {
+                Err(error)
+            }
+            Err(error) => panic!("transaction failed outside the program: {}", error),
+        }
+    }
+
+    // initialize creates vesting_data through a system program CPI, native program-test cannot
+    // run that as it does not resize account data. The fixture writes the accounts as initialize
+    // leaves them instead, test_initialize holds that against the BPF build.
+    async fn initialize(&mut self) {
+        for (address, account) in self.initialized_accounts().await {
+            self.context
+                .set_account(&address, &AccountSharedData::from(account));
+        }
+    }
+
+    async fn initialized_accounts(&mut self) -> Vec<(Pubkey, Account)> {
+        let rent = self.context.banks_client.get_rent().await.unwrap();
+        let rent_exempt = |data: Vec<u8>| Account {
+            lamports: rent.minimum_balance(data.len()),
+            ..account(&vesting_schedule::ID, data)
+        };
+        let authority = pda::vesting_vault_authority(&self.admin.pubkey());
+        let vault_owner = authority.key;
+        let mut vesting_data = VestingData::default();
+        vesting_operations::initialize_vesting_data(
+            &mut vesting_data,
+            self.admin.pubkey(),
+            self.vesting_schedule.pubkey(),
+            self.vesting_vault,
+            authority,
+            TGE as u64,
+        );
+        let mut data = Vec::new();
+        vesting_data.try_serialize(&mut data).unwrap();
+        let mut schedule = VestingScheduleBuf::new(INITIAL_CAPACITY);
+        vesting_operations::initialize_users(&mut schedule.borrow_mut());
+        vec![
+            (self.vesting_data.pubkey(), rent_exempt(data)),
+            (
+                self.vesting_schedule.pubkey(),
+                rent_exempt(schedule.account_data().to_vec()),
+            ),
+            (
+                self.vesting_vault,
+                token_account(&self.mint, &vault_owner, ALICE_TOKENS + BOB_TOKENS),
+            ),
+        ]
+    }
+
+    fn initialize_instruction(&self, tge_timestamp: i64) -> Instruction {
+        instructions::initialize(
+            &self.admin.pubkey(),
+            &self.vesting_data.pubkey(),
+            &self.vesting_schedule.pubkey(),
+            &self.vesting_vault,
+            tge_timestamp as u64,
+        )
+    }
+
+    fn keypairs(&self) -> (Keypair, Keypair, Keypair) {
+        let copy = |keypair: &Keypair| Keypair::from_bytes(&keypair.to_bytes()).unwrap();
+        (
+            copy(&self.admin),
+            copy(&self.vesting_data),
+            copy(&self.vesting_schedule),
+        )
+    }
+
+    async fn add_user(
+        &mut self,
+        beneficiary: Pubkey,
+        unlocked_at_tge: u8,
+        unlocking_period: u8,
+        planned_tokens: u64,
+    ) {
+        let add_user = self.add_user_instruction(
+            &self.admin.pubkey(),
+            beneficiary,
+            unlocked_at_tge,
+            unlocking_period,
+            planned_tokens,
+        );
+        let (admin, _, _) = self.keypairs();
+        self.process(&[add_user], &[&admin]).await.unwrap();
+    }
+
+    fn add_user_instruction(
+        &self,
+        admin: &Pubkey,
+        beneficiary: Pubkey,
+        unlocked_at_tge: u8,
+        unlocking_period: u8,
+        planned_tokens: u64,
+    ) -> Instruction {
+        instructions::add_user(
+            admin,
+            &self.vesting_data.pubkey(),
+            &self.vesting_schedule.pubkey(),
+            &beneficiary,
+            unlocked_at_tge,
+            unlocking_period,
+            planned_tokens,
+        )
+    }
+
+    async fn remove_user(&mut self, beneficiary: Pubkey) -> Result<(), InstructionError> {
+        let remove_user = self.remove_user_instruction(&self.admin.pubkey(), beneficiary);
+        let (admin, _, _) = self.keypairs();
+        self.process(&[remove_user], &[&admin]).await
+    }
+
+    fn remove_user_instruction(&self, admin: &Pubkey, beneficiary: Pubkey) -> Instruction {
+        instructions::remove_user(
+            admin,
+            &self.vesting_data.pubkey(),
+            &self.vesting_schedule.pubkey(),
+            &beneficiary,
+            None,
+        )
+    }
+
+    fn claim_instruction(&self, beneficiary: Pubkey, destination: Pubkey) -> Instruction {
+        instructions::claim(
+            &self.admin.pubkey(),
+            &self.vesting_data.pubkey(),
+            &self.vesting_schedule.pubkey(),
+            &self.vesting_vault,
+            &beneficiary,
+            &destination,
+            None,
+            None,
+        )
+    }
+
+    async fn claim(&mut self, beneficiary: Pubkey) -> Result<(), InstructionError> {
+        let claim = self.claim_instruction(beneficiary, token_address(&beneficiary));
+        self.process(&[claim], &[]).await
+    }
+
+    async fn account_data(&mut self, address: Pubkey) -> Vec<u8> {
+        self.context
+            .banks_client
+            .get_account(address)
+            .await
+            .unwrap()
+            .unwrap()
+            .data
+    }
+
+    async fn token_amount(&mut self, owner: Pubkey) -> u64 {
+        spl_token::state::Account::unpack(&self.account_data(token_address(&owner)).await)
+            .unwrap()
+            .amount
+    }
+
+    async fn vault(&mut self) -> spl_token::state::Account {
+        spl_token::state::Account::unpack(&self.account_data(self.vesting_vault).await).unwrap()
+    }
+
+    async fn vesting_data(&mut self) -> VestingData {
+        VestingData::try_deserialize(&mut &self.account_data(self.vesting_data.pubkey()).await[..])
+            .unwrap()
+    }
+
+    async fn schedule(&mut self) -> VestingScheduleBuf {
+        accounts::decode_vesting_schedule(&self.account_data(self.vesting_schedule.pubkey()).await)
+            .unwrap()
+    }
+}
+
+// The accounts the fixture writes are the ones initialize creates
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_initialize() {
+    let mut fixture = Fixture::new().await;
+    let expected = fixture.initialized_accounts().await;
+    let rent = fixture.context.banks_client.get_rent().await.unwrap();
+    let space = instructions::vesting_schedule_space();
+    let create_schedule = solana_sdk::system_instruction::create_account(
+        &fixture.context.payer.pubkey(),
+        &fixture.vesting_schedule.pubkey(),
+        rent.minimum_balance(space),
+        space as u64,
+        &vesting_schedule::ID,
+    );
+    let initialize = fixture.initialize_instruction(TGE);
+    let (admin, vesting_data, vesting_schedule) = fixture.keypairs();
+    fixture
+        .process(
+            &[create_schedule, initialize],
+            &[&admin, &vesting_data, &vesting_schedule],
+        )
+        .await
+        .unwrap();
+
+    for (address, account) in expected {
+        let initialized = fixture
+            .context
+            .banks_client
+            .get_account(address)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            (account.owner, account.lamports, account.data),
+            (initialized.owner, initialized.lamports, initialized.data)
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_claims_follow_the_clock() {
+    let mut fixture = Fixture::new().await;
+    fixture.initialize().await;
+    let vesting_data = fixture.vesting_data().await;
+    let authority = pda::vesting_vault_authority(&fixture.admin.pubkey());
+    assert_eq!(
+        (fixture.admin.pubkey(), TGE as u64),
+        (vesting_data.admin, vesting_data.tge_timestamp)
+    );
+    assert_eq!(authority.key, vesting_data.vesting_vault_authority);
+    assert_eq!(
+        (authority.key, fixture.mint),
+        (fixture.vault().await.owner, fixture.vault().await.mint)
+    );
+
+    fixture.add_user(fixture.alice, 20, 12, ALICE_TOKENS).await;
+    fixture.add_user(fixture.bob, 100, 0, BOB_TOKENS).await;
+    assert_eq!(2, { fixture.schedule().await.borrow().len });
+
+    // before TGE a claim goes through and pays nothing
+    fixture.claim(fixture.alice).await.unwrap();
+    assert_eq!(0, fixture.token_amount(fixture.alice).await);
+
+    fixture.now = TGE + 1;
+    fixture.claim(fixture.alice).await.unwrap();
+    fixture.claim(fixture.bob).await.unwrap();
+    assert_eq!(
+        (200_000, BOB_TOKENS),
+        (
+            fixture.token_amount(fixture.alice).await,
+            fixture.token_amount(fixture.bob).await
+        )
+    );
+
+    // 20% at TGE and a quarter of the remaining 80% after three of twelve months
+    fixture.now = TGE + 3 * MONTH + 30;
+    fixture.claim(fixture.alice).await.unwrap();
+    assert_eq!(400_000, fixture.token_amount(fixture.alice).await);
+    assert_eq!(
+        400_000 + BOB_TOKENS,
+        fixture.vesting_data().await.total_issued_so_far
+    );
+    assert_eq!(600_000, fixture.vault().await.amount);
+
+    fixture.now = TGE + 13 * MONTH;
+    fixture.claim(fixture.alice).await.unwrap();
+    fixture.claim(fixture.alice).await.unwrap();
+    fixture.claim(fixture.bob).await.unwrap();
+    assert_eq!(
+        (ALICE_TOKENS, BOB_TOKENS),
+        (
+            fixture.token_amount(fixture.alice).await,
+            fixture.token_amount(fixture.bob).await
+        )
+    );
+    assert_eq!(
+        ALICE_TOKENS + BOB_TOKENS,
+        fixture.vesting_data().await.total_issued_so_far
+    );
+    assert_eq!(0, fixture.vault().await.amount);
+    let schedule = fixture.schedule().await;
+    assert_eq!(ALICE_TOKENS, { schedule.borrow().data[0].claimed_tokens });
+}
+
+#[tokio::test]
+async fn test_removed_grants_stop_vesting() {
+    let mut fixture = Fixture::new().await;
+    fixture.initialize().await;
+    fixture.add_user(fixture.alice, 20, 12, ALICE_TOKENS).await;
+    fixture.add_user(fixture.bob, 100, 0, BOB_TOKENS).await;
+
+    fixture.now = TGE + 1;
+    fixture.claim(fixture.alice).await.unwrap();
+    fixture.remove_user(fixture.alice).await.unwrap();
+    let schedule = fixture.schedule().await;
+    assert_eq!(1, { schedule.borrow().len });
+    assert_eq!(
+        EventStatus::Inactive as u8,
+        schedule.borrow().data[0].status
+    );
+    assert_eq!(Some(0), schedule.borrow().next_free_slot());
+
+    let not_found = error_code(ErrorCode::GrantNotFound);
+    fixture.now = TGE + 13 * MONTH;
+    assert_eq!(Err(not_found.clone()), fixture.claim(fixture.alice).await);
+    assert_eq!(Err(not_found), fixture.remove_user(fixture.alice).await);
+    assert_eq!(200_000, fixture.token_amount(fixture.alice).await);
+
+    // the freed slot is reused
+    fixture.add_user(fixture.alice, 0, 1, 10).await;
+    fixture.claim(fixture.alice).await.unwrap();
+    fixture.claim(fixture.bob).await.unwrap();
+    assert_eq!(
+        (200_010, BOB_TOKENS),
+        (
+            fixture.token_amount(fixture.alice).await,
+            fixture.token_amount(fixture.bob).await
+        )
+    );
+    assert_eq!(
+        200_010 + BOB_TOKENS,
+        fixture.vesting_data().await.total_issued_so_far
+    );
+}
+
+#[tokio::test]
+async fn test_claims_reject_foreign_accounts() {
+    let mut fixture = Fixture::new().await;
+    fixture.initialize().await;
+    fixture.add_user(fixture.alice, 20, 12, ALICE_TOKENS).await;
+    fixture.now = TGE + 6 * MONTH;
+    let (alice, attacker) = (fixture.alice, fixture.attacker);
+
+    // no grant of the attacker, and alice's claim cannot be paid to the attacker
+    assert_eq!(
+        Err(error_code(ErrorCode::GrantNotFound)),
+        fixture.claim(attacker).await
+    );
+    let redirected = fixture.claim_instruction(alice, token_address(&attacker));
+    let constraint_raw = error_code(anchor_lang::__private::ErrorCode::ConstraintRaw);
+    assert_eq!(
+        Err(constraint_raw),
+        fixture.process(&[redirected], &[]).await
+    );
+
+    // a schedule or vault that does not belong to vesting_data
+    let mut forged = fixture.claim_instruction(attacker, token_address(&attacker));
+    forged.accounts[1].pubkey = fixture.forged_schedule;
+    let has_one = error_code(anchor_lang::__private::ErrorCode::ConstraintHasOne);
+    assert_eq!(Err(has_one.clone()), fixture.process(&[forged], &[]).await);
+    let mut other_vault = fixture.claim_instruction(alice, token_address(&alice));
+    other_vault.accounts[5].pubkey = token_address(&attacker);
+    assert_eq!(Err(has_one), fixture.process(&[other_vault], &[]).await);
+
+    // initialize once only, the system program refuses to create vesting_data again
+    let initialize = fixture.initialize_instruction(TGE - MONTH);
+    let (admin, vesting_data, _) = fixture.keypairs();
+    let already_in_use = InstructionError::Custom(SystemError::AccountAlreadyInUse as u32);
+    assert_eq!(
+        Err(already_in_use),
+        fixture
+            .process(&[initialize], &[&admin, &vesting_data])
+            .await
+    );
+
+    assert_eq!(
+        (0, ALICE_TOKENS + BOB_TOKENS),
+        (
+            fixture.token_amount(attacker).await,
+            fixture.vault().await.amount
+        )
+    );
+    assert_eq!(0, fixture.vesting_data().await.total_issued_so_far);
+    fixture.claim(alice).await.unwrap();
+    assert_eq!(600_000, fixture.token_amount(alice).await);
+}
+
+#[tokio::test]
+async fn test_failed_payout_fails_the_claim() {
+    let mut fixture = Fixture::new().await;
+    fixture.initialize().await;
+    fixture.add_user(fixture.alice, 20, 12, ALICE_TOKENS).await;
+    fixture.now = TGE + 1;
+    let alice = fixture.alice;
+
+    // alice's token account holds another mint, the token program refuses the transfer
+    let other_mint = token_account(&Pubkey::new_unique(), &alice, 0);
+    fixture
+        .context
+        .set_account(&token_address(&alice), &AccountSharedData::from(other_mint));
+    let mint_mismatch = InstructionError::Custom(spl_token::error::TokenError::MintMismatch as u32);
+    assert_eq!(Err(mint_mismatch), fixture.claim(alice).await);
+    assert_eq!(0, fixture.vesting_data().await.total_issued_so_far);
+    assert_eq!(0, { fixture.schedule().await.borrow().data[0].claimed_tokens });
+}
+
+#[tokio::test]
+async fn test_claims_follow_the_receipt() {
+    let mut fixture = Fixture::new().await;
+    fixture.initialize().await;
+    let seller = Keypair::new();
+    let buyer = fixture.bob;
+    fixture.add_user(seller.pubkey(), 20, 12, ALICE_TOKENS).await;
+    let set_transferable = instructions::set_transferable(
+        &fixture.admin.pubkey(),
+        &fixture.vesting_data.pubkey(),
+        true,
+    );
+    let (admin, _, _) = fixture.keypairs();
+    fixture
+        .process(&[set_transferable], &[&admin])
+        .await
+        .unwrap();
+
+    // the mint create_receipt_mint makes, and a receipt token account for either side
+    let receipt_mint = Pubkey::new_unique();
+    let mut data = vec![0; spl_token::state::Mint::LEN];
+    let state = spl_token::state::Mint {
+        mint_authority: COption::Some(pda::vesting_vault_authority(&fixture.admin.pubkey()).key),
+        is_initialized: true,
+        ..Default::default()
+    };
+    spl_token::state::Mint::pack(state, &mut data).unwrap();
+    let (seller_receipt, buyer_receipt) = (Pubkey::new_unique(), Pubkey::new_unique());
+    for (address, account) in [
+        (receipt_mint, account(&spl_token::ID, data)),
+        (seller_receipt, token_account(&receipt_mint, &seller.pubkey(), 0)),
+        (buyer_receipt, token_account(&receipt_mint, &buyer, 0)),
+        (
+            token_address(&seller.pubkey()),
+            token_account(&fixture.mint, &seller.pubkey(), 0),
+        ),
+    ] {
+        fixture
+            .context
+            .set_account(&address, &AccountSharedData::from(account));
+    }
+    let mint_receipt = instructions::mint_receipt(
+        &seller.pubkey(),
+        &fixture.admin.pubkey(),
+        &fixture.vesting_data.pubkey(),
+        &fixture.vesting_schedule.pubkey(),
+        &receipt_mint,
+        &seller_receipt,
+        0,
+    );
+    fixture.process(&[mint_receipt], &[&seller]).await.unwrap();
+    // turning transfers off does not stop a receipt already minted from moving
+    let set_transferable = instructions::set_transferable(
+        &fixture.admin.pubkey(),
+        &fixture.vesting_data.pubkey(),
+        false,
+    );
+    fixture
+        .process(&[set_transferable], &[&admin])
+        .await
+        .unwrap();
+
+    // a plain token transfer, nobody told the schedule
+    let transfer = spl_token::instruction::transfer(
+        &spl_token::ID,
+        &seller_receipt,
+        &buyer_receipt,
+        &seller.pubkey(),
+        &[],
+        1,
+    )
+    .unwrap();
+    fixture.process(&[transfer], &[&seller]).await.unwrap();
+    assert_eq!(seller.pubkey(), {
+        fixture.schedule().await.borrow().data[0].user
+    });
+
+    fixture.now = TGE + 1;
+    let not_held = error_code(ErrorCode::ReceiptNotHeld);
+    assert_eq!(Err(not_held.clone()), fixture.claim(seller.pubkey()).await);
+    let mut claim = fixture.claim_instruction(seller.pubkey(), token_address(&seller.pubkey()));
+    claim.accounts[9].pubkey = seller_receipt;
+    assert_eq!(Err(not_held), fixture.process(&[claim], &[]).await);
+
+    let mut claim = fixture.claim_instruction(buyer, token_address(&buyer));
+    claim.accounts[9].pubkey = buyer_receipt;
+    fixture.process(&[claim], &[]).await.unwrap();
+    assert_eq!(200_000, fixture.token_amount(buyer).await);
+    assert_eq!(0, fixture.token_amount(seller.pubkey()).await);
+    assert_eq!(buyer, { fixture.schedule().await.borrow().data[0].user });
+}
+
+// Accounts as the first deployed program left them, set up by the fixture's admin
+fn v1_accounts(fixture: &Fixture) -> (Account, Account) {
+    let authority = pda::vesting_vault_authority(&fixture.admin.pubkey());
+    let mut vesting_data = include_bytes!("fixtures/vesting_data_v1.bin").to_vec();
+    vesting_data[48..80].copy_from_slice(authority.key.as_ref());
+    vesting_data[80] = authority.seed;
+    let schedule = include_bytes!("fixtures/vesting_schedule_v1.bin").to_vec();
+    (
+        account(&vesting_schedule::ID, vesting_data),
+        account(&vesting_schedule::ID, schedule),
+    )
+}
+
+#[tokio::test]
+async fn test_migrate_checks_v1_accounts() {
+    let mut fixture = Fixture::new().await;
+    fixture.initialize().await;
+    let (v1_data, v1_schedule) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (data_account, schedule_account) = v1_accounts(&fixture);
+    for (address, account) in [(v1_data, data_account), (v1_schedule, schedule_account)] {
+        fixture
+            .context
+            .set_account(&address, &AccountSharedData::from(account));
+    }
+    let invalid = error_code(ErrorCode::InvalidInput);
+
+    // v1 recorded no admin, whoever signs has to be the one the vault authority was derived from
+    let stranger = Keypair::new();
+    let migrate = instructions::migrate(&stranger.pubkey(), &v1_data, &v1_schedule, &[]);
+    assert_eq!(
+        Err(invalid.clone()),
+        fixture.process(&[migrate], &[&stranger]).await
+    );
+
+    // a schedule in the current layout is linked to its own vesting data already
+    let (admin, _, _) = fixture.keypairs();
+    let schedule = fixture.vesting_schedule.pubkey();
+    let migrate = instructions::migrate(&admin.pubkey(), &v1_data, &schedule, &[]);
+    assert_eq!(Err(invalid), fixture.process(&[migrate], &[&admin]).await);
+    assert_eq!(
+        include_bytes!("fixtures/vesting_schedule_v1.bin").len(),
+        fixture.account_data(v1_schedule).await.len()
+    );
+}
+
+// Growing the accounts needs the BPF runtime, native program-test cannot resize account data
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_migrate_from_v1() {
+    let mut fixture = Fixture::new().await;
+    let (data_account, schedule_account) = v1_accounts(&fixture);
+    let (v1_data, v1_schedule) = (fixture.vesting_data.pubkey(), fixture.vesting_schedule.pubkey());
+    for (address, account) in [(v1_data, data_account), (v1_schedule, schedule_account)] {
+        fixture
+            .context
+            .set_account(&address, &AccountSharedData::from(account));
+    }
+    let (admin, _, _) = fixture.keypairs();
+    let migrate = instructions::migrate(&admin.pubkey(), &v1_data, &v1_schedule, &[]);
+    fixture.process(&[migrate], &[&admin]).await.unwrap();
+
+    let vesting_data = fixture.vesting_data().await;
+    assert_eq!(
+        (admin.pubkey(), v1_schedule, 450_000),
+        (
+            vesting_data.admin,
+            vesting_data.vesting_schedule,
+            vesting_data.total_issued_so_far
+        )
+    );
+    let schedule = fixture.schedule().await;
+    let schedule = schedule.borrow();
+    assert_eq!((3, 300), ({ schedule.len }, { schedule.capacity }));
+    assert_eq!(Some(1), schedule.next_free_slot());
+    assert_eq!(750_000, { schedule.data[2].planned_tokens });
+
+    // nothing left to do the second time, and has_one = admin now holds for the admin's instructions
+    let migrate = instructions::migrate(&admin.pubkey(), &v1_data, &v1_schedule, &[]);
+    fixture.process(&[migrate], &[&admin]).await.unwrap();
+    let set_transferable = instructions::set_transferable(&admin.pubkey(), &v1_data, true);
+    fixture
+        .process(&[set_transferable], &[&admin])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_only_the_admin_adds_and_removes_grants() {
+    let mut fixture = Fixture::new().await;
+    fixture.initialize().await;
+    fixture.add_user(fixture.alice, 20, 12, ALICE_TOKENS).await;
+    let attacker = Keypair::new();
+    let has_one = error_code(anchor_lang::__private::ErrorCode::ConstraintHasOne);
+
+    // signed, but not by the admin of vesting_data
+    let add_user =
+        fixture.add_user_instruction(&attacker.pubkey(), attacker.pubkey(), 100, 0, ALICE_TOKENS);
+    assert_eq!(
+        Err(has_one.clone()),
+        fixture.process(&[add_user], &[&attacker]).await
+    );
+    let remove_user = fixture.remove_user_instruction(&attacker.pubkey(), fixture.alice);
+    assert_eq!(
+        Err(has_one.clone()),
+        fixture.process(&[remove_user], &[&attacker]).await
+    );
+
+    // the admin's key without the admin's signature
+    let mut add_user =
+        fixture.add_user_instruction(&fixture.admin.pubkey(), attacker.pubkey(), 100, 0, 1);
+    add_user.accounts[0].is_signer = false;
+    let constraint_signer = error_code(anchor_lang::__private::ErrorCode::ConstraintSigner);
+    assert_eq!(
+        Err(constraint_signer),
+        fixture.process(&[add_user], &[]).await
+    );
+
+    // the admin's signature on a schedule vesting_data does not point to
+    let (admin, _, _) = fixture.keypairs();
+    let mut add_user =
+        fixture.add_user_instruction(&admin.pubkey(), attacker.pubkey(), 100, 0, 1);
+    add_user.accounts[2].pubkey = fixture.forged_schedule;
+    assert_eq!(Err(has_one), fixture.process(&[add_user], &[&admin]).await);
+
+    let schedule = fixture.schedule().await;
+    assert_eq!(1, { schedule.borrow().len });
+    assert_eq!(fixture.alice, schedule.borrow().data[0].user);
+    fixture.remove_user(fixture.alice).await.unwrap();
+}
+
+// Growing the schedule needs the BPF runtime, native program-test cannot resize account data
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_grow_full_schedule() {
+    use vesting_schedule::utils::layout::{schedule_size, GROWTH_CHUNK};
+
+    let mut fixture = Fixture::new().await;
+    fixture.initialize().await;
+    let (admin, _, _) = fixture.keypairs();
+    let others: Vec<Instruction> = (0..INITIAL_CAPACITY)
+        .map(|_| fixture.add_user_instruction(&admin.pubkey(), Pubkey::new_unique(), 20, 12, 1))
+        .collect();
+    for batch in others.chunks(12) {
+        fixture.process(batch, &[&admin]).await.unwrap();
+    }
+    let alice = fixture.alice;
+    let add_alice = fixture.add_user_instruction(&admin.pubkey(), alice, 20, 12, ALICE_TOKENS);
+    assert_eq!(
+        Err(error_code(ErrorCode::VestingUserDataFull)),
+        fixture.process(std::slice::from_ref(&add_alice), &[&admin]).await
+    );
+
+    // the schedule was created rent exempt and no more, so the admin pays for the new grants
+    let rent = fixture.context.banks_client.get_rent().await.unwrap();
+    let schedule_key = fixture.vesting_schedule.pubkey();
+    let lamports = |account: Option<Account>| account.unwrap().lamports;
+    let banks_client = &mut fixture.context.banks_client;
+    let admin_before = lamports(banks_client.get_account(admin.pubkey()).await.unwrap());
+    assert_eq!(
+        rent.minimum_balance(schedule_size(INITIAL_CAPACITY)),
+        lamports(banks_client.get_account(schedule_key).await.unwrap())
+    );
+    let grow = instructions::grow_schedule(
+        &admin.pubkey(),
+        &fixture.vesting_data.pubkey(),
+        &schedule_key,
+        GROWTH_CHUNK,
+    );
+    assert_eq!(1, grow.len());
+    fixture.process(&grow, &[&admin]).await.unwrap();
+
+    let grown_size = schedule_size(INITIAL_CAPACITY + GROWTH_CHUNK);
+    let schedule_account = fixture
+        .context
+        .banks_client
+        .get_account(schedule_key)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        (grown_size, rent.minimum_balance(grown_size)),
+        (schedule_account.data.len(), schedule_account.lamports)
+    );
+    let top_up = rent.minimum_balance(grown_size) - rent.minimum_balance(schedule_size(INITIAL_CAPACITY));
+    let banks_client = &mut fixture.context.banks_client;
+    assert_eq!(
+        admin_before - top_up,
+        lamports(banks_client.get_account(admin.pubkey()).await.unwrap())
+    );
+
+    // the first of the new slots takes the grant, and it vests like any other
+    fixture.process(&[add_alice], &[&admin]).await.unwrap();
+    let schedule = fixture.schedule().await;
+    assert_eq!(
+        (INITIAL_CAPACITY + 1, INITIAL_CAPACITY + GROWTH_CHUNK),
+        ({ schedule.borrow().len }, { schedule.borrow().capacity })
+    );
+    assert_eq!(alice, schedule.borrow().data[INITIAL_CAPACITY as usize].user);
+    fixture.now = TGE + 1;
+    fixture.claim(alice).await.unwrap();
+    assert_eq!(200_000, fixture.token_amount(alice).await);
+}
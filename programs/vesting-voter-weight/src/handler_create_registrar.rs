@@ -0,0 +1,12 @@
+use crate::CreateRegistrar;
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<CreateRegistrar>, lockup_saturation_seconds: u64) -> ProgramResult {
+    let registrar = &mut ctx.accounts.registrar;
+    registrar.realm = ctx.accounts.realm.key();
+    registrar.governing_token_mint = ctx.accounts.governing_token_mint.key();
+    registrar.vesting_data = ctx.accounts.vesting_data.key();
+    registrar.lockup_saturation_seconds = lockup_saturation_seconds;
+
+    Ok(())
+}
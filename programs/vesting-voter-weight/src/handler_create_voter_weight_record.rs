@@ -0,0 +1,13 @@
+use crate::CreateVoterWeightRecord;
+use anchor_lang::prelude::*;
+
+pub fn process(ctx: Context<CreateVoterWeightRecord>, governing_token_owner: Pubkey) -> ProgramResult {
+    let record = &mut ctx.accounts.voter_weight_record;
+    record.realm = ctx.accounts.registrar.realm;
+    record.governing_token_mint = ctx.accounts.registrar.governing_token_mint;
+    record.governing_token_owner = governing_token_owner;
+    // no weight until the first update
+    record.voter_weight_expiry = Some(0);
+
+    Ok(())
+}
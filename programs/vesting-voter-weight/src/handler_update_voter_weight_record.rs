@@ -0,0 +1,24 @@
+use crate::{voter_weight, ErrorCode, UpdateVoterWeightRecord};
+use anchor_lang::prelude::*;
+use vesting_schedule::VestingScheduleRef;
+
+pub fn process(ctx: Context<UpdateVoterWeightRecord>) -> ProgramResult {
+    let data = ctx.accounts.vesting_schedule.try_borrow_data()?;
+    let schedule = VestingScheduleRef::load(&data)?;
+    let record = &mut ctx.accounts.voter_weight_record;
+    let clock = Clock::get()?;
+
+    record.voter_weight = voter_weight::owner_voter_weight(
+        schedule.data,
+        &record.governing_token_owner,
+        &ctx.accounts.vesting_data,
+        ctx.accounts.registrar.lockup_saturation_seconds,
+        clock.unix_timestamp as u64,
+    )?
+    .ok_or(ErrorCode::NoActiveGrant)?;
+    record.voter_weight_expiry = Some(clock.slot);
+    record.weight_action = None;
+    record.weight_action_target = None;
+
+    Ok(())
+}
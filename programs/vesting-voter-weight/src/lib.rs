@@ -0,0 +1,146 @@
+use anchor_lang::prelude::*;
+pub mod handler_create_registrar;
+pub mod handler_create_voter_weight_record;
+pub mod handler_update_voter_weight_record;
+pub mod voter_weight;
+use vesting_schedule::VestingData;
+declare_id!("7QorpFRCfPPbriRo5WMiNQAdGKB2s5ULnFbSGSrLx72G");
+
+pub const VOTER_WEIGHT_RECORD_TAG: &str = "voter-weight-record";
+
+// Address of the voter weight record of `governing_token_owner` under the registrar
+pub fn voter_weight_record_address(registrar: &Pubkey, governing_token_owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[VOTER_WEIGHT_RECORD_TAG.as_ref(), registrar.as_ref(), governing_token_owner.as_ref()],
+        &ID,
+    )
+}
+
+// SPL Governance voter weight add-in: the voter weight of a beneficiary is the locked
+// balance of their grants in one vesting schedule, optionally scaled by the remaining lockup.
+// Registrars are created by the client and passed in zeroed, like the vesting schedule.
+// Voter weight records are PDAs of the registrar and the governing token owner.
+#[program]
+pub mod vesting_voter_weight {
+    use super::*;
+
+    // lockup_saturation_seconds 0 counts locked tokens one to one, otherwise a grant
+    // counts fully only if it stays locked at least that long
+    pub fn create_registrar(ctx: Context<CreateRegistrar>, lockup_saturation_seconds: u64) -> ProgramResult {
+        handler_create_registrar::process(ctx, lockup_saturation_seconds)
+    }
+
+    pub fn create_voter_weight_record(
+        ctx: Context<CreateVoterWeightRecord>,
+        governing_token_owner: Pubkey,
+    ) -> ProgramResult {
+        handler_create_voter_weight_record::process(ctx, governing_token_owner)
+    }
+
+    // Meant to run right before the governance instruction in the same transaction,
+    // the weight expires at the current slot
+    pub fn update_voter_weight_record(ctx: Context<UpdateVoterWeightRecord>) -> ProgramResult {
+        handler_update_voter_weight_record::process(ctx)
+    }
+}
+
+#[derive(Accounts)]
+pub struct CreateRegistrar<'info> {
+    #[account(zero)]
+    pub registrar: Account<'info, Registrar>,
+    pub realm: AccountInfo<'info>,
+    pub governing_token_mint: AccountInfo<'info>,
+    // only the schedule admin decides where its grants vote
+    #[account(signer)]
+    pub admin: AccountInfo<'info>,
+    #[account(has_one = admin)]
+    pub vesting_data: Account<'info, VestingData>,
+}
+
+#[derive(Accounts)]
+#[instruction(governing_token_owner: Pubkey)]
+pub struct CreateVoterWeightRecord<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = VoterWeightRecord::SPACE,
+        seeds = [
+            VOTER_WEIGHT_RECORD_TAG.as_ref(),
+            registrar.key().as_ref(),
+            governing_token_owner.as_ref()
+        ],
+        bump
+    )]
+    pub voter_weight_record: Account<'info, VoterWeightRecord>,
+    pub registrar: Account<'info, Registrar>,
+    #[account(signer, mut)]
+    pub payer: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateVoterWeightRecord<'info> {
+    #[account(has_one = vesting_data)]
+    pub registrar: Account<'info, Registrar>,
+    #[account(
+        mut,
+        seeds = [
+            VOTER_WEIGHT_RECORD_TAG.as_ref(),
+            registrar.key().as_ref(),
+            voter_weight_record.governing_token_owner.as_ref()
+        ],
+        bump,
+        constraint = voter_weight_record.realm == registrar.realm,
+        constraint = voter_weight_record.governing_token_mint == registrar.governing_token_mint
+    )]
+    pub voter_weight_record: Account<'info, VoterWeightRecord>,
+    #[account(has_one = vesting_schedule)]
+    pub vesting_data: Account<'info, VestingData>,
+    #[account(owner = vesting_schedule::ID)]
+    pub vesting_schedule: AccountInfo<'info>,
+}
+
+#[error]
+#[derive(PartialEq, Eq)]
+pub enum ErrorCode {
+    #[msg("Governing token owner has no active grant in the schedule")]
+    NoActiveGrant,
+}
+
+#[account]
+#[derive(Debug, Default)]
+pub struct Registrar {
+    pub realm: Pubkey,
+    pub governing_token_mint: Pubkey,
+    pub vesting_data: Pubkey,
+    pub lockup_saturation_seconds: u64,
+}
+
+// Layout of spl-governance-addin-api's VoterWeightRecord, Anchor's discriminator for this
+// name is the one the interface expects
+#[account]
+#[derive(Debug, Default)]
+pub struct VoterWeightRecord {
+    pub realm: Pubkey,
+    pub governing_token_mint: Pubkey,
+    pub governing_token_owner: Pubkey,
+    pub voter_weight: u64,
+    pub voter_weight_expiry: Option<u64>,
+    pub weight_action: Option<VoterWeightAction>,
+    pub weight_action_target: Option<Pubkey>,
+    pub reserved: [u8; 8],
+}
+
+impl VoterWeightRecord {
+    // with every Option set
+    pub const SPACE: usize = 8 + 3 * 32 + 8 + (1 + 8) + (1 + 1) + (1 + 32) + 8;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum VoterWeightAction {
+    CastVote,
+    CommentProposal,
+    CreateGovernance,
+    CreateProposal,
+    SignOffProposal,
+}
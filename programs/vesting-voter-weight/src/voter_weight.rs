@@ -0,0 +1,94 @@
+use anchor_lang::prelude::*;
+use vesting_schedule::utils::utils::EventStatus;
+use vesting_schedule::{vesting_operations, VestingData, VestingUser};
+
+// Locked tokens of the grant, scaled down linearly when it unlocks completely within
+// lockup_saturation_seconds
+pub fn voter_weight(
+    grant: &VestingUser,
+    vesting_data: &VestingData,
+    lockup_saturation_seconds: u64,
+    timestamp: u64,
+) -> std::result::Result<u64, ProgramError> {
+    let locked = vesting_operations::locked_balance(grant, vesting_data, timestamp)?.locked;
+    if lockup_saturation_seconds == 0 {
+        return Ok(locked);
+    }
+    let remaining = vesting_operations::remaining_lockup(grant, vesting_data, timestamp);
+    let scaled = locked as u128 * u64::min(remaining, lockup_saturation_seconds) as u128
+        / lockup_saturation_seconds as u128;
+    Ok(scaled as u64)
+}
+
+// Voter weight of every active grant of the owner added up, None if they have none
+pub fn owner_voter_weight(
+    grants: &[VestingUser],
+    governing_token_owner: &Pubkey,
+    vesting_data: &VestingData,
+    lockup_saturation_seconds: u64,
+    timestamp: u64,
+) -> std::result::Result<Option<u64>, ProgramError> {
+    let mut total = None;
+    for grant in grants
+        .iter()
+        .filter(|grant| grant.status == EventStatus::PendingToken as u8 && grant.user == *governing_token_owner)
+    {
+        let weight = voter_weight(grant, vesting_data, lockup_saturation_seconds, timestamp)?;
+        total = Some(total.unwrap_or(0u64).saturating_add(weight));
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VoterWeightRecord;
+    use anchor_lang::Discriminator;
+
+    const MONTH: u64 = 732 * 60 * 60;
+
+    #[test]
+    fn test_discriminator_matches_addin_api() {
+        // spl_governance_addin_api::voter_weight::VoterWeightRecord::ACCOUNT_DISCRIMINATOR
+        assert_eq!([46, 249, 155, 75, 153, 248, 116, 9], VoterWeightRecord::discriminator());
+    }
+
+    #[test]
+    fn test_voter_weight() {
+        let vesting_data = VestingData { tge_timestamp: MONTH, ..Default::default() };
+        let grant = VestingUser::new(20, Pubkey::new_unique(), 12, 1_000_000);
+
+        // 4 of 12 months passed, 533_334 locked for another 8 months
+        let now = 5 * MONTH;
+        assert_eq!(533_334, voter_weight(&grant, &vesting_data, 0, now).unwrap());
+        assert_eq!(533_334, voter_weight(&grant, &vesting_data, 6 * MONTH, now).unwrap());
+        assert_eq!(266_667, voter_weight(&grant, &vesting_data, 16 * MONTH, now).unwrap());
+        assert_eq!(0, voter_weight(&grant, &vesting_data, 6 * MONTH, 13 * MONTH).unwrap());
+
+        // before TGE everything is locked for the whole period plus the wait for TGE
+        assert_eq!(1_000_000, voter_weight(&grant, &vesting_data, 13 * MONTH, 0).unwrap());
+    }
+
+    #[test]
+    fn test_owner_voter_weight() {
+        let vesting_data = VestingData { tge_timestamp: MONTH, ..Default::default() };
+        let owner = Pubkey::new_unique();
+        let mut removed = VestingUser::new(20, owner, 12, 1_000_000);
+        removed.status = EventStatus::Inactive as u8;
+        let mut expired = VestingUser::new(20, owner, 12, 1_000_000);
+        expired.status = EventStatus::Expired as u8;
+        let grants = [
+            VestingUser::new(20, owner, 12, 1_000_000),
+            VestingUser::new(20, Pubkey::new_unique(), 12, 1_000_000),
+            removed,
+            VestingUser::new(0, owner, 6, 600_000),
+            expired,
+        ];
+
+        // 533_334 and 200_000 locked 4 months in, the removed and expired grants count for nothing
+        let now = 5 * MONTH;
+        assert_eq!(Some(733_334), owner_voter_weight(&grants, &owner, &vesting_data, 0, now).unwrap());
+        assert_eq!(Some(0), owner_voter_weight(&grants, &owner, &vesting_data, 0, 13 * MONTH).unwrap());
+        assert_eq!(None, owner_voter_weight(&grants[1..3], &owner, &vesting_data, 0, now).unwrap());
+    }
+}
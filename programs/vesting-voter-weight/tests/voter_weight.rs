@@ -0,0 +1,280 @@
+// The add-in under program-test, with a vesting schedule put into genesis
+use anchor_lang::{AccountDeserialize, AccountSerialize, Discriminator, InstructionData, ToAccountMetas};
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::account::Account;
+use solana_sdk::clock::Clock;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::system_program;
+use solana_sdk::transaction::Transaction;
+use vesting_schedule::{VestingData, VestingScheduleBuf, VestingUser};
+use vesting_voter_weight::{Registrar, VoterWeightRecord};
+
+const MONTH: i64 = 732 * 60 * 60;
+const TGE: i64 = 1_629_158_400;
+
+struct Fixture {
+    context: ProgramTestContext,
+    admin: Keypair,
+    voter: Pubkey,
+    vesting_data: Pubkey,
+    vesting_schedule: Pubkey,
+    realm: Pubkey,
+    governing_token_mint: Pubkey,
+}
+
+fn vesting_account(data: Vec<u8>) -> Account {
+    Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: vesting_schedule::ID,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+impl Fixture {
+    async fn new() -> Self {
+        let mut program_test = ProgramTest::new(
+            "vesting_voter_weight",
+            vesting_voter_weight::ID,
+            processor!(vesting_voter_weight::entry),
+        );
+        let admin = Keypair::new();
+        let voter = Pubkey::new_unique();
+        let vesting_data = Pubkey::new_unique();
+        let vesting_schedule = Pubkey::new_unique();
+
+        let mut data = Vec::new();
+        VestingData {
+            admin: admin.pubkey(),
+            vesting_schedule,
+            tge_timestamp: TGE as u64,
+            ..Default::default()
+        }
+        .try_serialize(&mut data)
+        .unwrap();
+        program_test.add_account(vesting_data, vesting_account(data));
+
+        // the voter holds the grants in slots 0 and 2
+        let mut schedule = VestingScheduleBuf::new(300);
+        schedule.borrow_mut().len = 3;
+        schedule.borrow_mut().data[0] = VestingUser::new(20, voter, 12, 1_000_000);
+        schedule.borrow_mut().data[1] = VestingUser::new(20, Pubkey::new_unique(), 12, 1_000_000);
+        schedule.borrow_mut().data[2] = VestingUser::new(0, voter, 6, 600_000);
+        program_test.add_account(vesting_schedule, vesting_account(schedule.account_data().to_vec()));
+
+        Fixture {
+            context: program_test.start_with_context().await,
+            admin,
+            voter,
+            vesting_data,
+            vesting_schedule,
+            realm: Pubkey::new_unique(),
+            governing_token_mint: Pubkey::new_unique(),
+        }
+    }
+
+    // four months after TGE, with slack for the clock moving on between slots
+    async fn process(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> Result<(), String> {
+        let mut clock: Clock = self.context.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp = TGE + 4 * MONTH + 30;
+        self.context.set_sysvar(&clock);
+
+        let mut all_signers = vec![&self.context.payer];
+        all_signers.extend_from_slice(signers);
+        let recent_blockhash = self.context.banks_client.get_latest_blockhash().await.unwrap();
+        let transaction =
+            Transaction::new_signed_with_payer(instructions, Some(&self.context.payer.pubkey()), &all_signers, recent_blockhash);
+        self.context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .map_err(|err| err.to_string())
+    }
+
+    async fn create_account(&mut self, account: &Keypair, space: usize) -> Instruction {
+        let rent = self.context.banks_client.get_rent().await.unwrap();
+        system_instruction::create_account(
+            &self.context.payer.pubkey(),
+            &account.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &vesting_voter_weight::ID,
+        )
+    }
+
+    async fn create_registrar(&mut self, admin: &Keypair, lockup_saturation_seconds: u64) -> Result<Pubkey, String> {
+        let registrar = Keypair::new();
+        let instructions = vec![
+            self.create_account(&registrar, 8 + 3 * 32 + 8).await,
+            Instruction {
+                program_id: vesting_voter_weight::ID,
+                accounts: vesting_voter_weight::accounts::CreateRegistrar {
+                    registrar: registrar.pubkey(),
+                    realm: self.realm,
+                    governing_token_mint: self.governing_token_mint,
+                    admin: admin.pubkey(),
+                    vesting_data: self.vesting_data,
+                }
+                .to_account_metas(None),
+                data: vesting_voter_weight::instruction::CreateRegistrar { lockup_saturation_seconds }.data(),
+            },
+        ];
+        self.process(&instructions, &[&registrar, admin]).await?;
+        Ok(registrar.pubkey())
+    }
+
+    // Native processors cannot create accounts through a CPI under program-test, so without
+    // the BPF build the record goes in place the way create_voter_weight_record leaves it
+    async fn create_voter_weight_record(&mut self, registrar: &Pubkey, governing_token_owner: Pubkey) -> Pubkey {
+        let (record, _) = vesting_voter_weight::voter_weight_record_address(registrar, &governing_token_owner);
+        if cfg!(feature = "test-bpf") {
+            let instruction = self.create_voter_weight_record_instruction(registrar, governing_token_owner);
+            self.process(&[instruction], &[]).await.unwrap();
+            return record;
+        }
+        let mut data = Vec::new();
+        VoterWeightRecord {
+            realm: self.realm,
+            governing_token_mint: self.governing_token_mint,
+            governing_token_owner,
+            voter_weight_expiry: Some(0),
+            ..Default::default()
+        }
+        .try_serialize(&mut data)
+        .unwrap();
+        data.resize(VoterWeightRecord::SPACE, 0);
+        let rent = self.context.banks_client.get_rent().await.unwrap();
+        let account = Account {
+            lamports: rent.minimum_balance(data.len()),
+            data,
+            owner: vesting_voter_weight::ID,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.context.set_account(&record, &account.into());
+        record
+    }
+
+    fn create_voter_weight_record_instruction(&self, registrar: &Pubkey, governing_token_owner: Pubkey) -> Instruction {
+        let (record, _) = vesting_voter_weight::voter_weight_record_address(registrar, &governing_token_owner);
+        Instruction {
+            program_id: vesting_voter_weight::ID,
+            accounts: vesting_voter_weight::accounts::CreateVoterWeightRecord {
+                voter_weight_record: record,
+                registrar: *registrar,
+                payer: self.context.payer.pubkey(),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: vesting_voter_weight::instruction::CreateVoterWeightRecord { governing_token_owner }.data(),
+        }
+    }
+
+    fn update_voter_weight_record(&self, registrar: &Pubkey, record: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: vesting_voter_weight::ID,
+            accounts: vesting_voter_weight::accounts::UpdateVoterWeightRecord {
+                registrar: *registrar,
+                voter_weight_record: *record,
+                vesting_data: self.vesting_data,
+                vesting_schedule: self.vesting_schedule,
+            }
+            .to_account_metas(None),
+            data: vesting_voter_weight::instruction::UpdateVoterWeightRecord {}.data(),
+        }
+    }
+
+    async fn account_data(&mut self, address: Pubkey) -> Vec<u8> {
+        self.context.banks_client.get_account(address).await.unwrap().unwrap().data
+    }
+}
+
+#[tokio::test]
+async fn test_voter_weight_record() {
+    let mut fixture = Fixture::new().await;
+    let admin = Keypair::from_bytes(&fixture.admin.to_bytes()).unwrap();
+    let registrar = fixture.create_registrar(&admin, 0).await.unwrap();
+    let decoded = Registrar::try_deserialize(&mut &fixture.account_data(registrar).await[..]).unwrap();
+    assert_eq!((fixture.realm, fixture.vesting_data), (decoded.realm, decoded.vesting_data));
+
+    let record = fixture.create_voter_weight_record(&registrar, fixture.voter).await;
+    let data = fixture.account_data(record).await;
+    assert_eq!(Some(0), VoterWeightRecord::try_deserialize(&mut &data[..]).unwrap().voter_weight_expiry);
+
+    let update = fixture.update_voter_weight_record(&registrar, &record);
+    fixture.process(&[update], &[]).await.unwrap();
+    let clock: Clock = fixture.context.banks_client.get_sysvar().await.unwrap();
+    let data = fixture.account_data(record).await;
+    // the offsets spl-governance reads. Of the first grant 200_000 vested at TGE and 4 of 12 months
+    // of the other 800_000 since, of the second 4 of 6 months of 600_000.
+    assert_eq!(VoterWeightRecord::discriminator(), data[..8]);
+    assert_eq!(fixture.realm.as_ref(), &data[8..40]);
+    assert_eq!(fixture.governing_token_mint.as_ref(), &data[40..72]);
+    assert_eq!(fixture.voter.as_ref(), &data[72..104]);
+    assert_eq!((533_334u64 + 200_000).to_le_bytes(), data[104..112]);
+    let decoded = VoterWeightRecord::try_deserialize(&mut &data[..]).unwrap();
+    assert_eq!(Some(clock.slot), decoded.voter_weight_expiry);
+    assert_eq!(None, decoded.weight_action);
+}
+
+#[tokio::test]
+async fn test_lockup_scaled_voter_weight() {
+    let mut fixture = Fixture::new().await;
+    let admin = Keypair::from_bytes(&fixture.admin.to_bytes()).unwrap();
+    // 30 seconds short of 8 and 2 months of lockup left against a 16 month saturation,
+    // just under a half and an eighth
+    let registrar = fixture.create_registrar(&admin, 16 * MONTH as u64).await.unwrap();
+    let record = fixture.create_voter_weight_record(&registrar, fixture.voter).await;
+
+    let update = fixture.update_voter_weight_record(&registrar, &record);
+    fixture.process(&[update], &[]).await.unwrap();
+    let decoded = VoterWeightRecord::try_deserialize(&mut &fixture.account_data(record).await[..]).unwrap();
+    assert_eq!(266_666 + 24_999, decoded.voter_weight);
+}
+
+#[tokio::test]
+async fn test_voter_weight_checks() {
+    let mut fixture = Fixture::new().await;
+    assert!(fixture.create_registrar(&Keypair::new(), 0).await.is_err());
+
+    let admin = Keypair::from_bytes(&fixture.admin.to_bytes()).unwrap();
+    let registrar = fixture.create_registrar(&admin, 0).await.unwrap();
+    let record = fixture.create_voter_weight_record(&registrar, fixture.voter).await;
+    // someone without a grant in the schedule gets no weight
+    let stranger = fixture.create_voter_weight_record(&registrar, Pubkey::new_unique()).await;
+    let update = fixture.update_voter_weight_record(&registrar, &stranger);
+    assert!(fixture.process(&[update], &[]).await.is_err());
+    // a copy of the record anywhere but its address does not count
+    let copy = Pubkey::new_unique();
+    let account = fixture.context.banks_client.get_account(record).await.unwrap().unwrap();
+    fixture.context.set_account(&copy, &account.into());
+    let update = fixture.update_voter_weight_record(&registrar, &copy);
+    assert!(fixture.process(&[update], &[]).await.is_err());
+    // nor does the record match a registrar of another realm
+    fixture.realm = Pubkey::new_unique();
+    let other_registrar = fixture.create_registrar(&admin, 0).await.unwrap();
+    let update = fixture.update_voter_weight_record(&other_registrar, &record);
+    assert!(fixture.process(&[update], &[]).await.is_err());
+    let update = fixture.update_voter_weight_record(&registrar, &record);
+    fixture.process(&[update], &[]).await.unwrap();
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_create_voter_weight_record() {
+    let mut fixture = Fixture::new().await;
+    let admin = Keypair::from_bytes(&fixture.admin.to_bytes()).unwrap();
+    let registrar = fixture.create_registrar(&admin, 0).await.unwrap();
+    let record = fixture.create_voter_weight_record(&registrar, fixture.voter).await;
+    let decoded = VoterWeightRecord::try_deserialize(&mut &fixture.account_data(record).await[..]).unwrap();
+    assert_eq!((fixture.realm, fixture.voter), (decoded.realm, decoded.governing_token_owner));
+    assert_eq!(VoterWeightRecord::SPACE, fixture.account_data(record).await.len());
+
+    // one record per owner and registrar
+    let instruction = fixture.create_voter_weight_record_instruction(&registrar, fixture.voter);
+    assert!(fixture.process(&[instruction], &[]).await.is_err());
+}
@@ -74,6 +74,7 @@ describe('VestingSchedule', () => {
       12,
       1_000_000_000,
       vesting_schedule,
+      vesting_data,
     );
 
     await addUser(
@@ -82,6 +83,7 @@ describe('VestingSchedule', () => {
       8,
       1_000_000_000,
       vesting_schedule,
+      vesting_data,
     );
 
     const get_active_users = await program.account.vestingSchedule.fetch(vesting_schedule);
@@ -160,6 +162,7 @@ describe('VestingSchedule', () => {
       12,
       1_000_000_000,
       vesting_schedule,
+      vesting_data,
     );
 
     await addUser(
@@ -168,23 +171,24 @@ describe('VestingSchedule', () => {
       8,
       1_000_000_000,
       vesting_schedule,
+      vesting_data,
     );
 
-    await removeUser(0, vesting_schedule);
+    await removeUser(user1.publicKey, vesting_schedule, vesting_data);
     let get_active_users = await program.account.vestingSchedule.fetch(vesting_schedule);
     assert.ok(get_active_users.len, 1);
 
     const user_ata1 = await getUserAta(user1.secretKey, provider, mint_hbb);
     const user_ata2 = await getUserAta(user2.secretKey, provider, mint_hbb);
 
-    await claim(
+    await assert.rejects(claim(
       0,
       provider.wallet.publicKey,
       vesting_schedule,
       vesting_data,
       user1.publicKey,
       user_ata1,
-    );
+    ));
 
     await claim(
       1,
@@ -215,6 +219,7 @@ describe('VestingSchedule', () => {
       12,
       1_000_000_000,
       vesting_schedule,
+      vesting_data,
     );
 
     get_active_users = await program.account.vestingSchedule.fetch(vesting_schedule);
@@ -286,6 +291,7 @@ describe('VestingSchedule', () => {
       12,
       1_000_000_000,
       vesting_schedule,
+      vesting_data,
     );
 
     await addUser(
@@ -294,6 +300,7 @@ describe('VestingSchedule', () => {
       8,
       1_000_000_000,
       vesting_schedule,
+      vesting_data,
     );
 
     const user_ata1 = await getUserAta(user1.secretKey, provider, mint_hbb);
@@ -397,6 +404,7 @@ describe('VestingSchedule', () => {
       12,
       1_000_000_000,
       vesting_schedule,
+      vesting_data,
     );
 
     await addUser(
@@ -405,6 +413,7 @@ describe('VestingSchedule', () => {
       8,
       1_000_000_000,
       vesting_schedule,
+      vesting_data,
     );
 
     const user_ata1 = await getUserAta(user1.secretKey, provider, mint_hbb);
@@ -491,6 +500,7 @@ describe('VestingSchedule', () => {
       12,
       1_000_000_000,
       vesting_schedule,
+      vesting_data,
     );
 
     await addUser(
@@ -499,6 +509,7 @@ describe('VestingSchedule', () => {
       8,
       1_000_000_000,
       vesting_schedule,
+      vesting_data,
     );
 
     const user_ata1 = await getUserAta(user1.secretKey, provider, mint_hbb);
@@ -588,6 +599,7 @@ describe('VestingSchedule', () => {
       0,
       1_000_000_000,
       vesting_schedule,
+      vesting_data,
     );
 
     const user_ata1 = await getUserAta(user1.secretKey, provider, mint_hbb);
@@ -626,17 +638,18 @@ describe('VestingSchedule', () => {
       0,
       1_000_000_000,
       vesting_schedule,
+      vesting_data,
     );
 
-    await removeUser(1, vesting_schedule);
-    await claim(
-      1,
+    await removeUser(user2.publicKey, vesting_schedule, vesting_data);
+    await assert.rejects(claim(
+      null,
       provider.wallet.publicKey,
       vesting_schedule,
       vesting_data,
       user2.publicKey,
       user_ata2,
-    );
+    ));
 
     let token_amount_account2 = await getTokenAccount(provider, user_ata2);
     assert.ok(token_amount_account2.amount.eq(new anchor.BN(0)));
@@ -673,6 +686,7 @@ describe('VestingSchedule', () => {
       12,
       1_000_000_000,
       vesting_schedule,
+      vesting_data,
     );
 
     const user_ata1 = await getUserAta(user1.secretKey, provider, mint_hbb);
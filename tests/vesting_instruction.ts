@@ -9,6 +9,9 @@ import { sleep } from '@project-serum/common';
 
 const program = anchor.workspace.VestingSchedule as Program<VestingSchedule>;
 
+// Discriminator and header, 300 grants of 144 bytes and the used slot bitmap behind them
+export const VESTING_SCHEDULE_SPACE = 8 + 24 + 300 * 144 + 5 * 8;
+
 export type InitializeParameterInterface = {
     vesting_schedule: PublicKey,
     vesting_data: Keypair,
@@ -22,7 +25,7 @@ export async function getInitilizeParameter(provider:anchor.Provider) : Promise<
         await serumCmn.createAccountRentExempt(
             provider,
             program.programId,
-            program.account.vestingSchedule.size
+            VESTING_SCHEDULE_SPACE
         )
     ).publicKey;
 
@@ -63,7 +66,7 @@ export async function initialize(
 }
 
 export async function claim(
-    index: number,
+    index: number | null,
     admin: PublicKey,
     vesting_schedule: PublicKey,
     vesting_data: Keypair,
@@ -74,7 +77,7 @@ export async function claim(
     const auth = await program.account.vestingData.fetch(vesting_data.publicKey);
 
     await program.rpc.claim(
-      new anchor.BN(index), {
+      index === null ? null : new anchor.BN(index), {
       accounts: {
         owner: admin,
         vestingSchedule: vesting_schedule,
@@ -85,6 +88,8 @@ export async function claim(
         vestingVaultAuthority: auth.vestingVaultAuthority,
         tokenProgram: TokenInstructions.TOKEN_PROGRAM_ID,
         systemProgram: anchor.web3.SystemProgram.programId,
+        // none of these grants has a receipt
+        receiptAccount: claim_account,
       },
     });
 }
@@ -103,7 +108,11 @@ export async function addUser(
     unlock_period: number,
     planned_tokens: number,
     vesting_schedule: PublicKey,
+    vesting_data: Keypair,
 ) {
+    // the provider's wallet initialized the schedule, so it signs as the admin
+    const auth = await program.account.vestingData.fetch(vesting_data.publicKey);
+
     await program.rpc.addUser(
         new anchor.BN(unlock_percent),
         user_pubkey,
@@ -111,6 +120,8 @@ export async function addUser(
         new anchor.BN(planned_tokens), 
         {
             accounts: {
+                admin: auth.admin,
+                vestingData: vesting_data.publicKey,
                 vestingSchedule: vesting_schedule,
                 systemProgram: anchor.web3.SystemProgram.programId,
             },
@@ -119,13 +130,19 @@ export async function addUser(
 }
 
 export async function removeUser(
-    index: number,
+    beneficiary: PublicKey,
     vesting_schedule: PublicKey,
+    vesting_data: Keypair,
 ) {
+    const auth = await program.account.vestingData.fetch(vesting_data.publicKey);
+
     await program.rpc.removeUser(
-        new anchor.BN(index),
+        beneficiary,
+        null,
         {
             accounts: {
+                admin: auth.admin,
+                vestingData: vesting_data.publicKey,
                 vestingSchedule: vesting_schedule,
                 systemProgram: anchor.web3.SystemProgram.programId,
             },